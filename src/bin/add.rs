@@ -0,0 +1,78 @@
+use cargo::ops;
+use cargo::core::MultiShell;
+use cargo::core::dependency::Kind;
+use cargo::util::{CliResult, CliError, human};
+use cargo::util::important_paths::{find_root_manifest_for_cwd};
+
+#[derive(RustcDecodable)]
+struct Options {
+    flag_dev: bool,
+    flag_build: bool,
+    flag_features: Vec<String>,
+    flag_optional: bool,
+    flag_git: Option<String>,
+    flag_path: Option<String>,
+    flag_manifest_path: Option<String>,
+    flag_verbose: bool,
+    arg_crate: String,
+}
+
+pub const USAGE: &'static str = "
+Add a dependency to a Cargo.toml manifest file
+
+Usage:
+    cargo add [options] <crate>
+
+Options:
+    -h, --help              Print this message
+    --dev                   Add the crate as a development dependency
+    --build                 Add the crate as a build dependency
+    --features FEATURES     Space-separated list of features of the crate to
+                            activate
+    --optional              Add the crate as an optional dependency
+    --git URL               Git repository to fetch the crate from
+    --path PATH             Filesystem path to the crate
+    --manifest-path PATH    Path to the manifest to add the dependency to
+    -v, --verbose           Use verbose output
+
+The <crate> argument is the name of the crate, optionally followed by `@` and a
+version requirement, for example `cargo add time@0.1`. If no requirement is
+given and the crate comes from the registry, the newest version of the crate
+which hasn't been yanked is used.
+
+The manifest is edited in place, keeping its comments and formatting. A
+dependency on the same crate which is already listed is replaced.
+";
+
+pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
+    shell.set_verbose(options.flag_verbose);
+    let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
+
+    let kind = match (options.flag_dev, options.flag_build) {
+        (false, false) => Kind::Normal,
+        (true, false) => Kind::Development,
+        (false, true) => Kind::Build,
+        (true, true) => {
+            return Err(CliError::from_boxed(human("cannot specify both \
+                                                   --dev and --build"), 101))
+        }
+    };
+    if options.flag_git.is_some() && options.flag_path.is_some() {
+        return Err(CliError::from_boxed(human("cannot specify both --git \
+                                               and --path"), 101))
+    }
+
+    let opts = ops::AddOptions {
+        manifest_path: &root,
+        krate: options.arg_crate.as_slice(),
+        kind: kind,
+        features: options.flag_features.as_slice(),
+        optional: options.flag_optional,
+        git: options.flag_git.as_ref().map(|s| s.as_slice()),
+        path: options.flag_path.as_ref().map(|s| s.as_slice()),
+    };
+
+    ops::add(&opts, shell).map(|_| None).map_err(|err| {
+        CliError::from_boxed(err, 101)
+    })
+}
@@ -2,7 +2,7 @@ use std::io::process::ExitStatus;
 
 use cargo::ops;
 use cargo::core::MultiShell;
-use cargo::util::{CliResult, CliError, Human};
+use cargo::util::{CliResult, CliError, Human, Locked};
 use cargo::util::important_paths::{find_root_manifest_for_cwd};
 
 #[derive(RustcDecodable)]
@@ -15,7 +15,11 @@ struct Options {
     flag_no_default_features: bool,
     flag_target: Option<String>,
     flag_manifest_path: Option<String>,
+    flag_locked: bool,
+    flag_frozen: bool,
+    flag_offline: bool,
     flag_verbose: bool,
+    flag_message_format: ops::MessageFormat,
     arg_args: Vec<String>,
 }
 
@@ -35,6 +39,10 @@ Options:
     --no-default-features    Do not build the `default` feature
     --target TRIPLE          Build for the target triple
     --manifest-path PATH     Path to the manifest to build benchmarks for
+    --locked                 Require Cargo.lock to be up to date
+    --frozen                 Require Cargo.lock and the cache to be up to date
+    --offline                Run without accessing the network
+    --message-format FMT     Output format: human, json [default: human]
     -v, --verbose            Use verbose output
 
 All of the trailing arguments are passed to the benchmark binaries generated
@@ -65,6 +73,10 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
             spec: options.flag_package.as_ref().map(|s| s.as_slice()),
             lib_only: false,
             exec_engine: None,
+            message_format: options.flag_message_format,
+            locked: Locked::from_flags(options.flag_locked,
+                                       options.flag_frozen),
+            offline: options.flag_offline || options.flag_frozen,
         },
     };
 
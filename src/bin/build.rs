@@ -4,7 +4,7 @@ use cargo::core::MultiShell;
 use cargo::ops::CompileOptions;
 use cargo::ops;
 use cargo::util::important_paths::{find_root_manifest_for_cwd};
-use cargo::util::{CliResult, CliError};
+use cargo::util::{CliResult, CliError, Locked};
 
 #[derive(RustcDecodable)]
 struct Options {
@@ -14,7 +14,11 @@ struct Options {
     flag_no_default_features: bool,
     flag_target: Option<String>,
     flag_manifest_path: Option<String>,
+    flag_locked: bool,
+    flag_frozen: bool,
+    flag_offline: bool,
     flag_verbose: bool,
+    flag_message_format: ops::MessageFormat,
     flag_release: bool,
     flag_lib: bool
 }
@@ -35,6 +39,10 @@ Options:
     --no-default-features    Do not build the `default` feature
     --target TRIPLE          Build for the target triple
     --manifest-path PATH     Path to the manifest to compile
+    --locked                 Require Cargo.lock to be up to date
+    --frozen                 Require Cargo.lock and the cache to be up to date
+    --offline                Run without accessing the network
+    --message-format FMT     Output format: human, json [default: human]
     -v, --verbose            Use verbose output
 
 If the --package argument is given, then SPEC is a package id specification
@@ -66,6 +74,10 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
         spec: options.flag_package.as_ref().map(|s| s.as_slice()),
         lib_only: options.flag_lib,
         exec_engine: None,
+        message_format: options.flag_message_format,
+        locked: Locked::from_flags(options.flag_locked,
+                                   options.flag_frozen),
+        offline: options.flag_offline || options.flag_frozen,
     };
 
     ops::compile(&root, &mut opts).map(|_| None).map_err(|err| {
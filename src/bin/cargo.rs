@@ -53,6 +53,7 @@ fn main() {
 }
 
 macro_rules! each_subcommand{ ($mac:ident) => ({
+    $mac!(add);
     $mac!(bench);
     $mac!(build);
     $mac!(clean);
@@ -63,18 +64,25 @@ macro_rules! each_subcommand{ ($mac:ident) => ({
     $mac!(generate_lockfile);
     $mac!(git_checkout);
     $mac!(help);
+    $mac!(install);
     $mac!(locate_project);
     $mac!(login);
+    $mac!(metadata);
     $mac!(new);
+    $mac!(outdated);
     $mac!(owner);
     $mac!(package);
     $mac!(pkgid);
     $mac!(publish);
     $mac!(read_manifest);
+    $mac!(rm);
     $mac!(run);
     $mac!(search);
     $mac!(test);
+    $mac!(tree);
+    $mac!(uninstall);
     $mac!(update);
+    $mac!(vendor);
     $mac!(verify_project);
     $mac!(version);
     $mac!(yank);
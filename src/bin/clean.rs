@@ -2,7 +2,7 @@ use std::os;
 
 use cargo::ops;
 use cargo::core::MultiShell;
-use cargo::util::{CliResult, CliError};
+use cargo::util::{CargoResult, CliResult, CliError, ChainError, human};
 use cargo::util::important_paths::{find_root_manifest_for_cwd};
 
 #[derive(RustcDecodable)]
@@ -11,6 +11,10 @@ struct Options {
     flag_target: Option<String>,
     flag_manifest_path: Option<String>,
     flag_verbose: bool,
+    flag_gc: bool,
+    flag_max_age: Option<u32>,
+    flag_max_size: Option<String>,
+    flag_dry_run: bool,
 }
 
 pub const USAGE: &'static str = "
@@ -18,24 +22,49 @@ Remove artifacts that cargo has generated in the past
 
 Usage:
     cargo clean [options]
+    cargo clean --gc [--max-age DAYS] [--max-size SIZE] [--dry-run] [-v]
 
 Options:
     -h, --help               Print this message
     -p SPEC, --package SPEC  Package to clean artifacts for
     --manifest-path PATH     Path to the manifest to the package to clean
     --target TRIPLE          Target triple to clean output for (default all)
+    --gc                     Clean the caches of the cargo home directory
+    --max-age DAYS           Remove cache entries unused for more than DAYS
+    --max-size SIZE          Remove the least recently used cache entries
+                             until the caches fit in SIZE
+    --dry-run                Only report what would be removed
     -v, --verbose            Use verbose output
 
 If the --package argument is given, then SPEC is a package id specification
 which indicates which package's artifacts should be cleaned out. If it is not
 given, then all packages' artifacts are removed. For more information on SPEC
 and its format, see the `cargo help pkgid` command.
+
+With --gc, the git repositories, downloaded crates and unpacked sources that
+cargo keeps in its home directory are cleaned instead of a project. SIZE is a
+number of bytes, optionally followed by K, M or G. If neither --max-age nor
+--max-size is given, the entries unused for more than 90 days are removed.
 ";
 
+/// How long cache entries may go unused for when no limit is given.
+const DEFAULT_MAX_AGE: u32 = 90;
+
 pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
     shell.set_verbose(options.flag_verbose);
     debug!("executing; cmd=cargo-clean; args={:?}", os::args());
 
+    if options.flag_gc {
+        return gc(&options, shell).map(|_| None).map_err(|err| {
+          CliError::from_boxed(err, 101)
+        })
+    }
+    if options.flag_max_age.is_some() || options.flag_max_size.is_some() ||
+       options.flag_dry_run {
+        return Err(CliError::new("`--max-age`, `--max-size` and `--dry-run` \
+                                  can only be used with `--gc`", 1))
+    }
+
     let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
     let mut opts = ops::CleanOptions {
         shell: shell,
@@ -46,3 +75,40 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
       CliError::from_boxed(err, 101)
     })
 }
+
+fn gc(options: &Options, shell: &mut MultiShell) -> CargoResult<()> {
+    let max_size = match options.flag_max_size {
+        Some(ref size) => Some(try!(parse_size(size.as_slice()))),
+        None => None,
+    };
+    let max_age = match (options.flag_max_age, max_size) {
+        (None, None) => Some(DEFAULT_MAX_AGE),
+        (max_age, _) => max_age,
+    };
+    let mut opts = ops::GcOptions {
+        max_age: max_age,
+        max_size: max_size,
+        dry_run: options.flag_dry_run,
+        shell: shell,
+    };
+    ops::gc(&mut opts)
+}
+
+/// Parses a size such as `512`, `100K`, `20M` or `2G` into a number of bytes.
+fn parse_size(size: &str) -> CargoResult<u64> {
+    let size = size.trim();
+    let (digits, multiplier) = match size.chars().last() {
+        Some('K') | Some('k') => (size.slice_to(size.len() - 1), 1 << 10),
+        Some('M') | Some('m') => (size.slice_to(size.len() - 1), 1 << 20),
+        Some('G') | Some('g') => (size.slice_to(size.len() - 1), 1 << 30),
+        _ => (size, 1),
+    };
+    match digits.parse::<u64>() {
+        Some(n) => n.checked_mul(multiplier).chain_error(|| {
+            human(format!("invalid size `{}`, it is too large", size))
+        }),
+        None => Err(human(format!("invalid size `{}`, expected a number of \
+                                   bytes optionally followed by K, M or G",
+                                  size))),
+    }
+}
@@ -1,6 +1,6 @@
 use cargo::ops;
 use cargo::core::{MultiShell};
-use cargo::util::{CliResult, CliError};
+use cargo::util::{CliResult, CliError, Locked};
 use cargo::util::important_paths::{find_root_manifest_for_cwd};
 
 #[derive(RustcDecodable)]
@@ -8,10 +8,14 @@ struct Options {
     flag_features: Vec<String>,
     flag_jobs: Option<u32>,
     flag_manifest_path: Option<String>,
+    flag_locked: bool,
+    flag_frozen: bool,
+    flag_offline: bool,
     flag_no_default_features: bool,
     flag_no_deps: bool,
     flag_open: bool,
     flag_verbose: bool,
+    flag_message_format: ops::MessageFormat,
     flag_package: Option<String>,
 }
 
@@ -30,6 +34,10 @@ Options:
     --features FEATURES      Space-separated list of features to also build
     --no-default-features    Do not build the `default` feature
     --manifest-path PATH     Path to the manifest to document
+    --locked                 Require Cargo.lock to be up to date
+    --frozen                 Require Cargo.lock and the cache to be up to date
+    --offline                Run without accessing the network
+    --message-format FMT     Output format: human, json [default: human]
     -v, --verbose            Use verbose output
 
 By default the documentation for the local package and all dependencies is
@@ -60,6 +68,10 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
             spec: options.flag_package.as_ref().map(|s| s.as_slice()),
             lib_only: false,
             exec_engine: None,
+            message_format: options.flag_message_format,
+            locked: Locked::from_flags(options.flag_locked,
+                                       options.flag_frozen),
+            offline: options.flag_offline || options.flag_frozen,
         },
     };
 
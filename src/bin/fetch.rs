@@ -1,11 +1,14 @@
 use cargo::ops;
 use cargo::core::{MultiShell};
-use cargo::util::{CliResult, CliError};
+use cargo::util::{CliResult, CliError, Locked};
 use cargo::util::important_paths::find_root_manifest_for_cwd;
 
 #[derive(RustcDecodable)]
 struct Options {
     flag_manifest_path: Option<String>,
+    flag_locked: bool,
+    flag_frozen: bool,
+    flag_offline: bool,
     flag_verbose: bool,
 }
 
@@ -18,6 +21,9 @@ Usage:
 Options:
     -h, --help              Print this message
     --manifest-path PATH    Path to the manifest to fetch dependencies for
+    --locked                Require Cargo.lock to be up to date
+    --frozen                Require Cargo.lock and the cache to be up to date
+    --offline               Run without accessing the network
     -v, --verbose           Use verbose output
 
 If a lockfile is available, this command will ensure that all of the git
@@ -33,7 +39,9 @@ all updated.
 pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
     shell.set_verbose(options.flag_verbose);
     let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
-    try!(ops::fetch(&root, shell).map_err(|e| {
+    let locked = Locked::from_flags(options.flag_locked, options.flag_frozen);
+    let offline = options.flag_offline || options.flag_frozen;
+    try!(ops::fetch(&root, locked, offline, shell).map_err(|e| {
         CliError::from_boxed(e, 101)
     }));
     Ok(None)
@@ -2,12 +2,17 @@ use std::os;
 
 use cargo::ops;
 use cargo::core::MultiShell;
-use cargo::util::{CliResult, CliError};
+use cargo::core::resolver::Strategy;
+use cargo::util::{CliResult, CliError, Locked};
 use cargo::util::important_paths::find_root_manifest_for_cwd;
 
 #[derive(RustcDecodable)]
 struct Options {
     flag_manifest_path: Option<String>,
+    flag_minimal_versions: bool,
+    flag_locked: bool,
+    flag_frozen: bool,
+    flag_offline: bool,
     flag_verbose: bool,
 }
 
@@ -20,6 +25,13 @@ Usage:
 Options:
     -h, --help              Print this message
     --manifest-path PATH    Path to the manifest to generate a lockfile for
+    --minimal-versions      Pick the oldest versions allowed instead of the
+                            newest ones
+    --locked                Require Cargo.lock to be up to date
+    --frozen                Require Cargo.lock to be up to date, without
+                            accessing the network
+    --offline               Resolve with the index and git repositories as
+                            they were last fetched
     -v, --verbose           Use verbose output
 ";
 
@@ -28,6 +40,13 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
     shell.set_verbose(options.flag_verbose);
     let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
 
-    ops::generate_lockfile(&root, shell)
+    let strategy = if options.flag_minimal_versions {
+        Strategy::Oldest
+    } else {
+        Strategy::Newest
+    };
+    let locked = Locked::from_flags(options.flag_locked, options.flag_frozen);
+    let offline = options.flag_offline || options.flag_frozen;
+    ops::generate_lockfile(&root, strategy, locked, offline, shell)
         .map(|_| None).map_err(|err| CliError::from_boxed(err, 101))
 }
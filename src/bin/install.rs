@@ -0,0 +1,128 @@
+use std::os;
+
+use cargo::ops;
+use cargo::core::{MultiShell, SourceId, GitReference};
+use cargo::util::{CliResult, CliError, Locked, ToUrl, human};
+
+#[derive(RustcDecodable)]
+struct Options {
+    flag_jobs: Option<u32>,
+    flag_features: Vec<String>,
+    flag_no_default_features: bool,
+    flag_verbose: bool,
+    flag_root: Option<String>,
+    flag_vers: Option<String>,
+    flag_git: Option<String>,
+    flag_branch: Option<String>,
+    flag_tag: Option<String>,
+    flag_rev: Option<String>,
+    flag_path: Option<String>,
+    flag_locked: bool,
+    flag_frozen: bool,
+    flag_offline: bool,
+    arg_crate: Option<String>,
+}
+
+pub const USAGE: &'static str = "
+Install a Rust binary
+
+Usage:
+    cargo install [options] [<crate>]
+
+Specifying what crate to install:
+    --vers VERS               Specify a version to install from crates.io
+    --git URL                 Git URL to install the specified crate from
+    --branch BRANCH           Branch to use when installing from git
+    --tag TAG                 Tag to use when installing from git
+    --rev SHA                 Specific commit to use when installing from git
+    --path PATH               Filesystem path to local crate to install
+
+Build and install options:
+    -h, --help                Print this message
+    -j N, --jobs N            The number of jobs to run in parallel
+    --features FEATURES       Space-separated list of features to activate
+    --no-default-features     Do not build the `default` feature
+    --root DIR                Directory to install packages into
+    --locked                  Require Cargo.lock to be up to date
+    --frozen                  Require Cargo.lock and the cache to be up to date
+    --offline                 Run without accessing the network
+    -v, --verbose             Use verbose output
+
+This command manages Cargo's local set of installed binary crates. Only packages
+which have [[bin]] targets can be installed, and all binaries are installed into
+the installation root's `bin` folder. The installation root is determined, in
+order of precedence, by `--root`, `$CARGO_INSTALL_ROOT`, the `install.root`
+configuration key, and finally the home directory (which is either
+`$CARGO_HOME` if set or `$HOME/.cargo` by default).
+
+There are multiple sources from which a crate can be installed. The default
+location is crates.io but the `--git` and `--path` flags can change this source.
+If the source contains more than one package (such as crates.io or a git
+repository with multiple crates) the `<crate>` argument is required to indicate
+which crate should be installed. When installing from a local path the
+`<crate>` argument may be omitted to install the package at that path.
+
+Crates from crates.io can optionally specify the version they wish to install
+via the `--vers` flags, and similarly packages from git repositories can
+optionally specify the branch, tag, or revision that should be installed.
+
+The packages installed, and the binaries they provided, are recorded so that
+they can later be removed with `cargo uninstall`.
+";
+
+pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
+    debug!("executing; cmd=cargo-install; args={:?}", os::args());
+    shell.set_verbose(options.flag_verbose);
+
+    let source = if let Some(url) = options.flag_git {
+        let url = try!(url.as_slice().to_url().map_err(|e| {
+            human(format!("The URL `{}` you passed was not a valid URL: {}",
+                          url, e))
+        }).map_err(|e| CliError::from_boxed(e, 101)));
+        let gitref = if let Some(branch) = options.flag_branch {
+            GitReference::Branch(branch)
+        } else if let Some(tag) = options.flag_tag {
+            GitReference::Tag(tag)
+        } else if let Some(rev) = options.flag_rev {
+            GitReference::Rev(rev)
+        } else {
+            GitReference::Branch("master".to_string())
+        };
+        SourceId::for_git(&url, gitref)
+    } else if let Some(path) = options.flag_path {
+        let path = try!(os::getcwd().map_err(|e| {
+            CliError::from_error(e, 101)
+        })).join(path);
+        try!(SourceId::for_path(&path).map_err(|e| {
+            CliError::from_boxed(e, 101)
+        }))
+    } else {
+        try!(SourceId::for_central().map_err(|e| {
+            CliError::from_boxed(e, 101)
+        }))
+    };
+
+    let mut opts = ops::CompileOptions {
+        env: "release",
+        shell: shell,
+        jobs: options.flag_jobs,
+        target: None,
+        dev_deps: false,
+        features: options.flag_features.as_slice(),
+        no_default_features: options.flag_no_default_features,
+        spec: None,
+        lib_only: false,
+        exec_engine: None,
+        message_format: ops::MessageFormat::Human,
+        locked: Locked::from_flags(options.flag_locked, options.flag_frozen),
+        offline: options.flag_offline || options.flag_frozen,
+    };
+
+    let root = options.flag_root.as_ref().map(|s| s.as_slice());
+    let krate = options.arg_crate.as_ref().map(|s| s.as_slice());
+    let vers = options.flag_vers.as_ref().map(|s| s.as_slice());
+    try!(ops::install(root, krate, &source, vers, &mut opts).map_err(|e| {
+        CliError::from_boxed(e, 101)
+    }));
+    Ok(None)
+}
@@ -8,6 +8,7 @@ use cargo::util::{CliResult, CliError, Config};
 #[derive(RustcDecodable)]
 struct Options {
     flag_host: Option<String>,
+    flag_registry: Option<String>,
     arg_token: Option<String>,
     flag_verbose: bool,
 }
@@ -21,6 +22,7 @@ Usage:
 Options:
     -h, --help              Print this message
     --host HOST             Host to set the token for
+    --registry REGISTRY     Name of the registry to set the token for
     -v, --verbose           Use verbose output
 
 ";
@@ -32,7 +34,10 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
         None => {
             let err = (|:| {
                 let config = try!(Config::new(shell, None, None));
-                let src = try!(SourceId::for_central());
+                let src = match options.flag_registry {
+                    Some(ref name) => try!(SourceId::alt_registry(name.as_slice())),
+                    None => try!(SourceId::for_central()),
+                };
                 let mut src = RegistrySource::new(&src, &config);
                 try!(src.update());
                 let config = try!(src.config());
@@ -48,7 +53,8 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
     };
 
     let token = token.as_slice().trim().to_string();
-    try!(ops::registry_login(shell, token).map_err(|e| {
+    try!(ops::registry_login(shell, token,
+                             options.flag_registry.clone()).map_err(|e| {
         CliError::from_boxed(e, 101)
     }));
     Ok(None)
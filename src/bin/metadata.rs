@@ -0,0 +1,63 @@
+use cargo::core::MultiShell;
+use cargo::ops::{self, OutputMetadataOptions, ExportInfo};
+use cargo::util::important_paths::find_root_manifest_for_cwd;
+use cargo::util::{CliResult, CliError, Locked};
+
+#[derive(RustcDecodable)]
+struct Options {
+    flag_features: Vec<String>,
+    flag_no_default_features: bool,
+    flag_target: Option<String>,
+    flag_format_version: u32,
+    flag_manifest_path: Option<String>,
+    flag_locked: bool,
+    flag_frozen: bool,
+    flag_offline: bool,
+    flag_verbose: bool,
+}
+
+pub const USAGE: &'static str = "
+Output the resolved dependencies of a project, the concrete used versions
+including overrides, in machine-readable format
+
+Usage:
+    cargo metadata [options]
+
+Options:
+    -h, --help                 Print this message
+    --features FEATURES        Space-separated list of features
+    --no-default-features      Do not include the `default` feature
+    --target TRIPLE            Only include dependencies used on the target triple
+    --format-version VERSION   Format version [default: 1]
+                               Valid values: 1
+    --manifest-path PATH       Path to the manifest
+    --locked                   Require Cargo.lock to be up to date
+    --frozen                   Require Cargo.lock and the cache to be up to date
+    --offline                  Run without accessing the network
+    -v, --verbose              Use verbose output
+
+The output is a single JSON object listing every package of the dependency
+graph along with the resolved graph itself. The `version` field of the object
+is only increased when the format changes in a backwards incompatible way.
+";
+
+pub fn execute(options: Options,
+               shell: &mut MultiShell) -> CliResult<Option<ExportInfo>> {
+    shell.set_verbose(options.flag_verbose);
+    let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
+
+    let opts = OutputMetadataOptions {
+        manifest_path: &root,
+        features: options.flag_features.as_slice(),
+        no_default_features: options.flag_no_default_features,
+        target: options.flag_target.as_ref().map(|t| t.as_slice()),
+        version: options.flag_format_version,
+        locked: Locked::from_flags(options.flag_locked,
+                                   options.flag_frozen),
+        offline: options.flag_offline || options.flag_frozen,
+    };
+
+    ops::output_metadata(opts, shell).map(Some).map_err(|err| {
+        CliError::from_boxed(err, 101)
+    })
+}
@@ -0,0 +1,73 @@
+use cargo::ops::{self, OutdatedReport};
+use cargo::core::MultiShell;
+use cargo::util::{CliResult, CliError, Locked, human};
+use cargo::util::important_paths::{find_root_manifest_for_cwd};
+
+#[derive(RustcDecodable)]
+struct Options {
+    flag_format: String,
+    flag_manifest_path: Option<String>,
+    flag_locked: bool,
+    flag_frozen: bool,
+    flag_offline: bool,
+    flag_verbose: bool,
+}
+
+pub const USAGE: &'static str = "
+Display the dependencies for which newer versions are available
+
+Usage:
+    cargo outdated [options]
+
+Options:
+    -h, --help              Print this message
+    --format FORMAT         Output format [default: human]
+                            Valid values: human, json
+    --manifest-path PATH    Path to the manifest of the package
+    --locked                Require Cargo.lock to be up to date
+    --frozen                Require Cargo.lock and the cache to be up to date
+    --offline               Run without accessing the network
+    -v, --verbose           Use verbose output
+
+Each locked package coming from a registry or a git repository is compared
+with what its source currently contains. A line is printed for each package
+with a newer version, along with the newest version which the requirements of
+the packages depending on it allow (Compat) and the newest version overall
+(Latest). A version which isn't newer than the locked one is shown as `---`.
+
+The version of a package from a git repository includes the revision it is
+locked to, so such a package is outdated when its branch has moved.
+";
+
+pub fn execute(options: Options,
+               shell: &mut MultiShell) -> CliResult<Option<OutdatedReport>> {
+    shell.set_verbose(options.flag_verbose);
+    let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
+
+    let json = match options.flag_format.as_slice() {
+        "human" => false,
+        "json" => true,
+        f => return Err(CliError::from_boxed(human(format!("unknown output \
+                                                            format `{}`", f)),
+                                             101)),
+    };
+
+    // Only the JSON report may go to stdout, so that it can be parsed.
+    if json {
+        shell.set_status_to_err(true);
+    }
+
+    let opts = ops::OutdatedOptions {
+        manifest_path: &root,
+        locked: Locked::from_flags(options.flag_locked,
+                                   options.flag_frozen),
+        offline: options.flag_offline || options.flag_frozen,
+    };
+    let report = try!(ops::outdated(&opts, shell).map_err(|err| {
+        CliError::from_boxed(err, 101)
+    }));
+    if json { return Ok(Some(report)) }
+    ops::print_outdated(&report, shell).map(|_| None).map_err(|err| {
+        CliError::from_boxed(err, 101)
+    })
+}
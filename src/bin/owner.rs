@@ -9,6 +9,7 @@ struct Options {
     flag_add: Option<Vec<String>>,
     flag_remove: Option<Vec<String>>,
     flag_index: Option<String>,
+    flag_registry: Option<String>,
     flag_verbose: bool,
     flag_list: bool,
 }
@@ -25,6 +26,7 @@ Options:
     -r, --remove LOGIN      Login of a user to remove as an owner
     -l, --list              List owners of a crate
     --index INDEX           Registry index to modify owners for
+    --registry REGISTRY     Name of the registry to modify owners for
     --token TOKEN           API token to use when authenticating
     -v, --verbose           Use verbose output
 
@@ -39,6 +41,7 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
         krate: options.arg_crate,
         token: options.flag_token,
         index: options.flag_index,
+        registry: options.flag_registry,
         to_add: options.flag_add,
         to_remove: options.flag_remove,
         list: options.flag_list,
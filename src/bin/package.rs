@@ -1,6 +1,6 @@
 use cargo::ops;
 use cargo::core::{MultiShell};
-use cargo::util::{CliResult, CliError};
+use cargo::util::{CliResult, CliError, Locked};
 use cargo::util::important_paths::find_root_manifest_for_cwd;
 
 #[derive(RustcDecodable)]
@@ -10,6 +10,9 @@ struct Options {
     flag_no_verify: bool,
     flag_no_metadata: bool,
     flag_list: bool,
+    flag_locked: bool,
+    flag_frozen: bool,
+    flag_offline: bool,
 }
 
 pub const USAGE: &'static str = "
@@ -24,6 +27,9 @@ Options:
     --no-verify             Don't verify the contents by building them
     --no-metadata           Ignore warnings about a lack of human-usable metadata
     --manifest-path PATH    Path to the manifest to compile
+    --locked                Require Cargo.lock to be up to date
+    --frozen                Require Cargo.lock and the cache to be up to date
+    --offline               Run without accessing the network
     -v, --verbose           Use verbose output
 
 ";
@@ -31,10 +37,13 @@ Options:
 pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
     shell.set_verbose(options.flag_verbose);
     let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
+    let locked = Locked::from_flags(options.flag_locked, options.flag_frozen);
+    let offline = options.flag_offline || options.flag_frozen;
     ops::package(&root, shell,
                  !options.flag_no_verify,
                  options.flag_list,
-                 !options.flag_no_metadata).map(|_| None).map_err(|err| {
+                 !options.flag_no_metadata,
+                 locked, offline, None).map(|_| None).map_err(|err| {
         CliError::from_boxed(err, 101)
     })
 }
@@ -1,15 +1,19 @@
 use cargo::ops;
 use cargo::core::{MultiShell};
-use cargo::util::{CliResult, CliError};
+use cargo::util::{CliResult, CliError, Locked};
 use cargo::util::important_paths::find_root_manifest_for_cwd;
 
 #[derive(RustcDecodable)]
 struct Options {
     flag_host: Option<String>,
     flag_token: Option<String>,
+    flag_registry: Option<String>,
     flag_manifest_path: Option<String>,
     flag_verbose: bool,
     flag_no_verify: bool,
+    flag_locked: bool,
+    flag_frozen: bool,
+    flag_offline: bool,
 }
 
 pub const USAGE: &'static str = "
@@ -22,8 +26,12 @@ Options:
     -h, --help              Print this message
     --host HOST             Host to upload the package to
     --token TOKEN           Token to use when uploading
+    --registry REGISTRY     Name of the registry to upload to
     --no-verify             Don't verify package tarball before publish
     --manifest-path PATH    Path to the manifest to compile
+    --locked                Require Cargo.lock to be up to date
+    --frozen                Require Cargo.lock and the cache to be up to date
+    --offline               Verify the package without accessing the network
     -v, --verbose           Use verbose output
 
 ";
@@ -33,13 +41,19 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
     let Options {
         flag_token: token,
         flag_host: host,
+        flag_registry: registry,
         flag_manifest_path,
         flag_no_verify: no_verify,
+        flag_locked,
+        flag_frozen,
+        flag_offline,
         ..
     } = options;
 
     let root = try!(find_root_manifest_for_cwd(flag_manifest_path.clone()));
-    ops::publish(&root, shell, token, host, !no_verify).map(|_| None).map_err(|err| {
+    let locked = Locked::from_flags(flag_locked, flag_frozen);
+    ops::publish(&root, shell, token, host, registry, !no_verify, locked,
+                 flag_offline || flag_frozen).map(|_| None).map_err(|err| {
         CliError::from_boxed(err, 101)
     })
 }
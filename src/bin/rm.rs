@@ -0,0 +1,49 @@
+use cargo::ops;
+use cargo::core::MultiShell;
+use cargo::core::dependency::Kind;
+use cargo::util::{CliResult, CliError, human};
+use cargo::util::important_paths::{find_root_manifest_for_cwd};
+
+#[derive(RustcDecodable)]
+struct Options {
+    flag_dev: bool,
+    flag_build: bool,
+    flag_manifest_path: Option<String>,
+    flag_verbose: bool,
+    arg_crate: String,
+}
+
+pub const USAGE: &'static str = "
+Remove a dependency from a Cargo.toml manifest file
+
+Usage:
+    cargo rm [options] <crate>
+
+Options:
+    -h, --help              Print this message
+    --dev                   Remove the crate from the development dependencies
+    --build                 Remove the crate from the build dependencies
+    --manifest-path PATH    Path to the manifest to remove the dependency from
+    -v, --verbose           Use verbose output
+
+The manifest is edited in place, keeping its comments and formatting.
+";
+
+pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
+    shell.set_verbose(options.flag_verbose);
+    let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
+
+    let kind = match (options.flag_dev, options.flag_build) {
+        (false, false) => Kind::Normal,
+        (true, false) => Kind::Development,
+        (false, true) => Kind::Build,
+        (true, true) => {
+            return Err(CliError::from_boxed(human("cannot specify both \
+                                                   --dev and --build"), 101))
+        }
+    };
+
+    ops::remove(&root, options.arg_crate.as_slice(), kind, shell).map(|_| {
+        None
+    }).map_err(|err| CliError::from_boxed(err, 101))
+}
@@ -3,7 +3,7 @@ use std::io::process::ExitStatus;
 use cargo::ops;
 use cargo::core::{MultiShell};
 use cargo::core::manifest::TargetKind;
-use cargo::util::{CliResult, CliError, human};
+use cargo::util::{CliResult, CliError, Locked, human};
 use cargo::util::important_paths::{find_root_manifest_for_cwd};
 
 #[derive(RustcDecodable)]
@@ -15,7 +15,11 @@ struct Options {
     flag_no_default_features: bool,
     flag_target: Option<String>,
     flag_manifest_path: Option<String>,
+    flag_locked: bool,
+    flag_frozen: bool,
+    flag_offline: bool,
     flag_verbose: bool,
+    flag_message_format: ops::MessageFormat,
     flag_release: bool,
     arg_args: Vec<String>,
 }
@@ -36,6 +40,10 @@ Options:
     --no-default-features   Do not build the `default` feature
     --target TRIPLE         Build for the target triple
     --manifest-path PATH    Path to the manifest to execute
+    --locked                Require Cargo.lock to be up to date
+    --frozen                Require Cargo.lock and the cache to be up to date
+    --offline               Run without accessing the network
+    --message-format FMT    Output format: human, json [default: human]
     -v, --verbose           Use verbose output
 
 If neither `--bin` or `--example` are given, then if the project only has one
@@ -67,6 +75,10 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
         spec: None,
         lib_only: false,
         exec_engine: None,
+        message_format: options.flag_message_format,
+        locked: Locked::from_flags(options.flag_locked,
+                                   options.flag_frozen),
+        offline: options.flag_offline || options.flag_frozen,
     };
 
     let (target_kind, name) = match (options.flag_bin, options.flag_example) {
@@ -5,6 +5,7 @@ use cargo::util::{CliResult, CliError};
 #[derive(RustcDecodable)]
 struct Options {
     flag_host: Option<String>,
+    flag_registry: Option<String>,
     flag_verbose: bool,
     arg_query: String
 }
@@ -18,6 +19,7 @@ Usage:
 Options:
     -h, --help              Print this message
     --host HOST             Host of a registry to search in
+    --registry REGISTRY     Name of the registry to search in
     -v, --verbose           Use verbose output
 ";
 
@@ -25,11 +27,12 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
     shell.set_verbose(options.flag_verbose);
     let Options {
         flag_host: host,
+        flag_registry: registry,
         arg_query: query,
         ..
     } = options;
 
-    ops::search(query.as_slice(), shell, host)
+    ops::search(query.as_slice(), shell, host, registry)
         .map(|_| None)
         .map_err(|err| CliError::from_boxed(err, 101))
 }
@@ -2,7 +2,7 @@ use std::io::process::ExitStatus;
 
 use cargo::ops;
 use cargo::core::MultiShell;
-use cargo::util::{CliResult, CliError, Human};
+use cargo::util::{CliResult, CliError, Human, Locked};
 use cargo::util::important_paths::{find_root_manifest_for_cwd};
 
 #[derive(RustcDecodable)]
@@ -11,12 +11,16 @@ struct Options {
     flag_features: Vec<String>,
     flag_jobs: Option<u32>,
     flag_manifest_path: Option<String>,
+    flag_locked: bool,
+    flag_frozen: bool,
+    flag_offline: bool,
     flag_test: Option<String>,
     flag_no_default_features: bool,
     flag_no_run: bool,
     flag_package: Option<String>,
     flag_target: Option<String>,
     flag_verbose: bool,
+    flag_message_format: ops::MessageFormat,
 }
 
 pub const USAGE: &'static str = "
@@ -35,6 +39,10 @@ Options:
     --no-default-features    Do not build the `default` feature
     --target TRIPLE          Build for the target triple
     --manifest-path PATH     Path to the manifest to build tests for
+    --locked                 Require Cargo.lock to be up to date
+    --frozen                 Require Cargo.lock and the cache to be up to date
+    --offline                Run without accessing the network
+    --message-format FMT     Output format: human, json [default: human]
     -v, --verbose            Use verbose output
 
 All of the trailing arguments are passed to the test binaries generated for
@@ -67,6 +75,10 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
             spec: options.flag_package.as_ref().map(|s| s.as_slice()),
             lib_only: false,
             exec_engine: None,
+            message_format: options.flag_message_format,
+            locked: Locked::from_flags(options.flag_locked,
+                                       options.flag_frozen),
+            offline: options.flag_offline || options.flag_frozen,
         },
     };
 
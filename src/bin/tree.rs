@@ -0,0 +1,59 @@
+use cargo::ops;
+use cargo::core::MultiShell;
+use cargo::util::{CliResult, CliError, Locked};
+use cargo::util::important_paths::{find_root_manifest_for_cwd};
+
+#[derive(RustcDecodable)]
+struct Options {
+    flag_invert: Option<String>,
+    flag_duplicates: bool,
+    flag_manifest_path: Option<String>,
+    flag_locked: bool,
+    flag_frozen: bool,
+    flag_offline: bool,
+    flag_verbose: bool,
+}
+
+pub const USAGE: &'static str = "
+Display a tree visualization of the dependency graph
+
+Usage:
+    cargo tree [options]
+
+Options:
+    -h, --help              Print this message
+    -i SPEC, --invert SPEC  Invert the tree for the package matching SPEC
+    -d, --duplicates        Show only packages of which multiple versions are
+                            in the graph, along with their dependents
+    --manifest-path PATH    Path to the manifest of the package
+    --locked                Require Cargo.lock to be up to date
+    --frozen                Require Cargo.lock and the cache to be up to date
+    --offline               Run without accessing the network
+    -v, --verbose           Use verbose output
+
+Each package is listed along with its dependencies. Dependencies which are not
+normal dependencies are annotated with their kind (dev or build). A package
+which was already listed along with its dependencies is marked with `(*)`.
+
+With --invert, the packages which depend on the package matching SPEC are
+listed instead. For more information on SPEC and its format, see the
+`cargo help pkgid` command.
+";
+
+pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
+    shell.set_verbose(options.flag_verbose);
+    let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
+
+    let opts = ops::TreeOptions {
+        manifest_path: &root,
+        invert: options.flag_invert.as_ref().map(|s| s.as_slice()),
+        duplicates: options.flag_duplicates,
+        locked: Locked::from_flags(options.flag_locked,
+                                   options.flag_frozen),
+        offline: options.flag_offline || options.flag_frozen,
+    };
+
+    ops::tree(&opts, shell).map(|_| None).map_err(|err| {
+        CliError::from_boxed(err, 101)
+    })
+}
@@ -0,0 +1,36 @@
+use cargo::ops;
+use cargo::core::MultiShell;
+use cargo::util::{CliResult, CliError};
+
+#[derive(RustcDecodable)]
+struct Options {
+    flag_root: Option<String>,
+    flag_verbose: bool,
+    arg_spec: String,
+}
+
+pub const USAGE: &'static str = "
+Remove a Rust binary
+
+Usage:
+    cargo uninstall [options] <spec>
+
+Options:
+    -h, --help              Print this message
+    --root DIR              Directory to uninstall packages from
+    -v, --verbose           Use verbose output
+
+The argument SPEC is a package id specification (see `cargo help pkgid`) to
+specify which crate should be uninstalled. All binaries which were installed by
+the crate are removed, and the crate is removed from the list of installed
+crates.
+";
+
+pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
+    shell.set_verbose(options.flag_verbose);
+    let root = options.flag_root.as_ref().map(|s| s.as_slice());
+    try!(ops::uninstall(root, options.arg_spec.as_slice(), shell).map_err(|e| {
+        CliError::from_boxed(e, 101)
+    }));
+    Ok(None)
+}
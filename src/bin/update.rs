@@ -2,7 +2,8 @@ use std::os;
 
 use cargo::ops;
 use cargo::core::MultiShell;
-use cargo::util::{CliResult, CliError};
+use cargo::core::resolver::Strategy;
+use cargo::util::{CliResult, CliError, Locked};
 use cargo::util::important_paths::find_root_manifest_for_cwd;
 
 #[derive(RustcDecodable)]
@@ -11,6 +12,12 @@ struct Options {
     flag_package: Option<String>,
     flag_aggressive: bool,
     flag_precise: Option<String>,
+    flag_minimal_versions: bool,
+    flag_locked: bool,
+    flag_frozen: bool,
+    flag_offline: bool,
+    flag_dry_run: bool,
+    flag_check_yanked: bool,
     flag_manifest_path: Option<String>,
     flag_verbose: bool,
 }
@@ -27,6 +34,15 @@ Options:
     -p SPEC, --package SPEC  Package to update
     --aggressive             Force updating all dependencies of <name> as well
     --precise PRECISE        Update a single dependency to exactly PRECISE
+    --minimal-versions       Pick the oldest versions allowed instead of the
+                             newest ones
+    --locked                 Require Cargo.lock to be up to date
+    --frozen                 Require Cargo.lock to be up to date, without
+                             accessing the network
+    --offline                Resolve with the index and git repositories as
+                             they were last fetched
+    --dry-run                Report the changes without writing the lock file
+    --check-yanked           List the locked packages which have been yanked
     --manifest-path PATH     Path to the manifest to compile
     -v, --verbose            Use verbose output
 
@@ -48,6 +64,13 @@ be updated to.
 If SPEC is not given, then all dependencies will be re-resolved and
 updated.
 
+The packages which are added, removed or updated are reported, and with
+--dry-run nothing else is done. A warning is printed for each package of the
+lock file which has been yanked from its registry. With --aggressive, the
+dependencies of SPEC keep a yanked version only when no other version can be
+used, and --check-yanked lists the yanked packages without updating anything
+else than the registries.
+
 For more information about package id specifications, see `cargo help pkgid`.
 ";
 
@@ -69,10 +92,18 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
         aggressive: options.flag_aggressive,
         precise: options.flag_precise.as_ref().map(|s| s.as_slice()),
         to_update: spec.map(|s| s.as_slice()),
+        strategy: if options.flag_minimal_versions {
+            Strategy::Oldest
+        } else {
+            Strategy::Newest
+        },
+        locked: Locked::from_flags(options.flag_locked, options.flag_frozen),
+        offline: options.flag_offline || options.flag_frozen,
+        dry_run: options.flag_dry_run,
+        check_yanked: options.flag_check_yanked,
         shell: shell,
     };
 
     ops::update_lockfile(&root, &mut update_opts)
         .map(|_| None).map_err(|err| CliError::from_boxed(err, 101))
 }
-
@@ -0,0 +1,51 @@
+use cargo::ops;
+use cargo::core::MultiShell;
+use cargo::util::{CliResult, CliError, Locked};
+use cargo::util::important_paths::find_root_manifest_for_cwd;
+
+#[derive(RustcDecodable)]
+struct Options {
+    flag_manifest_path: Option<String>,
+    flag_locked: bool,
+    flag_frozen: bool,
+    flag_offline: bool,
+    flag_verbose: bool,
+    arg_path: Option<String>,
+}
+
+pub const USAGE: &'static str = "
+Vendor all dependencies of a package into a local directory
+
+Usage:
+    cargo vendor [options] [<path>]
+
+Options:
+    -h, --help              Print this message
+    --manifest-path PATH    Path to the manifest to vendor dependencies for
+    --locked                Require Cargo.lock to be up to date
+    --frozen                Require Cargo.lock and the cache to be up to date
+    --offline               Run without accessing the network
+    -v, --verbose           Use verbose output
+
+This command copies the source of every registry and git dependency of the
+package into <path> (`vendor` by default), along with checksums of all copied
+files. Once done, it prints the `[source]` configuration which should be added
+to `.cargo/config` to build from the vendored copies instead, at which point
+the network is no longer needed to build the package.
+";
+
+pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>> {
+    shell.set_verbose(options.flag_verbose);
+    let root = try!(find_root_manifest_for_cwd(options.flag_manifest_path));
+    let dst = Path::new(options.arg_path.unwrap_or("vendor".to_string()));
+    let locked = Locked::from_flags(options.flag_locked, options.flag_frozen);
+    let offline = options.flag_offline || options.flag_frozen;
+    let config = try!(ops::vendor(&root, &dst, locked, offline,
+                                  shell).map_err(|e| {
+        CliError::from_boxed(e, 101)
+    }));
+    try!(ops::print_vendor_config(config.as_slice(), shell).map_err(|e| {
+        CliError::from_boxed(e, 101)
+    }));
+    Ok(None)
+}
@@ -8,6 +8,7 @@ struct Options {
     flag_token: Option<String>,
     flag_vers: Option<String>,
     flag_index: Option<String>,
+    flag_registry: Option<String>,
     flag_verbose: bool,
     flag_undo: bool,
 }
@@ -23,6 +24,7 @@ Options:
     --vers VERSION          The version to yank or un-yank
     --undo                  Undo a yank, putting a version back into the index
     --index INDEX           Registry index to yank from
+    --registry REGISTRY     Name of the registry to yank from
     --token TOKEN           API token to use when authenticating
     -v, --verbose           Use verbose output
 
@@ -42,6 +44,7 @@ pub fn execute(options: Options, shell: &mut MultiShell) -> CliResult<Option<()>
                    options.flag_vers,
                    options.flag_token,
                    options.flag_index,
+                   options.flag_registry,
                    options.flag_undo).map_err(|e| {
         CliError::from_boxed(e, 101)
     }));
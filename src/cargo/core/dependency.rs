@@ -1,7 +1,10 @@
+use std::fmt;
+
 use semver::VersionReq;
 
 use core::{SourceId, Summary, PackageId};
-use util::CargoResult;
+use util::{CargoResult, ChainError, human};
+use util::cfg::{Cfg, CfgExpr};
 
 /// Informations about a dependency requested by a Cargo manifest.
 #[derive(PartialEq,Clone,Show)]
@@ -19,7 +22,10 @@ pub struct Dependency {
 
     // This dependency should be used only for this platform.
     // `None` means *all platforms*.
-    only_for_platform: Option<String>,
+    only_for_platform: Option<Platform>,
+
+    // Whether this is an edge from a workspace root to one of its members.
+    workspace_member: bool,
 }
 
 #[derive(PartialEq, Clone, Show, Copy)]
@@ -29,6 +35,24 @@ pub enum Kind {
     Build,
 }
 
+/// The platforms a platform-specific dependency applies to, as written in a
+/// `[target.<platform>.dependencies]` section.
+#[derive(PartialEq, Clone, Show)]
+pub enum Platform {
+    /// A single exact target triple, like `x86_64-unknown-linux-gnu`.
+    Name(String),
+    /// All platforms for which a `cfg(...)` expression holds.
+    Cfg(CfgExpr),
+}
+
+/// What is known about a platform that dependencies are built for: its target
+/// triple and the `cfg` values that rustc reports for it.
+#[derive(Clone, Show)]
+pub struct PlatformInfo {
+    triple: String,
+    cfg: Vec<Cfg>,
+}
+
 impl Dependency {
     /// Attempt to create a `Dependency` from an entry in the manifest.
     ///
@@ -69,6 +93,7 @@ impl Dependency {
             default_features: true,
             specified_req: None,
             only_for_platform: None,
+            workspace_member: false,
         }
     }
 
@@ -121,6 +146,14 @@ impl Dependency {
         self
     }
 
+    /// Points this dependency at `replace_with` if it currently points at
+    /// `to_replace`.
+    pub fn map_source(self, to_replace: &SourceId, replace_with: &SourceId)
+                      -> Dependency {
+        if self.source_id != *to_replace { return self }
+        self.source_id(replace_with.clone())
+    }
+
     /// Set the version requirement for this dependency
     pub fn version_req(mut self, req: VersionReq) -> Dependency {
         self.req = req;
@@ -135,11 +168,22 @@ impl Dependency {
             .source_id(id.get_source_id().clone())
     }
 
-    pub fn only_for_platform(mut self, platform: Option<String>) -> Dependency {
+    pub fn only_for_platform(mut self, platform: Option<Platform>) -> Dependency {
         self.only_for_platform = platform;
         self
     }
 
+    /// Sets whether this dependency points from a workspace root to one of the
+    /// members of the workspace.
+    pub fn workspace_member(mut self, member: bool) -> Dependency {
+        self.workspace_member = member;
+        self
+    }
+
+    /// Returns true if this dependency points at a workspace member, in which
+    /// case the member is resolved as if it were the root package.
+    pub fn is_workspace_member(&self) -> bool { self.workspace_member }
+
     /// Returns false if the dependency is only used to build the local package.
     pub fn is_transitive(&self) -> bool {
         match self.kind {
@@ -170,20 +214,61 @@ impl Dependency {
 
     /// If none, this dependencies must be built for all platforms.
     /// If some, it must only be built for the specified platform.
-    pub fn get_only_for_platform(&self) -> Option<&str> {
-        self.only_for_platform.as_ref().map(|s| s.as_slice())
+    pub fn get_only_for_platform(&self) -> Option<&Platform> {
+        self.only_for_platform.as_ref()
     }
 
     /// Returns true if the dependency should be built for this platform.
-    pub fn is_active_for_platform(&self, platform: &str) -> bool {
+    pub fn is_active_for_platform(&self, platform: &PlatformInfo) -> bool {
         match self.only_for_platform {
             None => true,
-            Some(ref p) if p.as_slice() == platform => true,
-            _ => false
+            Some(ref p) => p.matches(platform),
+        }
+    }
+}
+
+impl Platform {
+    /// Parses the key of a `[target.<platform>]` section, which is either a
+    /// target triple or a `cfg(...)` expression.
+    pub fn parse(s: &str) -> CargoResult<Platform> {
+        if s.starts_with("cfg(") && s.ends_with(")") {
+            let expr = s.slice(4, s.len() - 1);
+            CfgExpr::parse(expr).map(Platform::Cfg).chain_error(|| {
+                human(format!("failed to parse `{}` as a cfg expression", s))
+            })
+        } else {
+            Ok(Platform::Name(s.to_string()))
+        }
+    }
+
+    /// Returns true if this specification includes the given platform.
+    pub fn matches(&self, platform: &PlatformInfo) -> bool {
+        match *self {
+            Platform::Name(ref name) => name.as_slice() == platform.get_triple(),
+            Platform::Cfg(ref expr) => expr.matches(platform.get_cfg()),
+        }
+    }
+}
+
+impl fmt::String for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Platform::Name(ref name) => write!(f, "{}", name),
+            Platform::Cfg(ref expr) => write!(f, "cfg({})", expr),
         }
     }
 }
 
+impl PlatformInfo {
+    pub fn new(triple: String, cfg: Vec<Cfg>) -> PlatformInfo {
+        PlatformInfo { triple: triple, cfg: cfg }
+    }
+
+    pub fn get_triple(&self) -> &str { self.triple.as_slice() }
+
+    pub fn get_cfg(&self) -> &[Cfg] { self.cfg.as_slice() }
+}
+
 #[derive(PartialEq,Clone,RustcEncodable)]
 pub struct SerializedDependency {
     name: String,
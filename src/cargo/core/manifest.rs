@@ -21,6 +21,7 @@ pub struct Manifest {
     exclude: Vec<String>,
     include: Vec<String>,
     metadata: ManifestMetadata,
+    workspace_members: Option<Vec<String>>,
 }
 
 /// General metadata about a package which is just blindly uploaded to the
@@ -392,7 +393,8 @@ impl Manifest {
                exclude: Vec<String>,
                include: Vec<String>,
                links: Option<String>,
-               metadata: ManifestMetadata) -> Manifest {
+               metadata: ManifestMetadata,
+               workspace_members: Option<Vec<String>>) -> Manifest {
         Manifest {
             summary: summary,
             targets: targets,
@@ -404,6 +406,7 @@ impl Manifest {
             include: include,
             links: links,
             metadata: metadata,
+            workspace_members: workspace_members,
         }
     }
 
@@ -465,6 +468,12 @@ impl Manifest {
 
     pub fn get_metadata(&self) -> &ManifestMetadata { &self.metadata }
 
+    /// Returns the relative paths of the members listed in this manifest's
+    /// `[workspace]` section, if it has one.
+    pub fn get_workspace_members(&self) -> Option<&[String]> {
+        self.workspace_members.as_ref().map(|m| m.as_slice())
+    }
+
     pub fn set_summary(&mut self, summary: Summary) {
         self.summary = summary;
     }
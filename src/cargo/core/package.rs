@@ -115,6 +115,22 @@ impl Package {
     pub fn has_custom_build(&self) -> bool {
         self.get_targets().iter().any(|t| t.get_profile().is_custom_build())
     }
+
+    /// Returns this package as if it had been loaded from `replace_with`
+    /// instead of `to_replace`.
+    pub fn map_source(self, to_replace: &SourceId, replace_with: &SourceId)
+                      -> Package {
+        let Package { mut manifest, manifest_path, source_id } = self;
+        let summary = manifest.get_summary().clone()
+                              .map_source(to_replace, replace_with);
+        manifest.set_summary(summary);
+        let source_id = if source_id == *to_replace {
+            replace_with.clone()
+        } else {
+            source_id
+        };
+        Package::new(manifest, &manifest_path, &source_id)
+    }
 }
 
 impl fmt::String for Package {
@@ -158,6 +158,17 @@ impl PackageId {
             }),
         }
     }
+
+    /// Returns this package id as it would be if it came from `source`.
+    pub fn with_source_id(&self, source: &SourceId) -> PackageId {
+        PackageId {
+            inner: Arc::new(PackageIdInner {
+                name: self.inner.name.to_string(),
+                version: self.inner.version.clone(),
+                source_id: source.clone(),
+            }),
+        }
+    }
 }
 
 impl Metadata {
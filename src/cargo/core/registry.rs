@@ -11,6 +11,22 @@ use util::{CargoResult, ChainError, Config, human, profile};
 pub trait Registry {
     /// Attempt to find the packages that match a dependency request.
     fn query(&mut self, name: &Dependency) -> CargoResult<Vec<Summary>>;
+
+    /// Find the packages that match a dependency request but which were
+    /// yanked, and so aren't returned by `query`.
+    ///
+    /// This is only used to explain why a dependency couldn't be resolved.
+    fn query_yanked(&mut self, _dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        Ok(Vec::new())
+    }
+
+    /// Returns why `query` may have found nothing for a dependency request
+    /// even though matching packages exist.
+    ///
+    /// This is only used to explain why a dependency couldn't be resolved.
+    fn missing_hint(&mut self, _dep: &Dependency) -> Option<String> {
+        None
+    }
 }
 
 impl Registry for Vec<Summary> {
@@ -62,6 +78,10 @@ pub struct PackageRegistry<'a> {
     source_ids: HashMap<SourceId, (SourceId, Kind)>,
 
     locked: HashMap<SourceId, HashMap<String, Vec<(PackageId, Vec<PackageId>)>>>,
+
+    // Previously locked packages to fall back to for the dependencies which
+    // nothing else matches, see `add_last_resort`.
+    last_resort: Vec<PackageId>,
 }
 
 #[derive(PartialEq, Eq, Copy)]
@@ -79,9 +99,14 @@ impl<'a> PackageRegistry<'a> {
             overrides: vec!(),
             config: config,
             locked: HashMap::new(),
+            last_resort: Vec::new(),
         }
     }
 
+    pub fn config(&self) -> &'a Config<'a> {
+        self.config
+    }
+
     pub fn get(&mut self, package_ids: &[PackageId]) -> CargoResult<Vec<Package>> {
         log!(5, "getting packags; sources={}; ids={:?}", self.sources.len(),
              package_ids);
@@ -105,6 +130,16 @@ impl<'a> PackageRegistry<'a> {
         Ok(ret)
     }
 
+    /// Makes `id` the package returned for the dependencies it matches when
+    /// nothing else does.
+    ///
+    /// Yanked versions are only returned for dependencies locked to them, so
+    /// this keeps a previously locked version which has since been yanked
+    /// when no other version can be used instead.
+    pub fn add_last_resort(&mut self, id: PackageId) {
+        self.last_resort.push(id);
+    }
+
     pub fn move_sources(self) -> SourceMap<'a> {
         self.sources
     }
@@ -281,14 +316,39 @@ impl<'a> Registry for PackageRegistry<'a> {
         let overrides = try!(self.query_overrides(dep));
 
         let ret = if overrides.len() == 0 {
-            // Ensure the requested source_id is loaded
-            try!(self.ensure_loaded(dep.get_source_id()));
+            // Ensure the requested source_id is loaded. When offline, this
+            // fails if it hasn't been fetched before, so mention which
+            // package needed it.
+            let loaded = self.ensure_loaded(dep.get_source_id());
+            if self.config.offline() {
+                try!(loaded.chain_error(|| {
+                    human(format!("unable to get `{}` from {} while offline",
+                                  dep.get_name(), dep.get_source_id()))
+                }));
+            } else {
+                try!(loaded);
+            }
             let mut ret = Vec::new();
             for (id, src) in self.sources.sources_mut() {
                 if id == dep.get_source_id() {
                     ret.extend(try!(src.query(dep)).into_iter());
                 }
             }
+            let last_resort = self.last_resort.iter().find(|id| {
+                dep.matches_id(*id) &&
+                    dep.get_source_id() == id.get_source_id() &&
+                    dep.get_version_req().matches(id.get_version())
+            }).map(|id| dep.clone().lock_to(id));
+            match last_resort {
+                Some(ref locked) if ret.is_empty() => {
+                    for (id, src) in self.sources.sources_mut() {
+                        if id == locked.get_source_id() {
+                            ret.extend(try!(src.query(locked)).into_iter());
+                        }
+                    }
+                }
+                _ => {}
+            }
             ret
         } else {
             overrides
@@ -298,6 +358,26 @@ impl<'a> Registry for PackageRegistry<'a> {
         // relevant summaries to the right versions and sources
         Ok(ret.into_iter().map(|summary| self.lock(summary)).collect())
     }
+
+    fn query_yanked(&mut self, dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        try!(self.ensure_loaded(dep.get_source_id()));
+        let mut ret = Vec::new();
+        for (id, src) in self.sources.sources_mut() {
+            if id == dep.get_source_id() {
+                ret.extend(try!(src.query_yanked(dep)).into_iter());
+            }
+        }
+        Ok(ret)
+    }
+
+    fn missing_hint(&mut self, dep: &Dependency) -> Option<String> {
+        for (id, src) in self.sources.sources_mut() {
+            if id == dep.get_source_id() {
+                return src.missing_hint(dep)
+            }
+        }
+        None
+    }
 }
 
 #[cfg(test)]
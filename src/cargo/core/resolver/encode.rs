@@ -69,11 +69,30 @@ impl EncodableResolve {
             }
         }
 
+        // Checksums are stored in the metadata section with one key per
+        // package, they're split out here so they don't get copied over to
+        // the next lockfile as opaque metadata.
+        let mut checksums = HashMap::new();
+        let mut metadata = self.metadata.clone();
+        if let Some(ref mut metadata) = metadata {
+            for id in g.iter() {
+                let key = checksum_key(id);
+                if let Some(cksum) = metadata.remove(&key) {
+                    checksums.insert(id.clone(), Some(cksum));
+                }
+            }
+        }
+        let metadata = metadata.and_then(|m| {
+            if m.is_empty() { None } else { Some(m) }
+        });
+
         Ok(Resolve {
             graph: g,
             root: try!(self.root.to_package_id(default)),
             features: HashMap::new(),
-            metadata: self.metadata.clone(),
+            host_features: HashMap::new(),
+            checksums: checksums,
+            metadata: metadata,
         })
     }
 }
@@ -154,14 +173,28 @@ impl Encodable for Resolve {
             Some(encodable_resolve_node(id, &self.root, &self.graph))
         }).collect::<Vec<EncodableDependency>>();
 
+        let mut metadata = self.metadata.clone().unwrap_or(BTreeMap::new());
+        for &id in ids.iter() {
+            if let Some(cksum) = self.checksum(id) {
+                metadata.insert(checksum_key(id), cksum.to_string());
+            }
+        }
+
         EncodableResolve {
             package: Some(encodable),
             root: encodable_resolve_node(&self.root, &self.root, &self.graph),
-            metadata: self.metadata.clone(),
+            metadata: if metadata.is_empty() { None } else { Some(metadata) },
         }.encode(s)
     }
 }
 
+/// The key under which the checksum of `id` is recorded in the `[metadata]`
+/// section of a lockfile.
+fn checksum_key(id: &PackageId) -> String {
+    format!("checksum {} {} ({})", id.get_name(), id.get_version(),
+            id.get_source_id().with_precise(None).to_url())
+}
+
 fn encodable_resolve_node(id: &PackageId, root: &PackageId,
                           graph: &Graph<PackageId>) -> EncodableDependency {
     let deps = graph.edges(id).map(|edge| {
@@ -3,12 +3,13 @@ use std::collections::HashSet;
 use std::collections::hash_map::HashMap;
 use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::fmt;
-use std::rc::Rc;
+use std::rc::{self, Rc};
 use semver;
 
 use core::{PackageId, Registry, SourceId, Summary, Dependency};
 use core::PackageIdSpec;
-use util::{CargoResult, Graph, human, ChainError};
+use core::dependency::{Kind, PlatformInfo};
+use util::{CargoResult, CargoError, Graph, human, ChainError};
 use util::profile;
 use util::graph::{Nodes, Edges};
 
@@ -22,28 +23,68 @@ mod encode;
 ///
 /// Each instance of `Resolve` also understands the full set of features used
 /// for each package as well as what the root package is.
+///
+/// Features are resolved separately for the packages compiled for the target
+/// and for those compiled for the host, that is build dependencies and
+/// everything they depend on. A package used in both ways may have different
+/// features enabled in each.
 #[derive(PartialEq, Eq, Clone)]
 pub struct Resolve {
     graph: Graph<PackageId>,
+    /// The features of each package compiled for the target. There is an entry
+    /// for every package needed by the target, even if no feature is enabled.
     features: HashMap<PackageId, HashSet<String>>,
+    /// The features of each package compiled for the host, with an entry for
+    /// every package needed by the host.
+    host_features: HashMap<PackageId, HashSet<String>>,
     root: PackageId,
+    checksums: HashMap<PackageId, Option<String>>,
     metadata: Option<Metadata>,
 }
 
 #[derive(Copy)]
 pub enum Method<'a> {
-    Everything,
+    Everything(Strategy),
     Required(/* dev_deps = */ bool,
              /* features = */ &'a [String],
              /* uses_default_features = */ bool,
-             /* target_platform = */ Option<&'a str>),
+             /* target_platform = */ Option<&'a PlatformInfo>,
+             /* host_platform = */ Option<&'a PlatformInfo>,
+             /* strategy = */ Strategy),
+}
+
+/// Which of the versions matching a dependency requirement the resolver
+/// tries first.
+#[derive(Copy, PartialEq, Show)]
+pub enum Strategy {
+    /// Pick the newest version possible. This is what is normally wanted.
+    Newest,
+    /// Pick the oldest version possible, to check that the lower bounds of
+    /// version requirements are accurate.
+    Oldest,
+}
+
+impl<'a> Method<'a> {
+    pub fn strategy(&self) -> Strategy {
+        match *self {
+            Method::Everything(strategy) => strategy,
+            Method::Required(_, _, _, _, _, strategy) => strategy,
+        }
+    }
 }
 
 impl Resolve {
     fn new(root: PackageId) -> Resolve {
         let mut g = Graph::new();
         g.add(root.clone(), &[]);
-        Resolve { graph: g, root: root, features: HashMap::new(), metadata: None }
+        Resolve {
+            graph: g,
+            root: root,
+            features: HashMap::new(),
+            host_features: HashMap::new(),
+            checksums: HashMap::new(),
+            metadata: None,
+        }
     }
 
     pub fn copy_metadata(&mut self, other: &Resolve) {
@@ -110,9 +151,122 @@ impl Resolve {
         }
     }
 
+    /// Returns the features enabled for `pkg` when it is compiled for the
+    /// target, or `None` if only the host needs it.
     pub fn features(&self, pkg: &PackageId) -> Option<&HashSet<String>> {
         self.features.get(pkg)
     }
+
+    /// Returns the features enabled for `pkg` when it is compiled for the
+    /// host, or `None` if only the target needs it.
+    pub fn host_features(&self, pkg: &PackageId) -> Option<&HashSet<String>> {
+        self.host_features.get(pkg)
+    }
+
+    /// Returns the packages which the host can't share with the target.
+    ///
+    /// These are the packages with different features enabled for the host
+    /// and for the target, which are compiled once for each of them, along
+    /// with the packages needed by the host which depend on one of them.
+    pub fn divergent_packages(&self) -> HashSet<&PackageId> {
+        let mut ret = self.host_features.iter().filter(|&(pkg, host)| {
+            self.features.get(pkg).map_or(false, |target| target != host)
+        }).map(|(pkg, _)| pkg).collect::<HashSet<&PackageId>>();
+
+        loop {
+            let dependents = self.host_features.keys().filter(|pkg| {
+                !ret.contains(pkg) && self.deps(*pkg).map_or(false, |mut deps| {
+                    deps.any(|dep| ret.contains(&dep))
+                })
+            }).collect::<Vec<&PackageId>>();
+            if dependents.is_empty() { break }
+            ret.extend(dependents.into_iter());
+        }
+        ret
+    }
+
+    /// Returns the checksum of the contents of `pkg`, if its source provides
+    /// one.
+    pub fn checksum(&self, pkg: &PackageId) -> Option<&str> {
+        match self.checksums.get(pkg) {
+            Some(&Some(ref s)) => Some(s.as_slice()),
+            _ => None,
+        }
+    }
+
+    /// Returns whether `self` and `other` record the same lock file: the same
+    /// packages, from the same revisions of git repositories, with the same
+    /// dependencies, checksums and metadata. Features aren't part of lock
+    /// files, and so aren't compared.
+    pub fn same_lockfile(&self, other: &Resolve) -> bool {
+        // Source ids compare equal regardless of the revision they are locked
+        // to, which is what tells two checkouts of a git repository apart.
+        let revisions = other.graph.iter().map(|id| {
+            (id, id.get_source_id().get_precise())
+        }).collect::<HashMap<&PackageId, Option<&str>>>();
+
+        self.root == other.root &&
+            self.graph == other.graph &&
+            self.metadata == other.metadata &&
+            self.graph.iter().all(|id| {
+                let revision = id.get_source_id().get_precise();
+                self.checksum(id) == other.checksum(id) &&
+                    (!id.get_source_id().is_git() ||
+                     revisions.get(id) == Some(&revision))
+            })
+    }
+
+    /// Checks that the checksums of this resolve agree with those recorded
+    /// in `previous`, typically loaded from a lockfile.
+    ///
+    /// A mismatch means that the contents of a package changed since the
+    /// lockfile was generated, for example because a mirror of the registry
+    /// serves different contents, so it is reported as an error.
+    pub fn check_checksums(&self, previous: &Resolve) -> CargoResult<()> {
+        for (id, prev) in previous.checksums.iter() {
+            let prev = match *prev {
+                Some(ref s) => s,
+                None => continue,
+            };
+            let current = match self.checksums.get(id) {
+                Some(current) => current,
+                None => continue,
+            };
+            match *current {
+                Some(ref s) if s == prev => {}
+                Some(..) => {
+                    return Err(human(format!("checksum for `{0}` changed \
+                                              between lock files\n\n\
+                                              this could be indicative of a \
+                                              few possible errors:\n\n    \
+                                              * the lock file is corrupt\n    \
+                                              * a replacement source in use \
+                                              (e.g. a mirror) returned a \
+                                              different checksum\n    \
+                                              * the source itself may be \
+                                              corrupt in one way or \
+                                              another\n\n\
+                                              unable to verify that `{0}` is \
+                                              the same as when the lockfile \
+                                              was generated", id)))
+                }
+                None => {
+                    return Err(human(format!("checksum for `{0}` could not be \
+                                              calculated, but a checksum is \
+                                              listed in the existing lock \
+                                              file\n\n\
+                                              this could be indicative of a \
+                                              replacement source in use \
+                                              which does not provide \
+                                              checksums\n\n\
+                                              unable to verify that `{0}` is \
+                                              the same as when the lockfile \
+                                              was generated", id)))
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl fmt::Show for Resolve {
@@ -126,52 +280,158 @@ struct Context {
     activations: HashMap<(String, SourceId), Vec<Rc<Summary>>>,
     resolve: Resolve,
     visited: Rc<RefCell<HashSet<PackageId>>>,
+    /// For each activated package, the packages which depend on it along with
+    /// the dependency through which they do, most recent first. Only used to
+    /// explain failures.
+    ///
+    /// This is a persistent list so that cloning a context to try a candidate
+    /// doesn't copy it.
+    parents: Option<Rc<ParentLink>>,
+    /// Sets of activations which are known to make the resolution fail, along
+    /// with the error they lead to. They are indexed by the package whose
+    /// dependency couldn't be resolved because of them, and shared between
+    /// all contexts so that no set is explored twice.
+    past_conflicts: Rc<RefCell<HashMap<PackageId,
+                                       Vec<(Dependency, HashSet<PackageId>,
+                                            Rc<Box<CargoError>>)>>>>,
+    /// The strategy requested for the root package, which applies to the
+    /// whole graph.
+    strategy: Strategy,
+    /// Whether conflicts are recorded and jumped back to their causes, which
+    /// is only turned off to measure what this saves.
+    learn_conflicts: bool,
+}
+
+/// A link of the list of the reasons why packages were activated.
+struct ParentLink {
+    child: PackageId,
+    edge: (PackageId, Dependency),
+    next: Option<Rc<ParentLink>>,
+}
+
+impl Context {
+    fn is_active(&self, id: &PackageId) -> bool {
+        let key = (id.get_name().to_string(), id.get_source_id().clone());
+        self.activations.get(&key).map_or(false, |prev| {
+            prev.iter().any(|s| s.get_package_id() == id)
+        })
+    }
+
+    /// Records that `child` is depended on by `parent` through `dep`.
+    fn add_parent(&mut self, child: PackageId, parent: PackageId,
+                  dep: Dependency) {
+        let next = self.parents.take();
+        self.parents = Some(Rc::new(ParentLink {
+            child: child,
+            edge: (parent, dep),
+            next: next,
+        }));
+    }
+
+    /// Returns the first package which depended on `id`, along with the
+    /// dependency through which it did, if `id` isn't the root package.
+    fn first_parent(&self, id: &PackageId)
+                    -> Option<&(PackageId, Dependency)> {
+        let mut ret = None;
+        let mut link = self.parents.as_ref();
+        while let Some(l) = link {
+            if l.child == *id { ret = Some(&l.edge) }
+            link = l.next.as_ref();
+        }
+        ret
+    }
+}
+
+/// A failure to resolve the graph which selecting different versions of some
+/// packages may fix.
+struct Conflict {
+    /// Shared with the past conflicts recorded in the context.
+    error: Rc<Box<CargoError>>,
+    /// Activated packages which, as long as they are all activated, make the
+    /// resolution fail. `None` if they could not be determined, in which case
+    /// every remaining candidate has to be tried.
+    causes: Option<HashSet<PackageId>>,
+}
+
+/// Why a candidate for a dependency could not be selected.
+enum Rejection {
+    /// A semver-compatible version of the same package is already selected.
+    Compatible(Rc<Summary>),
+    /// Another selected package links to the same native library.
+    Links(Rc<Summary>),
 }
 
 /// Builds the list of all packages required to build the first argument.
 pub fn resolve<R: Registry>(summary: &Summary, method: Method,
                             registry: &mut R) -> CargoResult<Resolve> {
+    resolve_with(summary, method, registry, true)
+}
+
+/// Same as `resolve`, except that without `learn_conflicts` nothing is
+/// learned from conflicts and the resolver backtracks chronologically.
+fn resolve_with<R: Registry>(summary: &Summary, method: Method,
+                             registry: &mut R, learn_conflicts: bool)
+                             -> CargoResult<Resolve> {
     log!(5, "resolve; summary={:?}", summary);
 
     let mut cx = Context {
         resolve: Resolve::new(summary.get_package_id().clone()),
         activations: HashMap::new(),
         visited: Rc::new(RefCell::new(HashSet::new())),
+        parents: None,
+        past_conflicts: Rc::new(RefCell::new(HashMap::new())),
+        strategy: method.strategy(),
+        learn_conflicts: learn_conflicts,
     };
     let _p = profile::start(format!("resolving: {:?}", summary));
     cx.activations.insert((summary.get_name().to_string(),
                            summary.get_source_id().clone()),
                           vec![Rc::new(summary.clone())]);
-    match try!(activate(cx, registry, summary, method)) {
+    match try!(activate(cx, registry, summary, method, false)) {
         Ok(cx) => Ok(cx.resolve),
-        Err(e) => Err(e),
+        // The contexts holding the past conflicts are all gone by now, so the
+        // error isn't shared anymore.
+        Err(conflict) => {
+            Err(rc::try_unwrap(conflict.error).ok()
+                  .expect("BUG: resolver error still shared"))
+        }
     }
 }
 
+/// Activates the dependencies of `parent`, which is compiled for the host if
+/// `for_host` is true and for the target otherwise.
 fn activate<R: Registry>(mut cx: Context,
                          registry: &mut R,
                          parent: &Summary,
-                         method: Method)
-                         -> CargoResult<CargoResult<Context>> {
+                         method: Method,
+                         for_host: bool)
+                         -> CargoResult<Result<Context, Conflict>> {
     // Extracting the platform request.
-    let platform = match method {
-        Method::Required(_, _, _, platform) => platform,
-        Method::Everything => None,
+    let (dev_deps, platforms) = match method {
+        Method::Required(dev_deps, _, _, target, host, _) => {
+            (dev_deps, (target, host))
+        }
+        Method::Everything(..) => (true, (None, None)),
     };
 
     // First, figure out our set of dependencies based on the requsted set of
     // features. This also calculates what features we're going to enable for
     // our own dependencies.
-    let deps = try!(resolve_features(&mut cx, parent, method));
+    let deps = try!(resolve_features(&mut cx, parent, method, for_host));
 
     // Next, transform all dependencies into a list of possible candidates which
     // can satisfy that dependency.
+    let strategy = cx.strategy;
     let mut deps = try!(deps.into_iter().map(|(_dep_name, (dep, features))| {
         let mut candidates = try!(registry.query(dep));
         // When we attempt versions for a package, we'll want to start at the
-        // maximum version and work our way down.
+        // maximum version and work our way down, unless the oldest versions
+        // were asked for.
         candidates.as_mut_slice().sort_by(|a, b| {
-            b.get_version().cmp(a.get_version())
+            match strategy {
+                Strategy::Newest => b.get_version().cmp(a.get_version()),
+                Strategy::Oldest => a.get_version().cmp(b.get_version()),
+            }
         });
         let candidates = candidates.into_iter().map(Rc::new).collect::<Vec<_>>();
         Ok((dep, candidates, features))
@@ -185,19 +445,35 @@ fn activate<R: Registry>(mut cx: Context,
         a.len().cmp(&b.len())
     });
 
-    activate_deps(cx, registry, parent, platform, deps.as_slice(), 0)
+    activate_deps(cx, registry, parent, dev_deps, platforms, for_host,
+                  deps.as_slice(), 0)
 }
 
 fn activate_deps<'a, R: Registry>(cx: Context,
                                   registry: &mut R,
                                   parent: &Summary,
-                                  platform: Option<&'a str>,
+                                  dev_deps: bool,
+                                  platforms: (Option<&'a PlatformInfo>,
+                                              Option<&'a PlatformInfo>),
+                                  for_host: bool,
                                   deps: &'a [(&Dependency, Vec<Rc<Summary>>, Vec<String>)],
-                                  cur: usize) -> CargoResult<CargoResult<Context>> {
+                                  cur: usize)
+                                  -> CargoResult<Result<Context, Conflict>> {
     if cur == deps.len() { return Ok(Ok(cx)) }
     let (dep, ref candidates, ref features) = deps[cur];
-    let method = Method::Required(false, features.as_slice(),
-                                  dep.uses_default_features(), platform);
+
+    // Workspace members are built just like the root package, so they inherit
+    // its request for dev-dependencies.
+    let member_dev_deps = dev_deps && dep.is_workspace_member();
+
+    // Build dependencies are compiled for the host, and so is everything they
+    // depend on in turn.
+    let (target, host) = platforms;
+    let target = if dep.is_build() {host} else {target};
+    let dep_for_host = for_host || dep.is_build();
+    let method = Method::Required(member_dev_deps, features.as_slice(),
+                                  dep.uses_default_features(), target, host,
+                                  cx.strategy);
 
     let key = (dep.get_name().to_string(), dep.get_source_id().clone());
     let prev_active = cx.activations.get(&key)
@@ -207,18 +483,47 @@ fn activate_deps<'a, R: Registry>(cx: Context,
     log!(5, "{}[{}]>{} {} prev activations", parent.get_name(), cur,
          dep.get_name(), prev_active.len());
 
+    // If this dependency already failed to resolve with packages which are
+    // all still activated, it's going to fail again.
+    let known_conflict = {
+        let past_conflicts = cx.past_conflicts.borrow();
+        past_conflicts.get(parent.get_package_id()).and_then(|conflicts| {
+            conflicts.iter().find(|&&(ref d, ref causes, _)| {
+                d == dep && causes.iter().all(|id| cx.is_active(id))
+            }).map(|&(_, ref causes, ref error)| {
+                (causes.clone(), error.clone())
+            })
+        })
+    };
+    if let Some((causes, error)) = known_conflict {
+        log!(5, "{}[{}]>{} known conflict", parent.get_name(), cur,
+             dep.get_name());
+        return Ok(Err(Conflict { error: error, causes: Some(causes) }))
+    }
+
     // Filter the set of candidates based on the previously activated
     // versions for this dependency. We can actually use a version if it
     // precisely matches an activated version or if it is otherwise
     // incompatible with all other activated versions. Note that we define
     // "compatible" here in terms of the semver sense where if the left-most
     // nonzero digit is the same they're considered compatible.
-    let mut my_candidates = candidates.iter().filter(|&b| {
-        prev_active.iter().any(|a| a == b) ||
-            prev_active.iter().all(|a| {
-                !compatible(a.get_version(), b.get_version())
-            })
-    });
+    //
+    // Rejected candidates are remembered to explain a failure to select any
+    // of them.
+    let mut rejected = Vec::new();
+    let mut my_candidates = Vec::new();
+    for b in candidates.iter() {
+        if prev_active.iter().any(|a| a == b) {
+            my_candidates.push(b);
+            continue
+        }
+        match prev_active.iter().find(|a| {
+            compatible(a.get_version(), b.get_version())
+        }) {
+            Some(a) => rejected.push((b.clone(), Rejection::Compatible(a.clone()))),
+            None => my_candidates.push(b),
+        }
+    }
 
     // Alright, for each candidate that's gotten this far, it meets the
     // following requirements:
@@ -232,34 +537,80 @@ fn activate_deps<'a, R: Registry>(cx: Context,
     // This means that we're going to attempt to activate each candidate in
     // turn. We could possibly fail to activate each candidate, so we try
     // each one in turn.
+    //
+    // Each failure is caused by a set of activated packages. If one of them
+    // isn't caused by the candidate being tried, the other candidates would
+    // fail in the same way and we jump straight back to the latest activation
+    // which did cause it. Otherwise, the failure of this dependency as a whole
+    // is caused by the parent requiring it and whatever else the candidates'
+    // failures were caused by.
+    //
+    // Optional, platform-specific and dev-dependencies may not be required
+    // by the parent in other contexts, so their failures aren't attributed to
+    // it.
+    let mut causes = if !cx.learn_conflicts ||
+                        dep.is_optional() ||
+                        dep.get_only_for_platform().is_some() ||
+                        dep.get_kind() == Kind::Development {
+        None
+    } else {
+        let mut causes = HashSet::new();
+        causes.insert(parent.get_package_id().clone());
+        Some(causes)
+    };
     let mut last_err = None;
-    for candidate in my_candidates {
+    for candidate in my_candidates.into_iter() {
         log!(5, "{}[{}]>{} trying {}", parent.get_name(), cur, dep.get_name(),
              candidate.get_version());
+
+        // Only one package in the graph may link to a given native library.
+        if let Some(links) = candidate.get_links() {
+            let other = cx.activations.values().flat_map(|v| v.iter()).find(|s| {
+                s.get_links() == Some(links) &&
+                    s.get_package_id() != candidate.get_package_id()
+            });
+            if let Some(other) = other {
+                rejected.push((candidate.clone(), Rejection::Links(other.clone())));
+                continue
+            }
+        }
+
         let mut my_cx = cx.clone();
         let early_return = {
             my_cx.resolve.graph.link(parent.get_package_id().clone(),
                                      candidate.get_package_id().clone());
+            my_cx.add_parent(candidate.get_package_id().clone(),
+                             parent.get_package_id().clone(), dep.clone());
             let prev = match my_cx.activations.entry(key.clone()) {
                 Occupied(e) => e.into_mut(),
                 Vacant(e) => e.insert(Vec::new()),
             };
+            // A package already activated for the same side of the build
+            // with at least these features has nothing left to activate.
             if prev.iter().any(|c| c == candidate) {
-                match cx.resolve.features(candidate.get_package_id()) {
+                let prev_features = if dep_for_host {
+                    cx.resolve.host_features(candidate.get_package_id())
+                } else {
+                    cx.resolve.features(candidate.get_package_id())
+                };
+                match prev_features {
                     Some(prev_features) => {
                         features.iter().all(|f| prev_features.contains(f))
                     }
-                    None => features.len() == 0,
+                    None => false,
                 }
             } else {
                 my_cx.resolve.graph.add(candidate.get_package_id().clone(), &[]);
+                my_cx.resolve.checksums.insert(
+                    candidate.get_package_id().clone(),
+                    candidate.get_checksum().map(|s| s.to_string()));
                 prev.push(candidate.clone());
                 false
             }
         };
 
         let my_cx = if early_return {
-            my_cx
+            Ok(my_cx)
         } else {
             // Dependency graphs are required to be a DAG. Non-transitive
             // dependencies (dev-deps), however, can never introduce a cycle, so we
@@ -270,70 +621,189 @@ fn activate_deps<'a, R: Registry>(cx: Context,
                                           depends on itself",
                                          candidate.get_package_id())))
             }
-            let my_cx = try!(activate(my_cx, registry, &**candidate, method));
+            let my_cx = try!(activate(my_cx, registry, &**candidate, method,
+                                      dep_for_host));
             if dep.is_transitive() {
                 cx.visited.borrow_mut().remove(candidate.get_package_id());
             }
-            match my_cx {
-                Ok(cx) => cx,
-                Err(e) => { last_err = Some(e); continue }
+            my_cx
+        };
+        let conflict = match my_cx {
+            Ok(my_cx) => {
+                match try!(activate_deps(my_cx, registry, parent, dev_deps,
+                                         platforms, for_host, deps, cur + 1)) {
+                    Ok(cx) => return Ok(Ok(cx)),
+                    Err(conflict) => conflict,
+                }
             }
+            Err(conflict) => conflict,
+        };
+        let backjump = match conflict.causes {
+            Some(ref c) => c.iter().all(|id| cx.is_active(id)),
+            None => false,
         };
-        match try!(activate_deps(my_cx, registry, parent, platform, deps, cur + 1)) {
-            Ok(cx) => return Ok(Ok(cx)),
-            Err(e) => { last_err = Some(e); }
+        if backjump {
+            log!(5, "{}[{}]>{} backjumping", parent.get_name(), cur,
+                 dep.get_name());
+            return Ok(Err(conflict))
+        }
+        match conflict.causes {
+            Some(ref c) => {
+                if let Some(ref mut causes) = causes {
+                    causes.extend(c.iter().filter(|id| {
+                        *id != candidate.get_package_id()
+                    }).map(|id| id.clone()));
+                }
+            }
+            None => causes = None,
         }
+        last_err = Some(conflict.error);
     }
     log!(5, "{}[{}]>{} -- {:?}", parent.get_name(), cur, dep.get_name(),
          last_err);
 
-    // Oh well, we couldn't activate any of the candidates, so we just can't
-    // activate this dependency at all
-    Ok(match last_err {
-        Some(e) => Err(e),
-        None if candidates.len() > 0 => {
-            let mut msg = format!("failed to select a version for `{}` \
-                                   (required by `{}`):\n\
-                                   all possible versions conflict with \
-                                   previously selected versions of `{}`",
-                                  dep.get_name(), parent.get_name(),
-                                  dep.get_name());
-            'outer: for v in prev_active.iter() {
-                for node in cx.resolve.graph.iter() {
-                    let mut edges = match cx.resolve.graph.edges(node) {
-                        Some(edges) => edges,
-                        None => continue,
-                    };
-                    for edge in edges {
-                        if edge != v.get_package_id() { continue }
-
-                        msg.push_str(format!("\n  version {} in use by {}",
-                                             v.get_version(), edge).as_slice());
-                        continue 'outer;
-                    }
+    if let Some(ref mut causes) = causes {
+        for &(_, ref why) in rejected.iter() {
+            match *why {
+                Rejection::Compatible(ref s) | Rejection::Links(ref s) => {
+                    causes.insert(s.get_package_id().clone());
                 }
-                msg.push_str(format!("\n  version {} in use by ??",
-                                     v.get_version()).as_slice());
             }
+        }
+    }
 
-            msg.push_str(&format!("\n  possible versions to select: {}",
-                                  candidates.iter()
-                                            .map(|v| v.get_version())
-                                            .map(|v| v.to_string())
-                                            .collect::<Vec<_>>()
-                                            .connect(", "))[]);
+    // Oh well, we couldn't activate any of the candidates, so we just can't
+    // activate this dependency at all
+    let error = match last_err {
+        Some(e) => e,
+        None => Rc::new(try!(explain_failure(&cx, registry, parent, dep,
+                                             candidates.as_slice(),
+                                             rejected.as_slice()))),
+    };
+    if let Some(ref causes) = causes {
+        let mut past_conflicts = cx.past_conflicts.borrow_mut();
+        match past_conflicts.entry(parent.get_package_id().clone()) {
+            Occupied(e) => e.into_mut(),
+            Vacant(e) => e.insert(Vec::new()),
+        }.push((dep.clone(), causes.clone(), error.clone()));
+    }
+    Ok(Err(Conflict { error: error, causes: causes }))
+}
 
-            Err(human(msg))
-        }
-        None => {
-            Err(human(format!("no package named `{}` found (required by `{}`)\n\
-                               location searched: {}\n\
-                               version required: {}",
+/// Builds the error for a dependency none of whose candidates could even be
+/// tried, either because there are none or because they all conflict with
+/// packages which are already activated.
+fn explain_failure<R: Registry>(cx: &Context,
+                                registry: &mut R,
+                                parent: &Summary,
+                                dep: &Dependency,
+                                candidates: &[Rc<Summary>],
+                                rejected: &[(Rc<Summary>, Rejection)])
+                                -> CargoResult<Box<CargoError>> {
+    let mut yanked = try!(registry.query_yanked(dep));
+    yanked.sort_by(|a, b| b.get_version().cmp(a.get_version()));
+
+    if candidates.len() > 0 {
+        let mut msg = format!("failed to select a version for `{}` \
+                               (required by `{}`):\n\
+                               all possible versions conflict with \
+                               previously selected packages\n\n  \
+                               `{} {}` is required through:",
                               dep.get_name(), parent.get_name(),
-                              dep.get_source_id(),
-                              dep.get_version_req())))
+                              dep.get_name(), dep.get_version_req());
+        describe_path(&mut msg, cx, parent.get_package_id());
+        msg.push_str(format!("\n      requires `{} {}`", dep.get_name(),
+                             dep.get_version_req()).as_slice());
+
+        let mut conflicting: Vec<&Rc<Summary>> = Vec::new();
+        for &(_, ref why) in rejected.iter() {
+            let other = match *why {
+                Rejection::Compatible(ref s) | Rejection::Links(ref s) => s,
+            };
+            if !conflicting.iter().any(|s| s == &other) {
+                conflicting.push(other);
+            }
+        }
+        for other in conflicting.iter() {
+            msg.push_str(format!("\n\n  previously selected `{}`",
+                                 other.get_package_id()).as_slice());
+            if cx.first_parent(other.get_package_id()).is_some() {
+                msg.push_str(" through:");
+                describe_path(&mut msg, cx, other.get_package_id());
+            } else {
+                msg.push_str(", which is the root package");
+            }
+        }
+
+        msg.push_str(format!("\n\n  rejected versions of `{}`:",
+                             dep.get_name()).as_slice());
+        let mut rejected = rejected.iter().map(|&(ref c, ref why)| {
+            let why = match *why {
+                Rejection::Compatible(ref s) => {
+                    format!("semver-compatible with the previously selected \
+                             version {}", s.get_version())
+                }
+                Rejection::Links(ref s) => {
+                    format!("links to native library `{}`, which is already \
+                             linked to by `{}`", s.get_links().unwrap(),
+                            s.get_package_id())
+                }
+            };
+            (c.get_version().clone(), why)
+        }).collect::<Vec<_>>();
+        rejected.extend(yanked.iter().map(|s| {
+            (s.get_version().clone(), "yanked".to_string())
+        }));
+        rejected.sort_by(|a, b| b.0.cmp(&a.0));
+        for &(ref version, ref why) in rejected.iter() {
+            msg.push_str(format!("\n    {}: {}", version, why).as_slice());
         }
-    })
+        return Ok(human(msg))
+    }
+
+    let mut msg = format!("no package named `{}` found (required by `{}`)\n\
+                           location searched: {}\n\
+                           version required: {}",
+                          dep.get_name(), parent.get_name(),
+                          dep.get_source_id(),
+                          dep.get_version_req());
+    if yanked.len() > 0 {
+        let versions = yanked.iter().map(|s| s.get_version().to_string())
+                             .collect::<Vec<String>>();
+        msg.push_str(format!("\nversions which were yanked: {}",
+                             versions.connect(", ")).as_slice());
+    }
+    if let Some(hint) = registry.missing_hint(dep) {
+        msg.push_str("\n");
+        msg.push_str(hint.as_slice());
+    }
+    if cx.first_parent(parent.get_package_id()).is_some() {
+        msg.push_str("\nrequired through:");
+        describe_path(&mut msg, cx, parent.get_package_id());
+    }
+    Ok(human(msg))
+}
+
+/// Appends to `msg` the chain of requirements which led from the root package
+/// to `id`, one dependency per line.
+///
+/// Packages depended on by several others are explained through the first
+/// package which required them.
+fn describe_path(msg: &mut String, cx: &Context, id: &PackageId) {
+    let mut path = Vec::new();
+    let mut seen = HashSet::new();
+    let mut cur = id;
+    while let Some(&(ref parent, ref dep)) = cx.first_parent(cur) {
+        if !seen.insert(parent) { break }
+        path.push((dep, cur));
+        cur = parent;
+    }
+
+    msg.push_str(format!("\n    {}", cur).as_slice());
+    for &(dep, id) in path.iter().rev() {
+        msg.push_str(format!("\n      requires `{} {}`: {}", dep.get_name(),
+                             dep.get_version_req(), id).as_slice());
+    }
 }
 
 // Returns if `a` and `b` are compatible in the semver sense. This is a
@@ -350,25 +820,28 @@ fn compatible(a: &semver::Version, b: &semver::Version) -> bool {
 }
 
 fn resolve_features<'a>(cx: &mut Context, parent: &'a Summary,
-                        method: Method)
+                        method: Method, for_host: bool)
                         -> CargoResult<HashMap<&'a str,
                                                (&'a Dependency, Vec<String>)>> {
     let dev_deps = match method {
-        Method::Everything => true,
-        Method::Required(dev_deps, _, _, _) => dev_deps,
+        Method::Everything(..) => true,
+        Method::Required(dev_deps, _, _, _, _, _) => dev_deps,
     };
 
     // First, filter by dev-dependencies
     let deps = parent.get_dependencies();
     let deps = deps.iter().filter(|d| d.is_transitive() || dev_deps);
 
-    // Second, ignoring dependencies that should not be compiled for this platform
+    // Second, ignoring dependencies that should not be compiled for this
+    // platform. Build dependencies are checked against the host as that's
+    // where build scripts run.
     let mut deps = deps.filter(|d| {
         match method {
-            Method::Required(_, _, _, Some(ref platform)) => {
-                d.is_active_for_platform(platform.as_slice())
-            },
-            _ => true
+            Method::Required(_, _, _, target, host, _) => {
+                let platform = if d.is_build() {host} else {target};
+                platform.map_or(true, |p| d.is_active_for_platform(p))
+            }
+            Method::Everything(..) => true,
         }
     });
 
@@ -409,14 +882,17 @@ fn resolve_features<'a>(cx: &mut Context, parent: &'a Summary,
         }
     }
 
-    // Record what list of features is active for this package.
-    if used_features.len() > 0 {
-        let pkgid = parent.get_package_id();
-        match cx.resolve.features.entry(pkgid.clone()) {
-            Occupied(entry) => entry.into_mut(),
-            Vacant(entry) => entry.insert(HashSet::new()),
-        }.extend(used_features.into_iter());
-    }
+    // Record what list of features is active for this package, on the side
+    // of the build it is activated for.
+    let features = if for_host {
+        &mut cx.resolve.host_features
+    } else {
+        &mut cx.resolve.features
+    };
+    match features.entry(parent.get_package_id().clone()) {
+        Occupied(entry) => entry.into_mut(),
+        Vacant(entry) => entry.insert(HashSet::new()),
+    }.extend(used_features.into_iter());
 
     Ok(ret)
 }
@@ -436,7 +912,7 @@ fn build_features(s: &Summary, method: Method)
     let mut used = HashSet::new();
     let mut visited = HashSet::new();
     match method {
-        Method::Everything => {
+        Method::Everything(..) => {
             for key in s.get_features().keys() {
                 try!(add_feature(s, key.as_slice(), &mut deps, &mut used,
                                  &mut visited));
@@ -446,7 +922,7 @@ fn build_features(s: &Summary, method: Method)
                                  &mut visited));
             }
         }
-        Method::Required(_, requested_features, _, _) =>  {
+        Method::Required(_, requested_features, _, _, _, _) =>  {
             for feat in requested_features.iter() {
                 try!(add_feature(s, feat.as_slice(), &mut deps, &mut used,
                                  &mut visited));
@@ -454,7 +930,7 @@ fn build_features(s: &Summary, method: Method)
         }
     }
     match method {
-        Method::Everything | Method::Required(_, _, true, _) => {
+        Method::Everything(..) | Method::Required(_, _, true, _, _, _) => {
             if s.get_features().get("default").is_some() &&
                !visited.contains("default") {
                 try!(add_feature(s, "default", &mut deps, &mut used,
@@ -514,3 +990,119 @@ fn build_features(s: &Summary, method: Method)
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod bench {
+    use std::collections::HashMap;
+    use test::Bencher;
+
+    use core::{Dependency, PackageId, Summary};
+    use core::registry::test::RegistryBuilder;
+    use core::source::SourceId;
+    use util::ToUrl;
+    use super::{resolve_with, Method, Strategy};
+
+    fn pkg(name: &str, version: &str, deps: &[(&str, &str)]) -> Summary {
+        let url = "http://example.com".to_url().unwrap();
+        let source_id = SourceId::for_registry(&url);
+        let deps = deps.iter().map(|&(name, req)| {
+            Dependency::parse(name, Some(req), &source_id).unwrap()
+        }).collect();
+        let id = PackageId::new(name, version, &source_id).unwrap();
+        Summary::new(id, deps, HashMap::new()).unwrap()
+    }
+
+    /// Builds a root package depending on `rest` and on `fillers` packages of
+    /// `versions` versions each, which are all resolved before `rest` as long
+    /// as it has more candidates.
+    ///
+    /// None of those versions has anything to do with a failure to resolve
+    /// `rest`, but backtracking chronologically tries all `versions^fillers`
+    /// of their combinations.
+    fn pathological(fillers: usize, versions: usize, registry: Vec<Summary>,
+                    rest: &[(&str, &str)]) -> (Summary, RegistryBuilder) {
+        let mut summaries = registry;
+        let names = range(0, fillers).map(|i| {
+            format!("filler{}", i)
+        }).collect::<Vec<String>>();
+        for name in names.iter() {
+            for v in range(0, versions) {
+                summaries.push(pkg(name.as_slice(),
+                                   format!("1.0.{}", v).as_slice(), &[]));
+            }
+        }
+        let mut deps = names.iter().map(|name| {
+            (name.as_slice(), "1")
+        }).collect::<Vec<(&str, &str)>>();
+        deps.push_all(rest);
+        let root = pkg("root", "1.0.0", deps.as_slice());
+        (root, RegistryBuilder::new().summaries(summaries))
+    }
+
+    /// No version of `bad` can be resolved because they all depend on a
+    /// package which doesn't exist.
+    fn missing_dependency(fillers: usize, versions: usize)
+                          -> (Summary, RegistryBuilder) {
+        let registry = range(0, versions + 1).map(|v| {
+            pkg("bad", format!("1.0.{}", v).as_slice(), &[("missing", "1")])
+        }).collect();
+        pathological(fillers, versions, registry, &[("bad", "1")])
+    }
+
+    /// Every version of `bad` requires a version of `pinned` other than the
+    /// one the root package requires.
+    fn conflicting_pins(fillers: usize, versions: usize)
+                        -> (Summary, RegistryBuilder) {
+        let mut registry = vec![pkg("pinned", "1.0.0", &[]),
+                                pkg("pinned", "1.0.1", &[])];
+        registry.extend(range(0, versions + 1).map(|v| {
+            pkg("bad", format!("1.0.{}", v).as_slice(), &[("pinned", "=1.0.0")])
+        }));
+        pathological(fillers, versions, registry, &[("bad", "1"),
+                                                    ("pinned", "=1.0.1")])
+    }
+
+    fn bench_failure(b: &mut Bencher, graph: (Summary, RegistryBuilder),
+                     learn_conflicts: bool) {
+        let (root, mut registry) = graph;
+        b.iter(|| {
+            let method = Method::Everything(Strategy::Newest);
+            assert!(resolve_with(&root, method, &mut registry,
+                                 learn_conflicts).is_err());
+        });
+    }
+
+    // Backtracking chronologically is only bearable on the small graphs,
+    // 6^4 combinations of the fillers, while learning from the conflicts
+    // doesn't depend on how many combinations there are.
+
+    #[bench]
+    fn missing_dependency_chronological(b: &mut Bencher) {
+        bench_failure(b, missing_dependency(4, 6), false);
+    }
+
+    #[bench]
+    fn missing_dependency_learning(b: &mut Bencher) {
+        bench_failure(b, missing_dependency(4, 6), true);
+    }
+
+    #[bench]
+    fn missing_dependency_learning_large(b: &mut Bencher) {
+        bench_failure(b, missing_dependency(16, 20), true);
+    }
+
+    #[bench]
+    fn conflicting_pins_chronological(b: &mut Bencher) {
+        bench_failure(b, conflicting_pins(4, 6), false);
+    }
+
+    #[bench]
+    fn conflicting_pins_learning(b: &mut Bencher) {
+        bench_failure(b, conflicting_pins(4, 6), true);
+    }
+
+    #[bench]
+    fn conflicting_pins_learning_large(b: &mut Bencher) {
+        bench_failure(b, conflicting_pins(16, 20), true);
+    }
+}
@@ -27,7 +27,10 @@ pub struct Shell {
 pub struct MultiShell {
     out: Shell,
     err: Shell,
-    verbose: bool
+    verbose: bool,
+    /// Whether status messages go to stderr, keeping stdout for machine
+    /// readable output
+    status_to_err: bool,
 }
 
 struct UghWhyIsThisNecessary {
@@ -36,7 +39,12 @@ struct UghWhyIsThisNecessary {
 
 impl MultiShell {
     pub fn new(out: Shell, err: Shell, verbose: bool) -> MultiShell {
-        MultiShell { out: out, err: err, verbose: verbose }
+        MultiShell {
+            out: out,
+            err: err,
+            verbose: verbose,
+            status_to_err: false,
+        }
     }
 
     pub fn out(&mut self) -> &mut Shell {
@@ -54,7 +62,11 @@ impl MultiShell {
     pub fn status<T, U>(&mut self, status: T, message: U) -> IoResult<()>
         where T: fmt::String, U: fmt::String
     {
-        self.out().say_status(status, message, GREEN)
+        if self.status_to_err {
+            self.err().say_status(status, message, GREEN)
+        } else {
+            self.out().say_status(status, message, GREEN)
+        }
     }
 
     pub fn verbose<F>(&mut self, mut callback: F) -> IoResult<()>
@@ -86,6 +98,12 @@ impl MultiShell {
     pub fn get_verbose(&self) -> bool {
         self.verbose
     }
+
+    /// Sends status messages to stderr instead of stdout, for commands whose
+    /// stdout is meant to be parsed.
+    pub fn set_status_to_err(&mut self, status_to_err: bool) {
+        self.status_to_err = status_to_err;
+    }
 }
 
 impl Shell {
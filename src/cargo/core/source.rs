@@ -9,7 +9,9 @@ use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
 use url::Url;
 
 use core::{Summary, Package, PackageId, Registry, Dependency};
-use sources::{PathSource, GitSource, RegistrySource};
+use sources::{PathSource, GitSource, RegistrySource, DirectorySource};
+use sources::LocalRegistrySource;
+use sources::ReplacedSource;
 use sources::git;
 use util::{human, Config, CargoResult, CargoError, ToUrl};
 
@@ -51,6 +53,10 @@ enum Kind {
     Path,
     /// represents the central registry
     Registry,
+    /// represents a local directory of vendored packages
+    Directory,
+    /// represents a registry laid out in a local directory
+    LocalRegistry,
 }
 
 #[derive(Show, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -129,6 +135,15 @@ impl SourceId {
                          .with_precise(Some("locked".to_string()))
             }
             "path" => SourceId::for_path(&Path::new(url.slice_from(5))).unwrap(),
+            "directory" => {
+                let url = url.to_url().unwrap();
+                SourceId::new(Kind::Directory, url)
+            }
+            "local-registry" => {
+                let url = url.to_url().unwrap();
+                SourceId::new(Kind::LocalRegistry, url)
+                         .with_precise(Some("locked".to_string()))
+            }
             _ => panic!("Unsupported serialized SourceId")
         }
     }
@@ -155,6 +170,12 @@ impl SourceId {
             SourceIdInner { kind: Kind::Registry, ref url, .. } => {
                 format!("registry+{}", url)
             }
+            SourceIdInner { kind: Kind::Directory, ref url, .. } => {
+                format!("directory+{}", url)
+            }
+            SourceIdInner { kind: Kind::LocalRegistry, ref url, .. } => {
+                format!("local-registry+{}", url)
+            }
         }
     }
 
@@ -172,6 +193,26 @@ impl SourceId {
         SourceId::new(Kind::Registry, url.clone())
     }
 
+    /// Creates a `SourceId` for a directory of vendored packages, such as the
+    /// output of `cargo vendor`. Pass an absolute path.
+    pub fn for_directory(path: &Path) -> CargoResult<SourceId> {
+        let url = try!(path.to_url().map_err(human));
+        Ok(SourceId::new(Kind::Directory, url))
+    }
+
+    /// Creates a `SourceId` for a registry laid out in a local directory.
+    /// Pass an absolute path.
+    pub fn for_local_registry(path: &Path) -> CargoResult<SourceId> {
+        let url = try!(path.to_url().map_err(human));
+        Ok(SourceId::new(Kind::LocalRegistry, url))
+    }
+
+    /// Returns the `SourceId` of the registry called `name` in the
+    /// `registries` table of a `.cargo/config`.
+    pub fn alt_registry(name: &str) -> CargoResult<SourceId> {
+        Ok(SourceId::for_registry(&try!(RegistrySource::named_url(name))))
+    }
+
     /// Returns the `SourceId` corresponding to the main repository.
     ///
     /// This is the main cargo registry by default, but it can be overridden in
@@ -183,6 +224,10 @@ impl SourceId {
     pub fn get_url(&self) -> &Url { &self.inner.url }
     pub fn is_path(&self) -> bool { self.inner.kind == Kind::Path }
     pub fn is_registry(&self) -> bool { self.inner.kind == Kind::Registry }
+    pub fn is_directory(&self) -> bool { self.inner.kind == Kind::Directory }
+    pub fn is_local_registry(&self) -> bool {
+        self.inner.kind == Kind::LocalRegistry
+    }
 
     pub fn is_git(&self) -> bool {
         match self.inner.kind {
@@ -192,7 +237,24 @@ impl SourceId {
     }
 
     /// Creates an implementation of `Source` corresponding to this ID.
+    ///
+    /// If this source has been replaced through the `[source]` table of the
+    /// configuration, the replacement is loaded instead, and the packages it
+    /// returns are presented as if they came from this source.
     pub fn load<'a>(&self, config: &'a Config) -> Box<Source+'a> {
+        match config.get_source_replacement(self) {
+            Some(replace_with) => {
+                log!(5, "loading SourceId; {} replaced with {}", self,
+                     replace_with);
+                let inner = replace_with.load_original(config);
+                Box::new(ReplacedSource::new(self, replace_with, inner))
+                    as Box<Source>
+            }
+            None => self.load_original(config),
+        }
+    }
+
+    fn load_original<'a>(&self, config: &'a Config) -> Box<Source+'a> {
         log!(5, "loading SourceId; {}", self);
         match self.inner.kind {
             Kind::Git(..) => Box::new(GitSource::new(self, config)) as Box<Source>,
@@ -206,6 +268,21 @@ impl SourceId {
             Kind::Registry => {
                 Box::new(RegistrySource::new(self, config)) as Box<Source>
             }
+            Kind::Directory => {
+                let path = match self.inner.url.to_file_path() {
+                    Ok(p) => p,
+                    Err(()) => panic!("directory sources cannot be remote"),
+                };
+                Box::new(DirectorySource::new(&path, self)) as Box<Source>
+            }
+            Kind::LocalRegistry => {
+                let path = match self.inner.url.to_file_path() {
+                    Ok(p) => p,
+                    Err(()) => panic!("local registry sources cannot be remote"),
+                };
+                Box::new(LocalRegistrySource::new(&path, self, config))
+                    as Box<Source>
+            }
         }
     }
 
@@ -294,6 +371,12 @@ impl fmt::String for SourceId {
             SourceIdInner { kind: Kind::Registry, ref url, .. } => {
                 write!(f, "registry {}", url)
             }
+            SourceIdInner { kind: Kind::Directory, ref url, .. } => {
+                write!(f, "directory {}", url)
+            }
+            SourceIdInner { kind: Kind::LocalRegistry, ref url, .. } => {
+                write!(f, "local registry {}", url)
+            }
         }
     }
 }
@@ -495,4 +578,17 @@ mod tests {
         let s3 = SourceId::new(foo, loc);
         assert!(s1 != s3);
     }
+
+    #[test]
+    fn local_registry_url_round_trips() {
+        let path = Path::new("/path/to/registry");
+        let id = SourceId::for_local_registry(&path).unwrap();
+        let url = id.to_url();
+        assert_eq!(url.as_slice(), "local-registry+file:///path/to/registry");
+
+        let parsed = SourceId::from_url(url);
+        assert_eq!(parsed, id);
+        assert!(parsed.is_local_registry());
+        assert!(!parsed.is_registry());
+    }
 }
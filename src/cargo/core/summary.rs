@@ -15,6 +15,8 @@ pub struct Summary {
     package_id: PackageId,
     dependencies: Vec<Dependency>,
     features: HashMap<String, Vec<String>>,
+    checksum: Option<String>,
+    links: Option<String>,
 }
 
 impl Summary {
@@ -65,6 +67,8 @@ impl Summary {
             package_id: pkg_id,
             dependencies: dependencies,
             features: features,
+            checksum: None,
+            links: None,
         })
     }
 
@@ -92,11 +96,45 @@ impl Summary {
         &self.features
     }
 
+    /// The SHA-256 checksum of the packaged contents of this package, if its
+    /// source is able to provide one.
+    pub fn get_checksum(&self) -> Option<&str> {
+        self.checksum.as_ref().map(|s| s.as_slice())
+    }
+
+    pub fn set_checksum(mut self, checksum: String) -> Summary {
+        self.checksum = Some(checksum);
+        self
+    }
+
+    /// The name of the native library this package links to, if known.
+    pub fn get_links(&self) -> Option<&str> {
+        self.links.as_ref().map(|s| s.as_slice())
+    }
+
+    pub fn set_links(mut self, links: Option<String>) -> Summary {
+        self.links = links;
+        self
+    }
+
     pub fn override_id(mut self, id: PackageId) -> Summary {
         self.package_id = id;
         self
     }
 
+    /// Moves this summary, and all of its dependencies on packages from the
+    /// same source, from `to_replace` over to `replace_with`.
+    pub fn map_source(self, to_replace: &SourceId, replace_with: &SourceId)
+                      -> Summary {
+        let summary = if self.get_source_id() == to_replace {
+            let id = self.package_id.with_source_id(replace_with);
+            self.override_id(id)
+        } else {
+            self
+        };
+        summary.map_dependencies(|dep| dep.map_source(to_replace, replace_with))
+    }
+
     pub fn map_dependencies<F>(mut self, f: F) -> Summary
                                where F: FnMut(Dependency) -> Dependency {
         let deps = mem::replace(&mut self.dependencies, Vec::new());
@@ -0,0 +1,203 @@
+use std::io::File;
+use std::io::fs::PathExtensions;
+
+use core::{Package, Dependency, Summary};
+use sources::PathSource;
+use core::source::Source;
+use util::{CargoResult, human, ChainError};
+use util::toml;
+
+/// A collection of packages which share one lockfile and one output directory.
+///
+/// A workspace is rooted at a package whose manifest contains a `[workspace]`
+/// section listing the relative paths of its members. All members are
+/// resolved together through the root package, so they end up with a single
+/// `Cargo.lock` and a single `target` directory next to the root manifest.
+///
+/// A package which is not part of any workspace is treated as a workspace of
+/// one, in which case the root and the current package are the same.
+pub struct Workspace {
+    // The root package, with a dependency on each member added to its summary
+    // so that resolving the root resolves the whole workspace.
+    root: Package,
+    // The root package exactly as it was read from its manifest.
+    root_pkg: Package,
+    // All members of the workspace, not including the root itself.
+    members: Vec<Package>,
+    // The package whose manifest was originally requested.
+    current: Package,
+}
+
+impl Workspace {
+    /// Loads the workspace which contains the manifest at `manifest_path`.
+    ///
+    /// The parent directories of the manifest are searched for a root
+    /// manifest listing this package as a member. If none is found, the
+    /// package forms a workspace on its own.
+    pub fn new(manifest_path: &Path) -> CargoResult<Workspace> {
+        let root_dir = try!(find_root(manifest_path));
+        let mut source = try!(PathSource::for_path(&root_dir));
+        try!(source.update());
+        let root = try!(source.get_root_package());
+
+        let members = match root.get_manifest().get_workspace_members() {
+            Some(members) => members.to_vec(),
+            // `find_root` only walks past the package itself when it finds a
+            // manifest listing it as a member, so this is a lone package.
+            None => return Ok(Workspace::single(root)),
+        };
+
+        let packages = try!(source.read_packages());
+        let members = try!(members.iter().map(|member| {
+            let dir = root_dir.join(member.as_slice());
+            packages.iter().find(|p| p.get_root() == dir).map(|p| p.clone())
+                    .chain_error(|| {
+                human(format!("workspace member `{}` listed in `{}` does not \
+                               contain a Cargo.toml", member,
+                              root.get_manifest_path().display()))
+            })
+        }).collect::<CargoResult<Vec<Package>>>());
+
+        let current = if root.get_manifest_path() == manifest_path {
+            root.clone()
+        } else {
+            try!(members.iter().find(|p| {
+                p.get_manifest_path() == manifest_path
+            }).map(|p| p.clone()).chain_error(|| {
+                human(format!("package at `{}` is not a member of the \
+                               workspace rooted at `{}`",
+                              manifest_path.display(),
+                              root.get_manifest_path().display()))
+            }))
+        };
+
+        Ok(Workspace {
+            root: try!(augment_root(&root, members.as_slice())),
+            root_pkg: root,
+            members: members,
+            current: current,
+        })
+    }
+
+    /// Creates a workspace consisting of only the specified package.
+    pub fn single(pkg: Package) -> Workspace {
+        Workspace {
+            root: pkg.clone(),
+            root_pkg: pkg.clone(),
+            members: Vec::new(),
+            current: pkg,
+        }
+    }
+
+    /// Returns the package which should be handed to the resolver, and whose
+    /// directory holds the lockfile and output directory of the workspace.
+    pub fn root(&self) -> &Package { &self.root }
+
+    /// Returns the root package as it was read from its manifest, without the
+    /// dependencies on workspace members.
+    pub fn root_package(&self) -> &Package { &self.root_pkg }
+
+    /// Returns the package whose manifest was requested.
+    pub fn current(&self) -> &Package { &self.current }
+
+    /// Returns the members of this workspace, not including the root.
+    pub fn members(&self) -> &[Package] { self.members.as_slice() }
+
+    /// Returns whether the requested manifest is the root of the workspace.
+    pub fn is_root_current(&self) -> bool {
+        self.current.get_package_id() == self.root.get_package_id()
+    }
+
+    /// Returns every package that should be built when the workspace is
+    /// operated on as a whole: the current package, or the root and all of
+    /// its members if the root was requested.
+    pub fn default_packages(&self) -> Vec<&Package> {
+        if self.is_root_current() {
+            let mut ret = vec![&self.root_pkg];
+            ret.extend(self.members.iter());
+            ret
+        } else {
+            vec![&self.current]
+        }
+    }
+}
+
+/// Finds the directory of the root manifest of the workspace containing the
+/// package at `manifest_path`.
+///
+/// Only the `[workspace]` sections of the manifests in parent directories are
+/// inspected here, the rest of the manifests is not required to be valid.
+pub fn find_root(manifest_path: &Path) -> CargoResult<Path> {
+    let pkg_dir = manifest_path.dir_path();
+    let mut current = pkg_dir.clone();
+
+    loop {
+        let manifest = current.join("Cargo.toml");
+        if manifest.exists() {
+            let members = try!(workspace_members(&manifest));
+            let is_member = match members {
+                Some(ref members) => members.iter().any(|m| {
+                    current.join(m.as_slice()) == pkg_dir
+                }),
+                None => false,
+            };
+            if is_member || (current == pkg_dir && members.is_some()) {
+                return Ok(current)
+            }
+        }
+        if !current.pop() { break }
+    }
+
+    Ok(pkg_dir)
+}
+
+fn workspace_members(manifest: &Path) -> CargoResult<Option<Vec<String>>> {
+    let contents = try!(File::open(manifest).read_to_string());
+    let table = try!(toml::parse(contents.as_slice(), manifest));
+    let members = table.get("workspace").and_then(|w| w.as_table())
+                       .and_then(|w| w.get("members"));
+    let members = match members {
+        Some(members) => members,
+        None => return Ok(table.get("workspace").map(|_| Vec::new())),
+    };
+    let members = try!(members.as_slice().chain_error(|| {
+        human(format!("`workspace.members` in `{}` must be an array of \
+                       strings", manifest.display()))
+    }));
+    members.iter().map(|m| {
+        m.as_str().map(|s| s.to_string()).chain_error(|| {
+            human(format!("`workspace.members` in `{}` must be an array of \
+                           strings", manifest.display()))
+        })
+    }).collect::<CargoResult<Vec<String>>>().map(Some)
+}
+
+/// Adds a dependency on each workspace member to the summary of the root, so a
+/// single resolution of the root covers the entire workspace.
+fn augment_root(root: &Package, members: &[Package]) -> CargoResult<Package> {
+    let source_id = root.get_package_id().get_source_id();
+    let is_member = |name: &str| {
+        members.iter().any(|m| m.get_name() == name)
+    };
+
+    // Dependencies of the root which already point at members are flagged as
+    // such, and every other member gets a new dependency edge.
+    let mut deps = root.get_dependencies().iter().map(|d| {
+        let member = d.get_source_id() == source_id && is_member(d.get_name());
+        d.clone().workspace_member(member)
+    }).collect::<Vec<Dependency>>();
+    for member in members.iter() {
+        if deps.iter().any(|d| d.get_name() == member.get_name()) { continue }
+        let dep = try!(Dependency::parse(member.get_name(), None, source_id));
+        deps.push(dep.workspace_member(true));
+    }
+
+    let summary = root.get_summary();
+    let links = summary.get_links().map(|s| s.to_string());
+    let summary = try!(Summary::new(summary.get_package_id().clone(), deps,
+                                    summary.get_features().clone()));
+    let summary = summary.set_links(links);
+    let mut manifest = root.get_manifest().clone();
+    manifest.set_summary(summary);
+    Ok(Package::new(manifest, root.get_manifest_path(), source_id))
+}
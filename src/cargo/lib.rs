@@ -19,6 +19,7 @@ extern crate tar;
 extern crate toml;
 extern crate url;
 #[cfg(test)] extern crate hamcrest;
+#[cfg(test)] extern crate test;
 
 extern crate registry;
 
@@ -0,0 +1,155 @@
+//! Adding and removing dependencies in a manifest.
+//!
+//! The manifest is edited through a `toml_document::Document`, so comments,
+//! the order of keys and the formatting chosen by the user are left alone.
+
+use std::io::File;
+use std::os;
+
+use core::{MultiShell, Dependency, Registry, SourceId, Summary};
+use core::dependency::Kind;
+use core::source::Source;
+use sources::RegistrySource;
+use util::{CargoResult, ChainError, Config, human};
+use util::toml as cargo_toml;
+use util::toml_document::{Document, Value};
+
+pub struct AddOptions<'a> {
+    pub manifest_path: &'a Path,
+    /// The name of the crate, optionally followed by `@` and a version
+    /// requirement.
+    pub krate: &'a str,
+    pub kind: Kind,
+    pub features: &'a [String],
+    pub optional: bool,
+    pub git: Option<&'a str>,
+    /// Path to the crate, relative to the current directory.
+    pub path: Option<&'a str>,
+}
+
+/// Adds a dependency to the manifest at `opts.manifest_path`, replacing any
+/// previous dependency on the same crate of the same kind.
+///
+/// If no version requirement is given for a crate from the registry, the
+/// newest version which hasn't been yanked is looked up in the index.
+pub fn add(opts: &AddOptions, shell: &mut MultiShell) -> CargoResult<()> {
+    let mut parts = opts.krate.splitn(1, '@');
+    let name = parts.next().unwrap();
+    let req = parts.next();
+    if name.is_empty() {
+        return Err(human(format!("invalid crate name: `{}`", opts.krate)))
+    }
+    if let Some(req) = req {
+        let source_id = try!(SourceId::for_central());
+        try!(Dependency::parse(name, Some(req), &source_id));
+    }
+
+    let config = try!(Config::new(shell, None, None));
+    let version = match req {
+        Some(req) => Some(req.to_string()),
+        None if opts.git.is_none() && opts.path.is_none() => {
+            Some(try!(latest_version(name, &config)))
+        }
+        None => None,
+    };
+
+    let mut table = Vec::new();
+    if let Some(ref version) = version {
+        table.push(("version".to_string(), Value::String(version.clone())));
+    }
+    if let Some(git) = opts.git {
+        table.push(("git".to_string(), Value::String(git.to_string())));
+    }
+    if let Some(path) = opts.path {
+        let path = try!(os::getcwd()).join(path);
+        let relative = path.path_relative_from(&opts.manifest_path.dir_path())
+                           .unwrap_or(path);
+        let relative = try!(relative.as_str().chain_error(|| {
+            human(format!("invalid path: `{}`", relative.display()))
+        })).replace("\\", "/");
+        table.push(("path".to_string(), Value::String(relative)));
+    }
+    let features = opts.features.iter().flat_map(|s| {
+        s.as_slice().split(' ')
+    }).filter(|s| !s.is_empty()).map(|s| {
+        Value::String(s.to_string())
+    }).collect::<Vec<Value>>();
+    if !features.is_empty() {
+        table.push(("features".to_string(), Value::Array(features)));
+    }
+    if opts.optional {
+        table.push(("optional".to_string(), Value::Boolean(true)));
+    }
+    let what = match version {
+        Some(ref version) if opts.git.is_none() && opts.path.is_none() => {
+            format!("{} {}", name, version)
+        }
+        _ => name.to_string(),
+    };
+    let value = match (table.len(), version) {
+        (1, Some(version)) => Value::String(version),
+        _ => Value::Table(table),
+    };
+
+    let mut doc = try!(read_manifest(opts.manifest_path));
+    doc.set(&[section(opts.kind)], name, &value);
+    try!(write_manifest(opts.manifest_path, &doc));
+    try!(config.shell().status("Adding", format!("{} to {}", what,
+                                                   section(opts.kind))));
+    Ok(())
+}
+
+/// Removes the dependency on `krate` of the given kind from the manifest at
+/// `manifest_path`.
+pub fn remove(manifest_path: &Path, krate: &str, kind: Kind,
+              shell: &mut MultiShell) -> CargoResult<()> {
+    let mut doc = try!(read_manifest(manifest_path));
+    if !doc.remove(&[section(kind)], krate) {
+        return Err(human(format!("the dependency `{}` could not be found in \
+                                  `{}`", krate, section(kind))))
+    }
+    try!(write_manifest(manifest_path, &doc));
+    try!(shell.status("Removing", format!("{} from {}", krate, section(kind))));
+    Ok(())
+}
+
+fn section(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Normal => "dependencies",
+        Kind::Development => "dev-dependencies",
+        Kind::Build => "build-dependencies",
+    }
+}
+
+/// Returns the newest version of `name` in the registry which hasn't been
+/// yanked.
+fn latest_version(name: &str, config: &Config) -> CargoResult<String> {
+    let source_id = try!(SourceId::for_central());
+    let mut source = RegistrySource::new(&source_id, config);
+    try!(source.update());
+    let dep = try!(Dependency::parse(name, None, &source_id));
+    let summaries = try!(source.query(&dep));
+    let latest = summaries.iter().max_by(|s| s.get_version());
+    latest.map(|s: &Summary| s.get_version().to_string()).chain_error(|| {
+        human(format!("could not find `{}` in `{}`", name, source_id))
+    })
+}
+
+fn read_manifest(path: &Path) -> CargoResult<Document> {
+    let contents = try!(File::open(path).read_to_string().chain_error(|| {
+        human(format!("failed to read `{}`", path.display()))
+    }));
+    Document::parse(contents.as_slice()).chain_error(|| {
+        human(format!("failed to parse manifest at `{}`", path.display()))
+    })
+}
+
+fn write_manifest(path: &Path, doc: &Document) -> CargoResult<()> {
+    let contents = doc.to_string();
+    // Make sure the edit didn't produce something the manifest parser would
+    // choke on before overwriting the user's manifest.
+    try!(cargo_toml::parse(contents.as_slice(), path));
+    File::create(path).write_str(contents.as_slice()).chain_error(|| {
+        human(format!("failed to write `{}`", path.display()))
+    })
+}
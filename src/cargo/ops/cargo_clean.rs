@@ -1,9 +1,10 @@
 use std::default::Default;
 use std::io::fs::{self, PathExtensions};
+use std::io::FileType;
+use time;
 
-use core::{MultiShell, PackageSet};
+use core::{MultiShell, PackageSet, Workspace};
 use core::source::{Source, SourceMap};
-use sources::PathSource;
 use util::{CargoResult, human, ChainError, Config};
 use ops::{self, Layout, Context};
 
@@ -13,11 +14,31 @@ pub struct CleanOptions<'a> {
     pub shell: &'a mut MultiShell,
 }
 
+pub struct GcOptions<'a> {
+    /// Entries unused for more than this many days are removed
+    pub max_age: Option<u32>,
+    /// The least recently used entries are removed until the caches take up
+    /// no more than this many bytes
+    pub max_size: Option<u64>,
+    /// Only report what would be removed
+    pub dry_run: bool,
+    pub shell: &'a mut MultiShell,
+}
+
+/// An entry of one of the caches in the cargo home directory, which can be
+/// removed on its own.
+struct CacheEntry {
+    path: Path,
+    /// When the entry was last used, in milliseconds since the epoch
+    last_use: u64,
+    /// How many bytes the entry takes up
+    size: u64,
+}
+
 /// Cleans the project from build artifacts.
 pub fn clean(manifest_path: &Path, opts: &mut CleanOptions) -> CargoResult<()> {
-    let mut src = try!(PathSource::for_path(&manifest_path.dir_path()));
-    try!(src.update());
-    let root = try!(src.get_root_package());
+    let ws = try!(Workspace::new(manifest_path));
+    let root = ws.root();
     let manifest = root.get_manifest();
 
     // If we have a spec, then we need to delete some package,s otherwise, just
@@ -52,16 +73,25 @@ pub fn clean(manifest_path: &Path, opts: &mut CleanOptions) -> CargoResult<()> {
                                Layout::at(root.get_absolute_target_dir()),
                                None, &pkg, Default::default()));
 
+    // Without a target, the package may also have been compiled with the
+    // features of the host in the separate `host` directory.
+    let triples = match opts.target {
+        Some(target) => vec![Some(target)],
+        None => vec![None, Some("host")],
+    };
+
     // And finally, clean everything out!
     for target in pkg.get_targets().iter() {
-        let layout = Layout::new(&root, opts.target,
-                                 target.get_profile().get_dest());
-        try!(rm_rf(&layout.native(&pkg)));
-        try!(rm_rf(&layout.fingerprint(&pkg)));
-        for filename in try!(cx.target_filenames(target)).iter() {
-            let filename = filename.as_slice();
-            try!(rm_rf(&layout.dest().join(filename)));
-            try!(rm_rf(&layout.deps().join(filename)));
+        for &triple in triples.iter() {
+            let layout = Layout::new(root, triple,
+                                     target.get_profile().get_dest());
+            try!(rm_rf(&layout.native(&pkg)));
+            try!(rm_rf(&layout.fingerprint(&pkg)));
+            for filename in try!(cx.target_filenames(target)).iter() {
+                let filename = filename.as_slice();
+                try!(rm_rf(&layout.dest().join(filename)));
+                try!(rm_rf(&layout.deps().join(filename)));
+            }
         }
     }
 
@@ -80,3 +110,102 @@ fn rm_rf(path: &Path) -> CargoResult<()> {
     }
     Ok(())
 }
+
+/// Removes the entries of the git and registry caches of the cargo home
+/// directory which haven't been used for `max_age` days, then the least
+/// recently used ones until the caches fit in `max_size` bytes.
+///
+/// The registry indexes are left alone, since they are needed to resolve
+/// anything at all.
+pub fn gc(opts: &mut GcOptions) -> CargoResult<()> {
+    let dry_run = opts.dry_run;
+    let max_age = opts.max_age;
+    let max_size = opts.max_size;
+    let config = try!(Config::new(opts.shell, None, None));
+
+    let mut entries = Vec::new();
+    try!(cache_entries(&config.git_db_path(), 1, &mut entries));
+    try!(cache_entries(&config.git_checkout_path(), 2, &mut entries));
+    try!(cache_entries(&config.registry_source_path(), 2, &mut entries));
+    try!(cache_entries(&config.registry_cache_path(), 2, &mut entries));
+    // Least recently used first
+    entries.sort_by(|a, b| a.last_use.cmp(&b.last_use));
+
+    let now = time::get_time();
+    let now = (now.sec as u64) * 1000 + (now.nsec as u64) / 1_000_000;
+    let oldest = max_age.map(|days| {
+        let age = (days as u64) * 24 * 60 * 60 * 1000;
+        if age > now {0} else {now - age}
+    });
+    let mut remaining = entries.iter().fold(0, |sum, e| sum + e.size);
+
+    let mut removed = 0usize;
+    let mut reclaimed = 0;
+    for entry in entries.iter() {
+        let too_old = oldest.map(|oldest| entry.last_use < oldest)
+                            .unwrap_or(false);
+        let too_big = max_size.map(|max| remaining > max).unwrap_or(false);
+        if !too_old && !too_big { continue }
+
+        let status = if dry_run {"Would remove"} else {"Removing"};
+        try!(config.shell().verbose(|shell| {
+            shell.status(status, entry.path.display().to_string())
+        }));
+        if !dry_run {
+            try!(rm_rf(&entry.path));
+        }
+        removed += 1;
+        reclaimed += entry.size;
+        remaining -= entry.size;
+    }
+
+    let status = if dry_run {"Would remove"} else {"Removed"};
+    try!(config.shell().status(status,
+         format!("{} cache {}, {}", removed,
+                 if removed == 1 {"entry"} else {"entries"},
+                 human_size(reclaimed))));
+    Ok(())
+}
+
+/// Collects the entries found `depth` levels below `root` into `entries`.
+fn cache_entries(root: &Path, depth: usize,
+                 entries: &mut Vec<CacheEntry>) -> CargoResult<()> {
+    if !root.is_dir() { return Ok(()) }
+    for path in try!(fs::readdir(root)).into_iter() {
+        if depth > 1 {
+            if path.is_dir() {
+                try!(cache_entries(&path, depth - 1, entries));
+            }
+            continue
+        }
+        let stat = try!(fs::lstat(&path));
+        let size = if stat.kind == FileType::Directory {
+            try!(fs::walk_dir(&path)).filter_map(|p| fs::lstat(&p).ok())
+                                      .fold(0, |sum, s| sum + s.size)
+        } else {
+            stat.size
+        };
+        entries.push(CacheEntry {
+            path: path,
+            last_use: stat.modified,
+            size: size,
+        });
+    }
+    Ok(())
+}
+
+/// Formats a number of bytes for people to read.
+fn human_size(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, units[0])
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
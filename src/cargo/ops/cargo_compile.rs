@@ -28,11 +28,11 @@ use std::default::Default;
 use std::sync::Arc;
 
 use core::registry::PackageRegistry;
-use core::{MultiShell, Source, SourceId, PackageSet, Package, Target, PackageId};
-use core::resolver::Method;
-use ops::{self, BuildOutput, ExecEngine};
-use sources::{PathSource};
-use util::config::{Config, ConfigValue};
+use core::{MultiShell, SourceId, PackageSet, Package, Target, PackageId};
+use core::Workspace;
+use core::resolver::{Method, Strategy};
+use ops::{self, BuildOutput, ExecEngine, MessageFormat};
+use util::config::{Config, ConfigValue, Locked};
 use util::{CargoResult, config, internal, human, ChainError, profile};
 
 /// Contains informations about how a package should be compiled.
@@ -50,6 +50,12 @@ pub struct CompileOptions<'a> {
     pub spec: Option<&'a str>,
     pub lib_only: bool,
     pub exec_engine: Option<Arc<Box<ExecEngine>>>,
+    /// How the progress of the build is reported.
+    pub message_format: MessageFormat,
+    /// True if `Cargo.lock` must not be modified.
+    pub locked: Locked,
+    /// True if the network must not be accessed.
+    pub offline: bool,
 }
 
 pub fn compile(manifest_path: &Path,
@@ -57,24 +63,37 @@ pub fn compile(manifest_path: &Path,
                -> CargoResult<ops::Compilation> {
     log!(4, "compile; manifest-path={}", manifest_path.display());
 
-    let mut source = try!(PathSource::for_path(&manifest_path.dir_path()));
-    try!(source.update());
+    let ws = try!(Workspace::new(manifest_path));
+    debug!("loaded workspace; root={}", ws.root());
 
-    // TODO: Move this into PathSource
-    let package = try!(source.get_root_package());
-    debug!("loaded package; package={}", package);
-
-    for key in package.get_manifest().get_warnings().iter() {
-        try!(options.shell.warn(key))
+    for pkg in ws.default_packages().iter() {
+        for key in pkg.get_manifest().get_warnings().iter() {
+            try!(options.shell.warn(key))
+        }
     }
-    compile_pkg(&package, options)
+    compile_ws(&ws, options)
 }
 
 pub fn compile_pkg(package: &Package, options: &mut CompileOptions)
                    -> CargoResult<ops::Compilation> {
+    compile_ws(&Workspace::single(package.clone()), options)
+}
+
+/// Compiles the packages of a workspace which were requested by `options`.
+///
+/// The whole workspace is resolved at once through its root package, so all
+/// members share one lockfile and one output directory.
+pub fn compile_ws(ws: &Workspace, options: &mut CompileOptions)
+                  -> CargoResult<ops::Compilation> {
+    // Only the JSON messages may go to stdout, so that it can be parsed.
+    if options.message_format == MessageFormat::Json {
+        options.shell.set_status_to_err(true);
+    }
     let CompileOptions { env, ref mut shell, jobs, target, spec,
                          dev_deps, features, no_default_features,
-                         lib_only, ref mut exec_engine } = *options;
+                         lib_only, ref mut exec_engine,
+                         message_format, locked, offline } = *options;
+    let package = ws.root();
 
     let target = target.map(|s| s.to_string());
     let features = features.iter().flat_map(|s| {
@@ -89,10 +108,13 @@ pub fn compile_pkg(package: &Package, options: &mut CompileOptions)
     let user_configs = try!(config::all_configs(try!(os::getcwd())));
     let override_ids = try!(source_ids_from_config(&user_configs,
                                                    package.get_root()));
-    let config = try!(Config::new(*shell, jobs, target.clone()));
+    let mut config = try!(Config::new(*shell, jobs, target.clone()));
+    config.set_locked(locked);
+    config.set_offline(offline);
 
     let (packages, resolve_with_overrides, sources) = {
-        let rustc_host = config.rustc_host().to_string();
+        let target_platform = try!(config.target_platform());
+        let host_platform = try!(config.host_platform());
         let mut registry = PackageRegistry::new(&config);
 
         // First, resolve the package's *listed* dependencies, as well as
@@ -106,9 +128,11 @@ pub fn compile_pkg(package: &Package, options: &mut CompileOptions)
 
         try!(registry.add_overrides(override_ids));
 
-        let platform = target.as_ref().map(|e| e.as_slice()).or(Some(rustc_host.as_slice()));
         let method = Method::Required(dev_deps, features.as_slice(),
-                                      !no_default_features, platform);
+                                      !no_default_features,
+                                      Some(&target_platform),
+                                      Some(&host_platform),
+                                      Strategy::Newest);
         let resolved_with_overrides =
                 try!(ops::resolve_with_previous(&mut registry, package, method,
                                                 Some(&resolve), None));
@@ -128,31 +152,36 @@ pub fn compile_pkg(package: &Package, options: &mut CompileOptions)
     let to_build = match spec {
         Some(spec) => {
             let pkgid = try!(resolve_with_overrides.query(spec));
-            packages.iter().find(|p| p.get_package_id() == pkgid).unwrap()
+            vec![packages.iter().find(|p| p.get_package_id() == pkgid).unwrap()]
         }
-        None => package,
+        None => ws.default_packages(),
     };
 
-    let targets = to_build.get_targets().iter().filter(|target| {
-        target.get_profile().is_custom_build() || match env {
-            // doc-all == document everything, so look for doc targets
-            "doc" | "doc-all" => target.get_profile().get_env() == "doc",
-            env => target.get_profile().get_env() == env,
-        }
-    }).filter(|target| !lib_only || target.is_lib()).collect::<Vec<&Target>>();
+    let pkg_targets = to_build.iter().map(|&to_build| {
+        let targets = to_build.get_targets().iter().filter(|target| {
+            target.get_profile().is_custom_build() || match env {
+                // doc-all == document everything, so look for doc targets
+                "doc" | "doc-all" => target.get_profile().get_env() == "doc",
+                env => target.get_profile().get_env() == env,
+            }
+        }).filter(|target| !lib_only || target.is_lib()).collect::<Vec<&Target>>();
+        (to_build, targets)
+    }).collect::<Vec<(&Package, Vec<&Target>)>>();
 
-    if lib_only && targets.len() == 0 {
+    if lib_only && pkg_targets.iter().all(|&(_, ref t)| t.len() == 0) {
         return Err(human("There is no lib to build, remove `--lib` flag".to_string()));
     }
 
     let ret = {
         let _p = profile::start("compiling");
-        let lib_overrides = try!(scrape_build_config(&config, &user_configs));
+        let mut build_config = try!(scrape_build_config(&config,
+                                                        &user_configs));
+        build_config.message_format = message_format;
 
-        try!(ops::compile_targets(env.as_slice(), targets.as_slice(), to_build,
+        try!(ops::compile_targets(env.as_slice(), pkg_targets.as_slice(),
                                   &PackageSet::new(packages.as_slice()),
                                   &resolve_with_overrides, &sources,
-                                  &config, lib_overrides, exec_engine.clone()))
+                                  &config, build_config, exec_engine.clone()))
     };
 
     return Ok(ret);
@@ -197,7 +226,11 @@ fn scrape_build_config(config: &Config,
         Some(triple) => try!(scrape_target_config(target, triple)),
         None => host.clone(),
     };
-    Ok(ops::BuildConfig { host: host, target: target })
+    Ok(ops::BuildConfig {
+        host: host,
+        target: target,
+        message_format: MessageFormat::Human,
+    })
 }
 
 fn scrape_target_config(target: &HashMap<String, config::ConfigValue>,
@@ -236,7 +269,11 @@ fn scrape_target_config(target: &HashMap<String, config::ConfigValue>,
                 let mut output = BuildOutput {
                     library_paths: Vec::new(),
                     library_links: Vec::new(),
+                    cfgs: Vec::new(),
+                    env: Vec::new(),
                     metadata: Vec::new(),
+                    rerun_if_changed: Vec::new(),
+                    rerun_if_env_changed: Vec::new(),
                 };
                 for (k, v) in table.iter() {
                     let v = try!(v.string().chain_error(|| {
@@ -1,10 +1,8 @@
 use std::io::fs::PathExtensions;
 use std::collections::HashSet;
 
-use core::PackageIdSpec;
-use core::source::Source;
+use core::{PackageIdSpec, Workspace};
 use ops;
-use sources::PathSource;
 use std::io::process::Command;
 use util::{CargoResult, human};
 
@@ -16,9 +14,8 @@ pub struct DocOptions<'a> {
 
 pub fn doc(manifest_path: &Path,
            options: &mut DocOptions) -> CargoResult<()> {
-    let mut source = try!(PathSource::for_path(&manifest_path.dir_path()));
-    try!(source.update());
-    let package = try!(source.get_root_package());
+    let ws = try!(Workspace::new(manifest_path));
+    let package = ws.current();
 
     let mut lib_names = HashSet::new();
     let mut bin_names = HashSet::new();
@@ -53,8 +50,8 @@ pub fn doc(manifest_path: &Path,
             }
         };
 
-        let path = package.get_absolute_target_dir().join("doc").join(name)
-                                                    .join("index.html");
+        let path = ws.root().get_absolute_target_dir().join("doc").join(name)
+                                                      .join("index.html");
         if path.exists() {
             open_docs(&path);
         }
@@ -1,19 +1,21 @@
-use core::MultiShell;
+use core::{MultiShell, Workspace};
 use core::registry::PackageRegistry;
-use core::source::Source;
 use ops;
-use sources::PathSource;
-use util::{CargoResult, Config};
+use util::{CargoResult, Config, Locked};
 
 /// Executes `cargo fetch`.
-pub fn fetch(manifest_path: &Path,
+///
+/// With `locked`, the lockfile must already be up to date. With `offline`,
+/// this only checks that everything has already been fetched.
+pub fn fetch(manifest_path: &Path, locked: Locked, offline: bool,
              shell: &mut MultiShell) -> CargoResult<()> {
-    let mut source = try!(PathSource::for_path(&manifest_path.dir_path()));
-    try!(source.update());
-    let package = try!(source.get_root_package());
+    let ws = try!(Workspace::new(manifest_path));
+    let package = ws.root();
 
     let mut config = try!(Config::new(shell, None, None));
+    config.set_locked(locked);
+    config.set_offline(offline);
     let mut registry = PackageRegistry::new(&mut config);
-    try!(ops::resolve_pkg(&mut registry, &package));
+    try!(ops::resolve_pkg(&mut registry, package));
     Ok(())
 }
@@ -1,12 +1,13 @@
-use std::collections::HashSet;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry::{Occupied, Vacant};
 
-use core::PackageId;
+use core::{PackageId, SourceId};
 use core::registry::PackageRegistry;
-use core::{MultiShell, Source, Resolve};
-use core::resolver::Method;
+use core::{MultiShell, Resolve, Workspace};
+use core::resolver::{Method, Strategy};
 use ops;
-use sources::{PathSource};
-use util::config::{Config};
+use util::config::{Config, Locked};
 use util::{CargoResult, human};
 
 pub struct UpdateOptions<'a> {
@@ -14,30 +15,46 @@ pub struct UpdateOptions<'a> {
     pub to_update: Option<&'a str>,
     pub precise: Option<&'a str>,
     pub aggressive: bool,
+    pub strategy: Strategy,
+    /// True if `Cargo.lock` must not be modified.
+    pub locked: Locked,
+    /// True if the network must not be accessed.
+    pub offline: bool,
+    /// True if the changes should only be reported, not written to the lock
+    /// file.
+    pub dry_run: bool,
+    /// True if the locked packages which have been yanked should only be
+    /// listed, without updating anything.
+    pub check_yanked: bool,
 }
 
 pub fn generate_lockfile(manifest_path: &Path,
+                         strategy: Strategy,
+                         locked: Locked,
+                         offline: bool,
                          shell: &mut MultiShell)
                          -> CargoResult<()> {
-    let mut source = try!(PathSource::for_path(&manifest_path.dir_path()));
-    try!(source.update());
-    let package = try!(source.get_root_package());
+    let ws = try!(Workspace::new(manifest_path));
+    let package = ws.root();
     let mut config = try!(Config::new(shell, None, None));
+    config.set_locked(locked);
+    config.set_offline(offline);
     let mut registry = PackageRegistry::new(&mut config);
-    let resolve = try!(ops::resolve_with_previous(&mut registry, &package,
-                                                  Method::Everything,
+    let resolve = try!(ops::resolve_with_previous(&mut registry, package,
+                                                  Method::Everything(strategy),
                                                   None, None));
-    try!(ops::write_pkg_lockfile(&package, &resolve));
+    let previous = try!(ops::load_pkg_lockfile(package));
+    try!(ops::record_pkg_lockfile(registry.config(), package,
+                                  previous.as_ref(), &resolve));
     Ok(())
 }
 
 pub fn update_lockfile(manifest_path: &Path,
                        opts: &mut UpdateOptions) -> CargoResult<()> {
-    let mut source = try!(PathSource::for_path(&manifest_path.dir_path()));
-    try!(source.update());
-    let package = try!(source.get_root_package());
+    let ws = try!(Workspace::new(manifest_path));
+    let package = ws.root();
 
-    let previous_resolve = match try!(ops::load_pkg_lockfile(&package)) {
+    let previous_resolve = match try!(ops::load_pkg_lockfile(package)) {
         Some(resolve) => resolve,
         None => return Err(human("A Cargo.lock must exist before it is updated"))
     };
@@ -48,7 +65,14 @@ pub fn update_lockfile(manifest_path: &Path,
     }
 
     let mut config = try!(Config::new(opts.shell, None, None));
+    config.set_locked(opts.locked);
+    config.set_offline(opts.offline);
     let mut registry = PackageRegistry::new(&mut config);
+
+    if opts.check_yanked {
+        return check_yanked(&mut registry, &previous_resolve)
+    }
+
     let mut to_avoid = HashSet::new();
 
     match opts.to_update {
@@ -57,6 +81,13 @@ pub fn update_lockfile(manifest_path: &Path,
             if opts.aggressive {
                 fill_with_deps(&previous_resolve, dep, &mut to_avoid,
                                &mut HashSet::new());
+
+                // The dependencies updated along with the requested package
+                // keep their locked version when nothing else can be used,
+                // which only matters if that version has been yanked.
+                for id in to_avoid.iter().filter(|id| **id != dep) {
+                    registry.add_last_resort((*id).clone());
+                }
             } else {
                 to_avoid.insert(dep);
                 match opts.precise {
@@ -72,12 +103,22 @@ pub fn update_lockfile(manifest_path: &Path,
         None => to_avoid.extend(previous_resolve.iter()),
     }
 
+    let method = Method::Everything(opts.strategy);
     let resolve = try!(ops::resolve_with_previous(&mut registry,
-                                                  &package,
-                                                  Method::Everything,
+                                                  package,
+                                                  method,
                                                   Some(&previous_resolve),
                                                   Some(&to_avoid)));
-    try!(ops::write_pkg_lockfile(&package, &resolve));
+    try!(print_lockfile_changes(registry.config(), &previous_resolve,
+                                &resolve));
+    try!(ops::warn_yanked(&mut registry, &resolve));
+    if opts.dry_run {
+        try!(registry.config().shell().warn("warning: not updating the lock \
+                                             file due to dry run"));
+    } else {
+        try!(ops::record_pkg_lockfile(registry.config(), package,
+                                      Some(&previous_resolve), &resolve));
+    }
     return Ok(());
 
     fn fill_with_deps<'a>(resolve: &'a Resolve, dep: &'a PackageId,
@@ -95,3 +136,104 @@ pub fn update_lockfile(manifest_path: &Path,
         }
     }
 }
+
+/// Reports how the packages of `resolve` differ from those of `previous`.
+///
+/// Packages are matched by name and source, so a new version or a new git
+/// revision of a package is reported as an update of that package rather
+/// than as the removal of one package and the addition of another.
+fn print_lockfile_changes(config: &Config, previous: &Resolve,
+                          resolve: &Resolve) -> CargoResult<()> {
+    let removed = missing_from(previous, resolve);
+    let added = missing_from(resolve, previous);
+    let mut keys = removed.keys().chain(added.keys()).map(|k| *k)
+                          .collect::<Vec<(&str, &SourceId)>>();
+    keys.sort();
+    keys.dedup();
+
+    for k in keys.into_iter() {
+        let removed = removed.get(&k).map(|v| v.as_slice()).unwrap_or(&[]);
+        let added = added.get(&k).map(|v| v.as_slice()).unwrap_or(&[]);
+
+        if removed.len() == 1 && added.len() == 1 {
+            try!(config.shell().status("Updating",
+                                       format!("{} {} -> {}", k.0,
+                                               describe(removed[0]),
+                                               describe(added[0]))));
+            continue
+        }
+        for id in removed.iter() {
+            try!(config.shell().status("Removing",
+                                       format!("{} {}", k.0, describe(*id))));
+        }
+        for id in added.iter() {
+            try!(config.shell().status("Adding",
+                                       format!("{} {}", k.0, describe(*id))));
+        }
+    }
+    return Ok(());
+
+    /// Returns the packages of `a` which aren't in `b`, by name and source.
+    fn missing_from<'a>(a: &'a Resolve, b: &Resolve)
+                        -> HashMap<(&'a str, &'a SourceId),
+                                   Vec<&'a PackageId>> {
+        let b = b.iter().map(|id| (id, id)).collect::<HashMap<_, _>>();
+        let mut ret = HashMap::new();
+        for id in a.iter() {
+            if b.get(&id).map_or(false, |other| same(id, *other)) { continue }
+            match ret.entry(key(id)) {
+                Occupied(e) => e.into_mut(),
+                Vacant(e) => e.insert(Vec::new()),
+            }.push(id);
+        }
+        ret
+    }
+
+    fn key(id: &PackageId) -> (&str, &SourceId) {
+        (id.get_name(), id.get_source_id())
+    }
+
+    // Source ids compare equal regardless of the revision they are locked
+    // to, which is what tells two checkouts of a git repository apart.
+    fn same(a: &PackageId, b: &PackageId) -> bool {
+        a == b && (!a.get_source_id().is_git() ||
+                   a.get_source_id().get_precise() ==
+                       b.get_source_id().get_precise())
+    }
+
+    fn describe(id: &PackageId) -> String {
+        match id.get_source_id().get_precise() {
+            Some(rev) if id.get_source_id().is_git() => {
+                format!("v{} (#{})", id.get_version(),
+                        rev.slice_to(cmp::min(rev.len(), 8)))
+            }
+            _ => format!("v{}", id.get_version()),
+        }
+    }
+}
+
+/// Lists the packages of `resolve` which have been yanked, after updating the
+/// registries they come from.
+fn check_yanked(registry: &mut PackageRegistry, resolve: &Resolve)
+                -> CargoResult<()> {
+    // The sources of a lock file are all precise, and so wouldn't be updated
+    // when loaded.
+    let mut sources = resolve.iter().map(|id| id.get_source_id())
+                             .filter(|id| id.is_registry())
+                             .map(|id| id.with_precise(None))
+                             .collect::<Vec<SourceId>>();
+    sources.sort();
+    sources.dedup();
+    try!(registry.add_sources(sources.as_slice()));
+
+    let yanked = try!(ops::yanked_packages(registry, resolve));
+    let mut shell = registry.config().shell();
+    if yanked.is_empty() {
+        try!(shell.status("Checked", "no package in Cargo.lock has been \
+                                      yanked"));
+    }
+    for id in yanked.iter() {
+        try!(shell.status("Yanked", id));
+    }
+    Ok(())
+}
@@ -0,0 +1,274 @@
+//! Installation and removal of the binaries of a package.
+//!
+//! Binaries are installed into `<root>/bin` and a listing of which package
+//! installed which binaries is kept in `<root>/.crates.toml`, so that they can
+//! later be removed again with `cargo uninstall`.
+
+use std::io::{self, File, TempDir};
+use std::io::fs::{self, PathExtensions};
+use std::os;
+
+use rustc_serialize::{Encodable, Decodable};
+use toml::{self, Encoder};
+
+use core::{Package, PackageId, PackageIdSpec, Dependency, SourceId};
+use core::MultiShell;
+use core::source::Source;
+use ops::{self, CompileOptions};
+use sources::PathSource;
+use util::config::{self, Config, Locked};
+use util::{CargoResult, ChainError, human, internal};
+use util::toml as cargo_toml;
+
+#[derive(RustcEncodable, RustcDecodable)]
+struct CrateListing {
+    package: Vec<InstalledPackage>,
+}
+
+#[derive(RustcEncodable, RustcDecodable)]
+struct InstalledPackage {
+    id: PackageId,
+    bins: Vec<String>,
+}
+
+/// Compiles `krate` from `source_id` in release mode and copies its binaries
+/// into the `bin` directory of the installation root.
+///
+/// The crate name may only be omitted for path sources, in which case the
+/// package at the root of the path is installed.
+pub fn install(root: Option<&str>,
+               krate: Option<&str>,
+               source_id: &SourceId,
+               vers: Option<&str>,
+               opts: &mut CompileOptions) -> CargoResult<()> {
+    let root = try!(resolve_root(root));
+
+    // Everything but local packages is built in a temporary directory which
+    // is thrown away once the binaries have been copied out of it.
+    let tmp = try!(TempDir::new("cargo-install"));
+    let pkg = {
+        let mut config = try!(Config::new(opts.shell, None, None));
+        config.set_offline(opts.offline);
+        try!(select_pkg(krate, source_id, vers, &config))
+    };
+    let pkg = if source_id.is_path() {
+        pkg
+    } else {
+        let mut manifest = pkg.get_manifest().clone();
+        manifest.set_target_dir(tmp.path().join("target"));
+        Package::new(manifest, pkg.get_manifest_path(),
+                     pkg.get_package_id().get_source_id())
+    };
+
+    if !pkg.get_targets().iter().any(|t| t.is_bin()) {
+        return Err(human(format!("specified package `{}` has no binaries",
+                                 pkg.get_package_id())))
+    }
+
+    // Packages are uploaded without their lock file, so a downloaded package
+    // usually has none for `--locked` to hold the build to.
+    if opts.locked != Locked::No && !source_id.is_path() &&
+       !pkg.get_root().join("Cargo.lock").exists() {
+        try!(opts.shell.warn(format!("warning: no Cargo.lock file published \
+                                      in {}", pkg.get_package_id())));
+        opts.locked = Locked::No;
+    }
+
+    let mut list = try!(read_crate_list(&root));
+    try!(opts.shell.status("Installing", pkg.get_package_id()));
+    let compile = try!(ops::compile_pkg(&pkg, opts).chain_error(|| {
+        human(format!("failed to compile `{}`, intermediate artifacts can be \
+                       found at `{}`", pkg.get_package_id(),
+                      pkg.get_absolute_target_dir().display()))
+    }));
+
+    let bins = compile.binaries.iter().filter_map(|bin| {
+        bin.filename_str().map(|s| s.to_string())
+    }).collect::<Vec<String>>();
+
+    // Refuse to overwrite anything that we did not put there ourselves while
+    // installing this very same package.
+    let dst = root.join("bin");
+    for bin in bins.iter() {
+        let owner = list.package.iter().find(|p| {
+            p.bins.iter().any(|b| b == bin)
+        });
+        match owner {
+            Some(p) if &p.id == pkg.get_package_id() => {}
+            Some(p) => {
+                return Err(human(format!("binary `{}` already exists in \
+                                          destination as part of `{}`",
+                                         bin, p.id)))
+            }
+            None if dst.join(bin.as_slice()).exists() => {
+                return Err(human(format!("binary `{}` already exists in \
+                                          destination `{}`", bin,
+                                         dst.display())))
+            }
+            None => {}
+        }
+    }
+
+    try!(fs::mkdir_recursive(&dst, io::USER_DIR).chain_error(|| {
+        human(format!("failed to create `{}`", dst.display()))
+    }));
+    for src in compile.binaries.iter() {
+        let dst = dst.join(src.filename().unwrap());
+        try!(opts.shell.status("Installing", dst.display()));
+        try!(fs::copy(src, &dst).chain_error(|| {
+            human(format!("failed to copy `{}` to `{}`", src.display(),
+                          dst.display()))
+        }));
+    }
+
+    list.package.retain(|p| &p.id != pkg.get_package_id());
+    list.package.push(InstalledPackage {
+        id: pkg.get_package_id().clone(),
+        bins: bins,
+    });
+    write_crate_list(&root, &list)
+}
+
+/// Removes all binaries which were installed by the package matching `spec`.
+pub fn uninstall(root: Option<&str>,
+                 spec: &str,
+                 shell: &mut MultiShell) -> CargoResult<()> {
+    let root = try!(resolve_root(root));
+    let spec = try!(PackageIdSpec::parse(spec));
+    let mut list = try!(read_crate_list(&root));
+
+    let idx = {
+        let mut matches = list.package.iter().enumerate().filter(|&(_, p)| {
+            spec.matches(&p.id)
+        });
+        let idx = match matches.next() {
+            Some((idx, _)) => idx,
+            None => return Err(human(format!("package id specification `{}` \
+                                              did not match any packages",
+                                             spec))),
+        };
+        match matches.next() {
+            Some((_, other)) => {
+                return Err(human(format!("package id specification `{}` is \
+                                          ambiguous, it matches both `{}` \
+                                          and `{}`", spec,
+                                         list.package[idx].id, other.id)))
+            }
+            None => idx,
+        }
+    };
+
+    let installed = list.package.remove(idx);
+    for bin in installed.bins.iter() {
+        let path = root.join("bin").join(bin.as_slice());
+        if !path.exists() { continue }
+        try!(shell.status("Removing", path.display()));
+        try!(fs::unlink(&path).chain_error(|| {
+            human(format!("failed to remove `{}`", path.display()))
+        }));
+    }
+    write_crate_list(&root, &list)
+}
+
+fn select_pkg(krate: Option<&str>,
+              source_id: &SourceId,
+              vers: Option<&str>,
+              config: &Config) -> CargoResult<Package> {
+    let name = match krate {
+        Some(name) => name,
+        None if source_id.is_path() => {
+            let path = try!(source_id.get_url().to_file_path().map_err(|()| {
+                internal("path sources cannot be remote")
+            }));
+            let mut src = PathSource::new(&path, source_id);
+            try!(src.update());
+            return src.get_root_package()
+        }
+        None if source_id.is_git() => {
+            return Err(human("must specify a crate to install from a git \
+                              repository"))
+        }
+        None => {
+            return Err(human("must specify a crate to install from crates.io, \
+                              or use --path or --git to specify an alternate \
+                              source"))
+        }
+    };
+
+    let mut source = source_id.load(config);
+    try!(source.update());
+    let dep = try!(Dependency::parse(name, vers, source_id));
+    let mut ids = try!(source.query(&dep)).into_iter().map(|s| {
+        s.get_package_id().clone()
+    }).collect::<Vec<PackageId>>();
+    ids.sort();
+    let id = try!(ids.pop().chain_error(|| {
+        let vers = vers.map(|v| format!(" with version `{}`", v))
+                       .unwrap_or(String::new());
+        human(format!("could not find `{}` in `{}`{}", name, source_id, vers))
+    }));
+
+    try!(source.download(&[id.clone()]));
+    let mut pkgs = try!(source.get(&[id.clone()]));
+    pkgs.pop().chain_error(|| {
+        internal(format!("failed to get package `{}` from its source", id))
+    })
+}
+
+/// Finds the directory binaries are installed into, which is the first of the
+/// `--root` flag, the `CARGO_INSTALL_ROOT` environment variable, the
+/// `install.root` configuration key and the cargo home directory.
+fn resolve_root(flag: Option<&str>) -> CargoResult<Path> {
+    let cwd = try!(os::getcwd());
+    if let Some(root) = flag {
+        return Ok(cwd.join(root))
+    }
+    if let Some(root) = os::getenv("CARGO_INSTALL_ROOT") {
+        return Ok(cwd.join(root))
+    }
+
+    let configs = try!(config::all_configs(cwd.clone()));
+    if let Some(install) = configs.get("install") {
+        let install = try!(install.table().chain_error(|| {
+            internal("invalid configuration for the key `install`")
+        }));
+        if let Some(root) = install.get("root") {
+            let (root, path) = try!(root.string().chain_error(|| {
+                internal("invalid configuration for key `install.root`")
+            }));
+            // Relative paths are relative to the directory containing the
+            // `.cargo` directory of the configuration file.
+            return Ok(path.dir_path().dir_path().join(root))
+        }
+    }
+
+    config::homedir().chain_error(|| {
+        human("Cargo couldn't find your home directory. \
+               This probably means that $HOME was not set.")
+    })
+}
+
+fn read_crate_list(root: &Path) -> CargoResult<CrateListing> {
+    let path = root.join(".crates.toml");
+    if !path.exists() {
+        return Ok(CrateListing { package: Vec::new() })
+    }
+    let contents = try!(File::open(&path).read_to_string());
+    let table = try!(cargo_toml::parse(contents.as_slice(), &path));
+    let mut d = toml::Decoder::new(toml::Value::Table(table));
+    Decodable::decode(&mut d).map_err(|e| {
+        human(format!("failed to parse crate metadata at `{}`\n\n{}",
+                      path.display(), e))
+    })
+}
+
+fn write_crate_list(root: &Path, list: &CrateListing) -> CargoResult<()> {
+    let path = root.join(".crates.toml");
+    let mut e = Encoder::new();
+    list.encode(&mut e).unwrap();
+    let contents = toml::Value::Table(e.toml).to_string();
+    try!(fs::mkdir_recursive(root, io::USER_DIR));
+    File::create(&path).write_str(contents.as_slice()).chain_error(|| {
+        human(format!("failed to write crate metadata at `{}`", path.display()))
+    })
+}
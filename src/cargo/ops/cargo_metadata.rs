@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use core::{MultiShell, Package, PackageId, SourceId, Target, Workspace};
+use core::registry::PackageRegistry;
+use core::resolver::{Method, Resolve, Strategy};
+use ops;
+use util::{CargoResult, ChainError, Config, Locked, human};
+
+/// The version of the format produced by `output_metadata`. It is bumped
+/// whenever the output changes in a backwards incompatible way.
+pub const VERSION: u32 = 1;
+
+pub struct OutputMetadataOptions<'a> {
+    pub manifest_path: &'a Path,
+    pub features: &'a [String],
+    pub no_default_features: bool,
+    /// Only include dependencies which are used on this target triple.
+    pub target: Option<&'a str>,
+    pub version: u32,
+    /// True if `Cargo.lock` must not be modified.
+    pub locked: Locked,
+    /// True if the network must not be accessed.
+    pub offline: bool,
+}
+
+/// The resolved dependency graph of a workspace, as printed by
+/// `cargo metadata`.
+#[derive(RustcEncodable)]
+pub struct ExportInfo {
+    packages: Vec<SerializedPackage>,
+    resolve: SerializedResolve,
+    version: u32,
+}
+
+#[derive(RustcEncodable)]
+struct SerializedPackage {
+    name: String,
+    version: String,
+    id: PackageId,
+    source: SourceId,
+    manifest_path: String,
+    targets: Vec<Target>,
+    features: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(RustcEncodable)]
+struct SerializedResolve {
+    root: PackageId,
+    nodes: Vec<SerializedNode>,
+}
+
+#[derive(RustcEncodable)]
+struct SerializedNode {
+    id: PackageId,
+    dependencies: Vec<PackageId>,
+    features: Vec<String>,
+}
+
+/// Resolves the workspace containing `opt.manifest_path` with the requested
+/// features and returns every package of the resulting graph.
+pub fn output_metadata(opt: OutputMetadataOptions,
+                       shell: &mut MultiShell) -> CargoResult<ExportInfo> {
+    if opt.version != VERSION {
+        return Err(human(format!("metadata version {} not supported, only \
+                                  {} is currently supported", opt.version,
+                                 VERSION)))
+    }
+
+    let ws = try!(Workspace::new(opt.manifest_path));
+    let features = opt.features.iter().flat_map(|s| {
+        s.as_slice().split(' ')
+    }).map(|s| s.to_string()).collect::<Vec<String>>();
+
+    let mut config = try!(Config::new(shell, None,
+                                      opt.target.map(|s| s.to_string())));
+    config.set_locked(opt.locked);
+    config.set_offline(opt.offline);
+    let mut registry = PackageRegistry::new(&config);
+    let resolve = try!(ops::resolve_pkg(&mut registry, ws.root()));
+
+    // Dependencies for other platforms are only filtered out when a target
+    // was explicitly requested, otherwise the graph covers all platforms.
+    let platforms = match opt.target {
+        Some(..) => Some((try!(config.target_platform()),
+                          try!(config.host_platform()))),
+        None => None,
+    };
+    let method = Method::Required(true, features.as_slice(),
+                                  !opt.no_default_features,
+                                  platforms.as_ref().map(|p| &p.0),
+                                  platforms.as_ref().map(|p| &p.1),
+                                  Strategy::Newest);
+    let resolve = try!(ops::resolve_with_previous(&mut registry, ws.root(),
+                                                  method, Some(&resolve),
+                                                  None));
+
+    let ids = resolve.iter().map(|id| id.clone()).collect::<Vec<PackageId>>();
+    let mut packages = try!(registry.get(ids.as_slice()).chain_error(|| {
+        human("Unable to get packages from source")
+    }));
+    packages.sort_by(|a, b| a.get_package_id().cmp(b.get_package_id()));
+
+    Ok(ExportInfo {
+        packages: packages.iter().map(serialize_package).collect(),
+        resolve: serialize_resolve(&resolve),
+        version: VERSION,
+    })
+}
+
+fn serialize_package(pkg: &Package) -> SerializedPackage {
+    let id = pkg.get_package_id();
+    SerializedPackage {
+        name: id.get_name().to_string(),
+        version: id.get_version().to_string(),
+        id: id.clone(),
+        source: id.get_source_id().clone(),
+        manifest_path: pkg.get_manifest_path().display().to_string(),
+        targets: pkg.get_targets().to_vec(),
+        features: pkg.get_summary().get_features().iter().map(|(k, v)| {
+            (k.clone(), v.clone())
+        }).collect(),
+    }
+}
+
+fn serialize_resolve(resolve: &Resolve) -> SerializedResolve {
+    let mut nodes = resolve.iter().map(|id| {
+        let mut dependencies = resolve.deps(id).into_iter().flat_map(|a| a)
+                                      .map(|id| id.clone())
+                                      .collect::<Vec<PackageId>>();
+        dependencies.sort();
+        let mut features = resolve.features(id).into_iter()
+                                  .chain(resolve.host_features(id).into_iter())
+                                  .flat_map(|f| f.iter())
+                                  .map(|f| f.clone()).collect::<Vec<String>>();
+        features.sort();
+        features.dedup();
+        SerializedNode {
+            id: id.clone(),
+            dependencies: dependencies,
+            features: features,
+        }
+    }).collect::<Vec<SerializedNode>>();
+    nodes.sort_by(|a, b| a.id.cmp(&b.id));
+
+    SerializedResolve {
+        root: resolve.root().clone(),
+        nodes: nodes,
+    }
+}
@@ -0,0 +1,198 @@
+use std::cmp;
+use std::iter::repeat;
+use term::color::BLACK;
+
+use core::{MultiShell, Dependency, Package, PackageId, Registry, SourceId};
+use core::{SourceMap, Workspace};
+use core::dependency::Kind;
+use core::registry::PackageRegistry;
+use core::resolver::{Resolve, Method, Strategy};
+use ops;
+use util::{CargoResult, ChainError, Config, Locked, human};
+
+pub struct OutdatedOptions<'a> {
+    pub manifest_path: &'a Path,
+    /// True if `Cargo.lock` must not be modified.
+    pub locked: Locked,
+    /// True if the network must not be accessed.
+    pub offline: bool,
+}
+
+/// The dependencies of a workspace for which something newer than the locked
+/// version exists, as printed by `cargo outdated`.
+#[derive(RustcEncodable)]
+pub struct OutdatedReport {
+    dependencies: Vec<OutdatedDependency>,
+}
+
+#[derive(RustcEncodable)]
+struct OutdatedDependency {
+    name: String,
+    source: SourceId,
+    /// The kinds of the dependencies on this package, separated by commas.
+    kind: String,
+    /// The locked version.
+    project: String,
+    /// The newest version matching the requirements of all the packages
+    /// depending on this one, if it is newer than the locked version.
+    compat: Option<String>,
+    /// The newest version, if it is newer than the locked version.
+    latest: Option<String>,
+}
+
+/// Compares the packages locked for the workspace containing
+/// `opts.manifest_path` with what their registry or git repository currently
+/// contains.
+///
+/// A version of a git dependency is its locked revision, so a git dependency
+/// is outdated when the head of its branch has moved.
+///
+/// The lock file is never written, even if it is missing or out of date.
+pub fn outdated(opts: &OutdatedOptions,
+                shell: &mut MultiShell) -> CargoResult<OutdatedReport> {
+    let ws = try!(Workspace::new(opts.manifest_path));
+    let mut config = try!(Config::new(shell, None, None));
+    config.set_locked(opts.locked);
+    config.set_offline(opts.offline);
+    let mut registry = PackageRegistry::new(&config);
+
+    // Only reading the lock file, this doesn't write it back as
+    // `ops::resolve_pkg` would.
+    let prev = try!(ops::load_pkg_lockfile(ws.root()));
+    let method = Method::Everything(Strategy::Newest);
+    let resolve = try!(ops::resolve_with_previous(&mut registry, ws.root(),
+                                                  method, prev.as_ref(),
+                                                  None));
+    try!(ops::warn_yanked(&mut registry, &resolve));
+    if let Some(flag) = config.lock_flag() {
+        try!(ops::check_pkg_lockfile(ws.root(), prev.as_ref(), &resolve,
+                                     flag));
+    }
+
+    let mut ids = resolve.iter().map(|id| id.clone()).collect::<Vec<PackageId>>();
+    ids.sort();
+    let packages = try!(registry.get(ids.as_slice()).chain_error(|| {
+        human("Unable to get packages from source")
+    }));
+
+    // The sources used for the resolution are locked to what the lock file
+    // contains, so they are loaded again without being locked to see their
+    // latest contents.
+    let mut sources = SourceMap::new();
+    let mut dependencies = Vec::new();
+    for id in ids.iter() {
+        let source_id = id.get_source_id().with_precise(None);
+        if !source_id.is_registry() && !source_id.is_git() { continue }
+        if !sources.contains(&source_id) {
+            let mut source = source_id.load(&config);
+            try!(source.update().chain_error(|| {
+                human(format!("Unable to update {}", source_id))
+            }));
+            sources.insert(&source_id, source);
+        }
+
+        let dep = try!(Dependency::parse(id.get_name(), None, &source_id));
+        let summaries = try!(sources.get_mut(&source_id).unwrap().query(&dep));
+        let reqs = requirements(&resolve, packages.as_slice(), id);
+
+        let compat = summaries.iter().filter(|s| {
+            reqs.iter().all(|d| d.matches(*s))
+        }).map(|s| s.get_package_id()).max_by(|p| p.get_version());
+        let latest = summaries.iter().map(|s| s.get_package_id())
+                              .max_by(|p| p.get_version());
+        let compat = compat.and_then(|c| newer(id, c));
+        let latest = latest.and_then(|l| newer(id, l));
+        if compat.is_none() && latest.is_none() { continue }
+
+        let mut kinds = reqs.iter().map(|d| match d.get_kind() {
+            Kind::Normal => "normal",
+            Kind::Development => "dev",
+            Kind::Build => "build",
+        }).collect::<Vec<&str>>();
+        kinds.sort();
+        kinds.dedup();
+        dependencies.push(OutdatedDependency {
+            name: id.get_name().to_string(),
+            source: source_id.clone(),
+            kind: kinds.connect(", "),
+            project: version(id),
+            compat: compat,
+            latest: latest,
+        });
+    }
+    Ok(OutdatedReport { dependencies: dependencies })
+}
+
+/// Prints `report` as a table with a line for each outdated dependency.
+pub fn print_outdated(report: &OutdatedReport,
+                      shell: &mut MultiShell) -> CargoResult<()> {
+    if report.dependencies.is_empty() {
+        try!(shell.say("All dependencies are up to date", BLACK));
+        return Ok(())
+    }
+
+    let none = "---".to_string();
+    let mut rows = vec![vec!["Name", "Project", "Compat", "Latest", "Kind"]];
+    for dep in report.dependencies.iter() {
+        rows.push(vec![dep.name.as_slice(), dep.project.as_slice(),
+                       dep.compat.as_ref().unwrap_or(&none).as_slice(),
+                       dep.latest.as_ref().unwrap_or(&none).as_slice(),
+                       dep.kind.as_slice()]);
+    }
+    let widths = range(0, rows[0].len()).map(|i| {
+        rows.iter().map(|row| row[i].len()).max().unwrap()
+    }).collect::<Vec<uint>>();
+
+    for row in rows.iter() {
+        let mut line = String::new();
+        for (i, cell) in row.iter().enumerate() {
+            line.push_str(*cell);
+            if i < row.len() - 1 {
+                let pad = widths[i] - cell.len() + 2;
+                line.push_str(repeat(" ").take(pad).collect::<String>()
+                                             .as_slice());
+            }
+        }
+        try!(shell.say(line, BLACK));
+    }
+    Ok(())
+}
+
+/// Returns the dependencies on `id` of the packages depending on it.
+fn requirements<'a>(resolve: &Resolve, packages: &'a [Package],
+                    id: &PackageId) -> Vec<&'a Dependency> {
+    let mut ret = Vec::new();
+    for pkg in packages.iter() {
+        let parent = pkg.get_package_id();
+        let depends = resolve.deps(parent).map(|mut deps| {
+            deps.any(|dep| dep == id)
+        }).unwrap_or(false);
+        if parent == id || !depends { continue }
+        ret.extend(pkg.get_dependencies().iter().filter(|d| {
+            d.get_name() == id.get_name() && d.matches_id(id)
+        }));
+    }
+    ret
+}
+
+/// Returns the version of `candidate` if it is newer than `current`, which
+/// for a git repository means that it is another revision.
+fn newer(current: &PackageId, candidate: &PackageId) -> Option<String> {
+    let newer = if current.get_source_id().is_git() {
+        current.get_source_id().get_precise() !=
+            candidate.get_source_id().get_precise()
+    } else {
+        candidate.get_version() > current.get_version()
+    };
+    if newer {Some(version(candidate))} else {None}
+}
+
+fn version(id: &PackageId) -> String {
+    match id.get_source_id().get_precise() {
+        Some(rev) if id.get_source_id().is_git() => {
+            format!("{}#{}", id.get_version(),
+                    rev.slice_to(cmp::min(rev.len(), 8)))
+        }
+        _ => id.get_version().to_string(),
+    }
+}
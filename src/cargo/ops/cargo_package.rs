@@ -9,7 +9,7 @@ use flate2::reader::GzDecoder;
 use core::source::{Source, SourceId};
 use core::{Package, MultiShell};
 use sources::PathSource;
-use util::{CargoResult, human, internal, ChainError};
+use util::{CargoResult, Locked, human, internal, ChainError};
 use ops;
 
 struct Bomb { path: Option<Path> }
@@ -23,11 +23,20 @@ impl Drop for Bomb {
     }
 }
 
+/// Packages the crate at `manifest_path` into a tarball, or only lists its
+/// files with `list`. With `verify`, the tarball is unpacked and built, which
+/// `locked` and `offline` apply to as they do to `cargo build`.
+///
+/// `registry` is the registry the package is meant for, the default one if
+/// `None`, which its path dependencies are taken from to verify it.
 pub fn package(manifest_path: &Path,
                shell: &mut MultiShell,
                verify: bool,
                list: bool,
-               metadata: bool) -> CargoResult<Option<Path>> {
+               metadata: bool,
+               locked: Locked,
+               offline: bool,
+               registry: Option<&SourceId>) -> CargoResult<Option<Path>> {
     let mut src = try!(PathSource::for_path(&manifest_path.dir_path()));
     try!(src.update());
     let pkg = try!(src.get_root_package());
@@ -60,7 +69,12 @@ pub fn package(manifest_path: &Path,
         human("failed to prepare local package for uploading")
     }));
     if verify {
-        try!(run_verify(&pkg, shell, &dst).chain_error(|| {
+        let registry = match registry {
+            Some(registry) => registry.clone(),
+            None => try!(SourceId::for_central()),
+        };
+        try!(run_verify(&pkg, shell, &dst, &registry, locked,
+                        offline).chain_error(|| {
             human("failed to verify package tarball")
         }))
     }
@@ -141,7 +155,8 @@ fn tar(pkg: &Package, src: &PathSource, shell: &mut MultiShell,
     Ok(())
 }
 
-fn run_verify(pkg: &Package, shell: &mut MultiShell, tar: &Path)
+fn run_verify(pkg: &Package, shell: &mut MultiShell, tar: &Path,
+              registry: &SourceId, locked: Locked, offline: bool)
               -> CargoResult<()> {
     try!(shell.status("Verifying", pkg));
 
@@ -155,10 +170,16 @@ fn run_verify(pkg: &Package, shell: &mut MultiShell, tar: &Path)
     try!(archive.unpack(&dst.dir_path()));
     let manifest_path = dst.join("Cargo.toml");
 
+    // The lock file isn't part of the package, so it's brought along for the
+    // build to be checked against it.
+    let lockfile = pkg.get_root().join("Cargo.lock");
+    if locked != Locked::No && lockfile.exists() {
+        try!(fs::copy(&lockfile, &dst.join("Cargo.lock")));
+    }
+
     // When packages are uploaded to the registry, all path dependencies are
     // implicitly converted to registry-based dependencies, so we rewrite those
     // dependencies here.
-    let registry = try!(SourceId::for_central());
     let new_summary = pkg.get_summary().clone().map_dependencies(|d| {
         if !d.get_source_id().is_path() { return d }
         d.source_id(registry.clone())
@@ -181,6 +202,9 @@ fn run_verify(pkg: &Package, shell: &mut MultiShell, tar: &Path)
         spec: None,
         lib_only: false,
         exec_engine: None,
+        message_format: ops::MessageFormat::Human,
+        locked: locked,
+        offline: offline,
     }));
 
     Ok(())
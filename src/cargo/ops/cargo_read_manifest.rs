@@ -114,7 +114,7 @@ fn read_nested_packages(path: &Path,
 
     // Registry sources are not allowed to have `path=` dependencies because
     // they're all translated to actual registry dependencies.
-    if !source_id.is_registry() {
+    if !source_id.is_registry() && !source_id.is_local_registry() {
         for p in nested.iter() {
             try!(read_nested_packages(&path.join(p), all_packages, source_id,
                                       visited));
@@ -2,18 +2,16 @@ use std::os;
 
 use ops::{self, ExecEngine};
 use util::{CargoResult, human, process, ProcessError, ChainError};
+use core::Workspace;
 use core::manifest::TargetKind;
-use core::source::Source;
-use sources::PathSource;
 
 pub fn run(manifest_path: &Path,
            target_kind: TargetKind,
            name: Option<String>,
            options: &mut ops::CompileOptions,
            args: &[String]) -> CargoResult<Option<ProcessError>> {
-    let mut src = try!(PathSource::for_path(&manifest_path.dir_path()));
-    try!(src.update());
-    let root = try!(src.get_root_package());
+    let ws = try!(Workspace::new(manifest_path));
+    let root = ws.current();
     let env = options.env;
     let mut bins = root.get_manifest().get_targets().iter().filter(|a| {
         let matches_kind = match target_kind {
@@ -36,22 +34,19 @@ pub fn run(manifest_path: &Path,
     }
 
     let compile = try!(ops::compile(manifest_path, options));
-    let dst = manifest_path.dir_path().join("target");
-    let dst = match options.target {
-        Some(target) => dst.join(target),
-        None => dst,
-    };
-    let exe = match (bin.get_profile().get_dest(), bin.is_example()) {
-        (Some(s), true) => dst.join(s).join("examples").join(bin.get_name()),
-        (Some(s), false) => dst.join(s).join(bin.get_name()),
-        (None, true) => dst.join("examples").join(bin.get_name()),
-        (None, false) => dst.join(bin.get_name()),
+    // The output directory already accounts for the target triple and the
+    // profile's destination, and is shared by all members of a workspace.
+    let dst = &compile.root_output;
+    let exe = if bin.is_example() {
+        dst.join("examples").join(bin.get_name())
+    } else {
+        dst.join(bin.get_name())
     };
     let exe = match exe.path_relative_from(&try!(os::getcwd())) {
         Some(path) => path,
         None => exe,
     };
-    let process = try!(try!(compile.target_process(exe, &root))
+    let process = try!(try!(compile.target_process(exe, root))
                               .into_process_builder())
                               .args(args)
                               .cwd(try!(os::getcwd()));
@@ -17,8 +17,9 @@ pub struct Compilation {
     /// on.
     pub libraries: HashMap<PackageId, Vec<Path>>,
 
-    /// An array of all tests created during this compilation.
-    pub tests: Vec<(String, Path)>,
+    /// An array of all tests created during this compilation, along with the
+    /// package which each test belongs to.
+    pub tests: Vec<(Package, String, Path)>,
 
     /// An array of all binaries created.
     pub binaries: Vec<Path>,
@@ -42,6 +43,10 @@ pub struct Compilation {
 
     /// Top-level package that was compiled
     pub package: Package,
+
+    /// Packages whose library documentation tests should be run, which are
+    /// all the packages that were explicitly requested to be compiled.
+    pub to_doc_test: Vec<Package>,
 }
 
 impl Compilation {
@@ -55,6 +60,7 @@ impl Compilation {
             binaries: Vec::new(),
             extra_env: HashMap::new(),
             package: pkg.clone(),
+            to_doc_test: Vec::new(),
         }
     }
 
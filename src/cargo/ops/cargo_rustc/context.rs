@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::collections::hash_map::HashMap;
 use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::str;
@@ -40,7 +41,9 @@ pub struct Context<'a, 'b: 'a> {
     target_dylib: Option<(String, String)>,
     target_exe: String,
     requirements: HashMap<(&'a PackageId, &'a str), Platform>,
+    divergent: HashSet<&'a PackageId>,
     build_config: BuildConfig,
+    workspace_members: Vec<Path>,
 }
 
 impl<'a, 'b: 'a> Context<'a, 'b> {
@@ -60,6 +63,17 @@ impl<'a, 'b: 'a> Context<'a, 'b> {
         };
         let target_triple = config.target().map(|s| s.to_string());
         let target_triple = target_triple.unwrap_or(config.rustc_host().to_string());
+
+        // Members of a workspace have their artifacts placed alongside the
+        // root package's rather than in the `deps` directory.
+        let workspace_members = deps.iter().find(|p| {
+            p.get_package_id() == resolve.root()
+        }).and_then(|root| {
+            root.get_manifest().get_workspace_members().map(|members| {
+                members.iter().map(|m| root.get_root().join(m.as_slice()))
+                       .collect()
+            })
+        }).unwrap_or(Vec::new());
         Ok(Context {
             target_triple: target_triple,
             env: env,
@@ -74,9 +88,11 @@ impl<'a, 'b: 'a> Context<'a, 'b> {
             host_dylib: host_dylib,
             host_exe: host_exe,
             requirements: HashMap::new(),
+            divergent: resolve.divergent_packages(),
             compilation: Compilation::new(root_pkg),
             build_state: Arc::new(BuildState::new(build_config.clone(), deps)),
             build_config: build_config,
+            workspace_members: workspace_members,
             exec_engine: Arc::new(Box::new(ProcessEngine) as Box<ExecEngine>),
         })
     }
@@ -194,16 +210,52 @@ impl<'a, 'b: 'a> Context<'a, 'b> {
     }
 
     /// Returns the appropriate directory layout for either a plugin or not.
+    ///
+    /// Without `--target`, a package compiled once for both the host and the
+    /// target is in the directory of the target whatever `kind` is.
     pub fn layout(&self, pkg: &Package, kind: Kind) -> LayoutProxy {
-        let primary = pkg.get_package_id() == self.resolve.root();
+        let primary = pkg.get_package_id() == self.resolve.root() ||
+                      self.is_workspace_member(pkg);
+        let target = self.target.as_ref().unwrap_or(&self.host);
+        match kind {
+            Kind::Host if self.is_host_separate(pkg) => {
+                LayoutProxy::new(&self.host, primary)
+            }
+            Kind::Host | Kind::Target => LayoutProxy::new(target, primary),
+        }
+    }
+
+    /// Returns whether `pkg` is compiled separately for the host, either
+    /// because another target was requested or because it has different
+    /// features for the host and for the target.
+    pub fn is_host_separate(&self, pkg: &Package) -> bool {
+        self.config.target().is_some() ||
+            self.divergent.contains(&pkg.get_package_id())
+    }
+
+    /// Returns the features `pkg` is compiled with for `kind`.
+    ///
+    /// A package only needed by one of the host and the target is compiled
+    /// with the features of that one, whatever `kind` is.
+    pub fn features(&self, pkg: &Package, kind: Kind)
+                    -> Option<&'a HashSet<String>> {
+        let id = pkg.get_package_id();
         match kind {
-            Kind::Host => LayoutProxy::new(&self.host, primary),
-            Kind::Target =>  LayoutProxy::new(self.target.as_ref()
-                                                .unwrap_or(&self.host),
-                                            primary),
+            Kind::Host => self.resolve.host_features(id)
+                              .or(self.resolve.features(id)),
+            Kind::Target => self.resolve.features(id)
+                                .or(self.resolve.host_features(id)),
         }
     }
 
+    /// Returns whether `pkg` is one of the members of the workspace being
+    /// compiled.
+    pub fn is_workspace_member(&self, pkg: &Package) -> bool {
+        pkg.get_package_id().get_source_id() ==
+            self.resolve.root().get_source_id() &&
+            self.workspace_members.contains(&pkg.get_root())
+    }
+
     /// Returns the appropriate output directory for the specified package and
     /// target.
     pub fn out_dir(&self, pkg: &Package, kind: Kind, target: &Target) -> Path {
@@ -279,9 +331,14 @@ impl<'a, 'b: 'a> Context<'a, 'b> {
             Some(deps) => deps,
         };
         deps.map(|id| self.get_package(id)).filter(|dep| {
-            let pkg_dep = pkg.get_dependencies().iter().find(|d| {
+            // The root of a workspace depends on all of its members in the
+            // resolve graph without necessarily listing them in its manifest.
+            let pkg_dep = match pkg.get_dependencies().iter().find(|d| {
                 d.get_name() == dep.get_name()
-            }).unwrap();
+            }) {
+                Some(pkg_dep) => pkg_dep,
+                None => return false,
+            };
 
             // If this target is a build command, then we only want build
             // dependencies, otherwise we want everything *other than* build
@@ -22,8 +22,18 @@ pub struct BuildOutput {
     pub library_paths: Vec<Path>,
     /// Names and link kinds of libraries, suitable for the `-l` flag
     pub library_links: Vec<String>,
+    /// Names to pass to rustc with the `--cfg` flag
+    pub cfgs: Vec<String>,
+    /// Environment variables to set when compiling the package
+    pub env: Vec<(String, String)>,
     /// Metadata to pass to the immediate dependencies
     pub metadata: Vec<(String, String)>,
+    /// Paths, relative to the package root, which trigger a rerun of the
+    /// build script when modified
+    pub rerun_if_changed: Vec<String>,
+    /// Environment variables which trigger a rerun of the build script when
+    /// their value changes
+    pub rerun_if_env_changed: Vec<String>,
 }
 
 pub struct BuildState {
@@ -69,7 +79,7 @@ pub fn prepare(pkg: &Package, target: &Target, req: Platform,
 
     // Be sure to pass along all enabled features for this package, this is the
     // last piece of statically known information that we have.
-    match cx.resolve.features(pkg.get_package_id()) {
+    match cx.features(pkg, kind) {
         Some(features) => {
             for feat in features.iter() {
                 p = p.env(format!("CARGO_FEATURE_{}",
@@ -179,7 +189,8 @@ pub fn prepare(pkg: &Package, target: &Target, req: Platform,
     //
     // Also note that a fresh build command needs to
     let (freshness, dirty, fresh) =
-            try!(fingerprint::prepare_build_cmd(cx, pkg, Some(target)));
+            try!(fingerprint::prepare_build_cmd(cx, pkg,
+                                                Some((target, kind))));
     let dirty = Work::new(move |tx| {
         try!(work(tx.clone()));
         dirty.call(tx)
@@ -252,7 +263,11 @@ impl BuildOutput {
     pub fn parse(input: &str, pkg_name: &str) -> CargoResult<BuildOutput> {
         let mut library_paths = Vec::new();
         let mut library_links = Vec::new();
+        let mut cfgs = Vec::new();
+        let mut env = Vec::new();
         let mut metadata = Vec::new();
+        let mut rerun_if_changed = Vec::new();
+        let mut rerun_if_env_changed = Vec::new();
         let whence = format!("build script of `{}`", pkg_name);
 
         for line in input.lines() {
@@ -277,25 +292,84 @@ impl BuildOutput {
                                               whence, line)))
             };
 
-            if key == "rustc-flags" {
-                let whence = whence.as_slice();
-                let (libs, links) = try!(
-                    BuildOutput::parse_rustc_flags(value, whence)
-                );
-                library_links.extend(links.into_iter());
-                library_paths.extend(libs.into_iter());
-            } else {
-                metadata.push((key.to_string(), value.to_string()))
+            match key {
+                "rustc-flags" => {
+                    let whence = whence.as_slice();
+                    let (libs, links) = try!(
+                        BuildOutput::parse_rustc_flags(value, whence)
+                    );
+                    library_links.extend(links.into_iter());
+                    library_paths.extend(libs.into_iter());
+                }
+                "rustc-link-lib" => {
+                    let link = try!(BuildOutput::parse_link_lib(value)
+                                                .chain_error(|| {
+                        human(format!("Invalid library kind in {}: `{}`",
+                                      whence, line))
+                    }));
+                    library_links.push(link);
+                }
+                "rustc-link-search" => {
+                    let kind = value.find('=').map(|i| value.slice_to(i));
+                    match kind {
+                        None | Some("dependency") | Some("crate") |
+                        Some("native") | Some("all") => {}
+                        Some(..) => {
+                            return Err(human(format!("Invalid search path \
+                                                      kind in {}: `{}`",
+                                                     whence, line)))
+                        }
+                    }
+                    library_paths.push(Path::new(value));
+                }
+                "rustc-cfg" => cfgs.push(value.to_string()),
+                "rustc-env" => {
+                    let mut iter = value.splitn(1, '=');
+                    match (iter.next(), iter.next()) {
+                        (Some(k), Some(v)) if !k.is_empty() => {
+                            env.push((k.to_string(), v.to_string()))
+                        }
+                        _ => return Err(human(format!("Variable in rustc-env \
+                                                       has no value in {}: \
+                                                       `{}`", whence, line))),
+                    }
+                }
+                "rerun-if-changed" => rerun_if_changed.push(value.to_string()),
+                "rerun-if-env-changed" => {
+                    rerun_if_env_changed.push(value.to_string())
+                }
+                _ => metadata.push((key.to_string(), value.to_string())),
             }
         }
 
         Ok(BuildOutput {
             library_paths: library_paths,
             library_links: library_links,
+            cfgs: cfgs,
+            env: env,
             metadata: metadata,
+            rerun_if_changed: rerun_if_changed,
+            rerun_if_env_changed: rerun_if_env_changed,
         })
     }
 
+    /// Converts the `[KIND=]NAME` value of a `rustc-link-lib` key into the
+    /// `NAME[:KIND]` form understood by the `-l` flag, returning `None` if the
+    /// kind is not known.
+    fn parse_link_lib(value: &str) -> Option<String> {
+        let mut iter = value.splitn(1, '=');
+        match (iter.next(), iter.next()) {
+            (Some(name), None) | (Some("dylib"), Some(name)) => {
+                Some(name.to_string())
+            }
+            (Some("static"), Some(name)) => Some(format!("{}:static", name)),
+            (Some("framework"), Some(name)) => {
+                Some(format!("{}:framework", name))
+            }
+            _ => None,
+        }
+    }
+
     pub fn parse_rustc_flags(value: &str, whence: &str)
                              -> CargoResult<(Vec<Path>, Vec<String>)> {
         // TODO: some arguments (like paths) may contain spaces
@@ -2,12 +2,14 @@ use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::hash::{Hash, Hasher, SipHasher};
 use std::io::{self, fs, File, BufferedReader};
 use std::io::fs::PathExtensions;
+use std::os;
 
 use core::{Package, Target};
 use util;
 use util::{CargoResult, Fresh, Dirty, Freshness, internal, profile, ChainError};
 
 use super::Kind;
+use super::custom_build::BuildOutput;
 use super::job::Work;
 use super::context::Context;
 
@@ -67,7 +69,7 @@ pub fn prepare_target(cx: &mut Context, pkg: &Package, target: &Target,
 
     // Second bit of the freshness calculation, whether rustc itself, the
     // target are fresh, and the enabled set of features are all fresh.
-    let features = cx.resolve.features(pkg.get_package_id());
+    let features = cx.features(pkg, kind);
     let features = features.map(|s| {
         let mut v = s.iter().collect::<Vec<&String>>();
         v.sort();
@@ -91,7 +93,9 @@ pub fn prepare_target(cx: &mut Context, pkg: &Package, target: &Target,
             }
 
             if target.get_profile().is_test() {
-                cx.compilation.tests.push((target.get_name().to_string(), dst));
+                cx.compilation.tests.push((pkg.clone(),
+                                           target.get_name().to_string(),
+                                           dst));
             } else if target.is_bin() {
                 cx.compilation.binaries.push(dst);
             } else if target.is_lib() {
@@ -122,10 +126,15 @@ pub fn prepare_target(cx: &mut Context, pkg: &Package, target: &Target,
 /// 3. A build command must itself provide a dep-info-like file stating how it
 ///    should be considered dirty or not.
 ///
-/// The currently implemented solution is option (1), although it is planned to
-/// migrate to option (2) in the near future.
+/// The currently implemented solution is option (1), unless the build script
+/// declared its inputs with `cargo:rerun-if-changed` or
+/// `cargo:rerun-if-env-changed` the last time it was run (option (3)). In that
+/// case the script is only dirty when one of the listed paths has been
+/// modified since it last ran or one of the listed environment variables
+/// changed.
 pub fn prepare_build_cmd(cx: &mut Context, pkg: &Package,
-                         target: Option<&Target>) -> CargoResult<Preparation> {
+                         target: Option<(&Target, Kind)>)
+                         -> CargoResult<Preparation> {
     let _p = profile::start(format!("fingerprint build cmd: {}",
                                     pkg.get_package_id()));
 
@@ -141,20 +150,56 @@ pub fn prepare_build_cmd(cx: &mut Context, pkg: &Package,
 
     info!("fingerprint at: {}", loc.display());
 
-    let new_fingerprint = try!(calculate_build_cmd_fingerprint(cx, pkg));
-    let new_fingerprint = mk_fingerprint(cx, &new_fingerprint);
-
-    let is_fresh = try!(is_fresh(&loc, new_fingerprint.as_slice()));
+    let pkg_fingerprint = try!(calculate_build_cmd_fingerprint(cx, pkg));
 
     // The new custom build command infrastructure handles its own output
     // directory as part of freshness.
-    if target.is_none() {
-        let native_dir = cx.layout(pkg, kind).native(pkg);
-        cx.compilation.native_dirs.insert(pkg.get_package_id().clone(),
-                                          native_dir);
-    }
+    let (target, script_kind) = match target {
+        Some(pair) => pair,
+        None => {
+            let new_fingerprint = mk_fingerprint(cx, &pkg_fingerprint);
+            let is_fresh = try!(is_fresh(&loc, new_fingerprint.as_slice()));
+            let native_dir = cx.layout(pkg, kind).native(pkg);
+            cx.compilation.native_dirs.insert(pkg.get_package_id().clone(),
+                                              native_dir);
+            return Ok(prepare(is_fresh, loc, new_fingerprint))
+        }
+    };
 
-    Ok(prepare(is_fresh, loc, new_fingerprint))
+    // The inputs declared by the build script are only known once it has
+    // run, so the fingerprint to write is calculated again afterwards from
+    // the output of that run. That output is the one of the run for the kind
+    // being built, as the script runs once for each kind when cross
+    // compiling.
+    let output = cx.layout(pkg, script_kind).build_out(pkg).dir_path()
+                   .join("output");
+    let script_fingerprint = try!(calculate_script_fingerprint(target));
+    let root = pkg.get_root();
+    let pkg_name = pkg.to_string();
+    let rustc_version = cx.config.rustc_version().to_string();
+
+    let inputs = try!(build_script_inputs(&output, pkg_name.as_slice()));
+    let new_fingerprint = build_script_fingerprint(rustc_version.as_slice(),
+                                                   pkg_fingerprint.as_slice(),
+                                                   script_fingerprint.as_slice(),
+                                                   inputs.as_ref());
+    let is_fresh = try!(is_fresh(&loc, new_fingerprint.as_slice())) &&
+                   inputs.as_ref().map_or(true, |inputs| {
+                       are_inputs_fresh(&root, inputs, &loc)
+                   });
+
+    let write_fingerprint = Work::new(move |desc_tx| {
+        drop(desc_tx);
+        let inputs = try!(build_script_inputs(&output, pkg_name.as_slice()));
+        let fingerprint = build_script_fingerprint(rustc_version.as_slice(),
+                                                   pkg_fingerprint.as_slice(),
+                                                   script_fingerprint.as_slice(),
+                                                   inputs.as_ref());
+        try!(File::create(&loc).write_str(fingerprint.as_slice()));
+        Ok(())
+    });
+
+    Ok((if is_fresh {Fresh} else {Dirty}, write_fingerprint, Work::noop()))
 }
 
 /// Prepare work for when a package starts to build
@@ -221,11 +266,72 @@ fn is_fresh(loc: &Path, new_fingerprint: &str) -> CargoResult<bool> {
 /// Frob in the necessary data from the context to generate the real
 /// fingerprint.
 fn mk_fingerprint<T: Hash<SipHasher>>(cx: &Context, data: &T) -> String {
+    hash_fingerprint(cx.config.rustc_version(), data)
+}
+
+fn hash_fingerprint<T: Hash<SipHasher>>(rustc_version: &str,
+                                        data: &T) -> String {
     let mut hasher = SipHasher::new_with_keys(0,0);
-    (cx.config.rustc_version(), data).hash(&mut hasher);
+    (rustc_version, data).hash(&mut hasher);
     util::to_hex(hasher.finish())
 }
 
+/// Reads the output of the last run of a build script, returning it if the
+/// script declared which inputs it depends on.
+fn build_script_inputs(output: &Path, pkg_name: &str)
+                       -> CargoResult<Option<BuildOutput>> {
+    if !output.exists() {
+        return Ok(None)
+    }
+    let contents = try!(File::open(output).read_to_string());
+    let output = try!(BuildOutput::parse(contents.as_slice(), pkg_name));
+    if output.rerun_if_changed.is_empty() &&
+       output.rerun_if_env_changed.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(output))
+    }
+}
+
+/// Calculates the fingerprint of a build script. Without declared inputs this
+/// is the fingerprint of the whole package, otherwise it covers the build
+/// script itself, the list of declared paths and the current values of the
+/// declared variables.
+fn build_script_fingerprint(rustc_version: &str, pkg_fingerprint: &str,
+                            script_fingerprint: &str,
+                            inputs: Option<&BuildOutput>) -> String {
+    match inputs {
+        Some(inputs) => {
+            let env = inputs.rerun_if_env_changed.iter().map(|var| {
+                (var.as_slice(), os::getenv(var.as_slice()))
+            }).collect::<Vec<_>>();
+            hash_fingerprint(rustc_version, &(script_fingerprint,
+                                              &inputs.rerun_if_changed, env))
+        }
+        None => hash_fingerprint(rustc_version, &pkg_fingerprint),
+    }
+}
+
+/// Returns whether none of the paths declared by a build script have been
+/// modified since the fingerprint at `loc` was written, which happens right
+/// after the script has run.
+fn are_inputs_fresh(root: &Path, inputs: &BuildOutput, loc: &Path) -> bool {
+    let mtime = match fs::stat(loc) {
+        Ok(stat) => stat.modified,
+        Err(..) => return false,
+    };
+    inputs.rerun_if_changed.iter().all(|path| {
+        match fs::stat(&root.join(path.as_slice())) {
+            Ok(stat) if stat.modified <= mtime => true,
+            Ok(stat) => {
+                info!("stale: {} -- {} vs {}", path, stat.modified, mtime);
+                false
+            }
+            Err(..) => { info!("stale: {} -- missing", path); false }
+        }
+    })
+}
+
 fn calculate_target_fresh(pkg: &Package, dep_info: &Path) -> CargoResult<bool> {
     let line = match BufferedReader::new(File::open(dep_info)).lines().next() {
         Some(Ok(line)) => line,
@@ -270,6 +376,13 @@ fn calculate_build_cmd_fingerprint(cx: &Context, pkg: &Package)
     calculate_pkg_fingerprint(cx, pkg)
 }
 
+/// Hashes the contents of the source file of a build script, which is part of
+/// its fingerprint whatever inputs it declares.
+fn calculate_script_fingerprint(target: &Target) -> CargoResult<String> {
+    let contents = try!(File::open(target.get_src_path()).read_to_end());
+    Ok(util::short_hash(&contents))
+}
+
 fn calculate_pkg_fingerprint(cx: &Context, pkg: &Package) -> CargoResult<String> {
     let source = cx.sources
         .get(pkg.get_package_id().get_source_id())
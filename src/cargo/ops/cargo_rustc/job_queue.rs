@@ -1,14 +1,17 @@
 use std::collections::HashSet;
 use std::collections::hash_map::HashMap;
 use std::collections::hash_map::Entry::{Occupied, Vacant};
-use std::sync::TaskPool;
+use std::sync::{Arc, TaskPool};
 use std::sync::mpsc::{channel, Sender, Receiver};
-use term::color::YELLOW;
+use rustc_serialize::{json, Encodable};
+use term::color::{BLACK, YELLOW};
 
 use core::{Package, PackageId, Resolve, PackageSet};
 use util::{Config, DependencyQueue, Fresh, Dirty, Freshness};
 use util::{CargoResult, Dependency, profile};
 
+use super::{Kind, MessageFormat};
+use super::custom_build::BuildState;
 use super::job::Job;
 
 /// A management structure of the entire dependency graph to compile.
@@ -29,6 +32,25 @@ pub struct JobQueue<'a, 'b> {
     state: HashMap<&'a PackageId, Freshness>,
     ignored: HashSet<&'a PackageId>,
     printed: HashSet<&'a PackageId>,
+    artifacts: HashMap<(&'a PackageId, Stage), Vec<Artifact>>,
+    build_state: Arc<BuildState>,
+    message_format: MessageFormat,
+    divergent: HashSet<&'a PackageId>,
+}
+
+/// A file produced by one of the targets of a package.
+///
+/// Artifacts are registered along with the stage which produces them and are
+/// reported once that stage has finished.
+pub struct Artifact {
+    /// Name of the target
+    pub target: String,
+    /// Crate types the target is compiled as
+    pub kind: Vec<String>,
+    /// Name of the profile the target is compiled with
+    pub profile: String,
+    /// Absolute paths of the files produced
+    pub filenames: Vec<String>,
 }
 
 /// A helper structure for metadata about the state of a building package.
@@ -62,9 +84,47 @@ pub enum Stage {
 
 type Message = (PackageId, Stage, Freshness, CargoResult<()>);
 
+// The events printed on stdout with `--message-format=json`. Each one is
+// encoded as a single line with a `reason` field identifying it.
+
+#[derive(RustcEncodable)]
+struct CompilingMessage<'a> {
+    reason: &'static str,
+    package_id: &'a PackageId,
+    fresh: bool,
+}
+
+#[derive(RustcEncodable)]
+struct ArtifactMessage<'a> {
+    reason: &'static str,
+    package_id: &'a PackageId,
+    target: &'a str,
+    kind: &'a [String],
+    profile: &'a str,
+    filenames: &'a [String],
+    fresh: bool,
+}
+
+#[derive(RustcEncodable)]
+struct BuildScriptMessage<'a> {
+    reason: &'static str,
+    package_id: &'a PackageId,
+    linked_libs: &'a [String],
+    linked_paths: Vec<String>,
+    metadata: &'a [(String, String)],
+}
+
+#[derive(RustcEncodable)]
+struct FailureMessage<'a> {
+    reason: &'static str,
+    package_id: &'a PackageId,
+    message: String,
+}
+
 impl<'a, 'b> JobQueue<'a, 'b> {
     pub fn new(resolve: &'a Resolve, packages: &'a PackageSet,
-               config: &Config) -> JobQueue<'a, 'b> {
+               config: &Config, build_state: Arc<BuildState>,
+               message_format: MessageFormat) -> JobQueue<'a, 'b> {
         let (tx, rx) = channel();
         JobQueue {
             pool: TaskPool::new(config.jobs() as usize),
@@ -78,6 +138,10 @@ impl<'a, 'b> JobQueue<'a, 'b> {
             state: HashMap::new(),
             ignored: HashSet::new(),
             printed: HashSet::new(),
+            artifacts: HashMap::new(),
+            build_state: build_state,
+            message_format: message_format,
+            divergent: resolve.divergent_packages(),
         }
     }
 
@@ -101,6 +165,15 @@ impl<'a, 'b> JobQueue<'a, 'b> {
         self.ignored.insert(pkg.get_package_id());
     }
 
+    /// Registers an artifact which is produced by the `stage` of `pkg`.
+    pub fn artifact(&mut self, pkg: &'a Package, stage: Stage,
+                    artifact: Artifact) {
+        match self.artifacts.entry((pkg.get_package_id(), stage)) {
+            Occupied(mut entry) => entry.get_mut().push(artifact),
+            Vacant(entry) => { entry.insert(vec![artifact]); }
+        }
+    }
+
     /// Execute all jobs necessary to build the dependency graph.
     ///
     /// This function will spawn off `config.jobs()` workers to build all of the
@@ -132,18 +205,31 @@ impl<'a, 'b> JobQueue<'a, 'b> {
             self.active -= 1;
             match result {
                 Ok(()) => {
-                    let state = &mut self.pending[(id, stage)];
-                    state.amt -= 1;
-                    state.fresh = state.fresh.combine(fresh);
-                    if state.amt == 0 {
-                        self.queue.finish(&(id, stage), state.fresh);
+                    let finished = {
+                        let state = &mut self.pending[(id, stage)];
+                        state.amt -= 1;
+                        state.fresh = state.fresh.combine(fresh);
+                        if state.amt == 0 { Some(state.fresh) } else { None }
+                    };
+                    if let Some(fresh) = finished {
+                        try!(self.finish(id, stage, fresh, config));
+                        self.queue.finish(&(id, stage), fresh);
                     }
                 }
                 Err(e) => {
+                    if self.message_format == MessageFormat::Json {
+                        try!(emit(config, &FailureMessage {
+                            reason: "build-failed",
+                            package_id: id,
+                            message: e.to_string(),
+                        }));
+                    }
                     if self.active > 0 {
-                        try!(config.shell().say(
-                                    "Build failed, waiting for other \
-                                     jobs to finish...", YELLOW));
+                        if self.message_format == MessageFormat::Human {
+                            try!(config.shell().say(
+                                        "Build failed, waiting for other \
+                                         jobs to finish...", YELLOW));
+                        }
                         for _ in self.rx.iter().take(self.active as usize) {}
                     }
                     return Err(e)
@@ -156,6 +242,61 @@ impl<'a, 'b> JobQueue<'a, 'b> {
         Ok(())
     }
 
+    /// Reports the outcome of a stage of a package which has finished.
+    ///
+    /// With the human message format nothing is printed here. Otherwise the
+    /// artifacts registered for the stage are printed, along with the output
+    /// of the build script if the stage ran one.
+    fn finish(&mut self, id: &'a PackageId, stage: Stage, fresh: Freshness,
+              config: &Config) -> CargoResult<()> {
+        if self.message_format != MessageFormat::Json ||
+           self.ignored.contains(&id) {
+            return Ok(())
+        }
+
+        if stage == Stage::RunCustomBuild {
+            let outputs = self.build_state.outputs.lock().unwrap();
+            let target = outputs.get(&(id.clone(), Kind::Target));
+            let host = outputs.get(&(id.clone(), Kind::Host));
+            // Without `--target` the host and the target are the same
+            // platform, so a script run for both is only reported once,
+            // unless the package's features differ and it really ran twice.
+            let same = config.target().is_none() &&
+                       !self.divergent.contains(&id);
+            let host = if target.is_some() && same {
+                None
+            } else {
+                host
+            };
+            for output in target.into_iter().chain(host.into_iter()) {
+                try!(emit(config, &BuildScriptMessage {
+                    reason: "build-script-executed",
+                    package_id: id,
+                    linked_libs: output.library_links.as_slice(),
+                    linked_paths: output.library_paths.iter().map(|p| {
+                        p.display().to_string()
+                    }).collect(),
+                    metadata: output.metadata.as_slice(),
+                }));
+            }
+        }
+
+        if let Some(artifacts) = self.artifacts.remove(&(id, stage)) {
+            for artifact in artifacts.iter() {
+                try!(emit(config, &ArtifactMessage {
+                    reason: "compiler-artifact",
+                    package_id: id,
+                    target: artifact.target.as_slice(),
+                    kind: artifact.kind.as_slice(),
+                    profile: artifact.profile.as_slice(),
+                    filenames: artifact.filenames.as_slice(),
+                    fresh: fresh == Fresh,
+                }));
+            }
+        }
+        Ok(())
+    }
+
     /// Execute a stage of compilation for a package.
     ///
     /// The input freshness is from `dequeue()` and indicates the combined
@@ -216,20 +357,37 @@ impl<'a, 'b> JobQueue<'a, 'b> {
         if print && (stage == Stage::Libraries ||
                      (total_fresh == Dirty && running.len() > 0)) {
             self.printed.insert(pkg.get_package_id());
-            match total_fresh {
-                Fresh => try!(config.shell().verbose(|c| {
-                    c.status("Fresh", pkg)
-                })),
-                Dirty => try!(config.shell().status("Compiling", pkg))
+            match (self.message_format, total_fresh) {
+                (MessageFormat::Json, fresh) => {
+                    try!(emit(config, &CompilingMessage {
+                        reason: "compiling-package",
+                        package_id: pkg.get_package_id(),
+                        fresh: fresh == Fresh,
+                    }))
+                }
+                (MessageFormat::Human, Fresh) => {
+                    try!(config.shell().verbose(|c| c.status("Fresh", pkg)))
+                }
+                (MessageFormat::Human, Dirty) => {
+                    try!(config.shell().status("Compiling", pkg))
+                }
             }
         }
-        for msg in running.iter() {
-            try!(config.shell().verbose(|c| c.status("Running", msg)));
+        if self.message_format == MessageFormat::Human {
+            for msg in running.iter() {
+                try!(config.shell().verbose(|c| c.status("Running", msg)));
+            }
         }
         Ok(())
     }
 }
 
+/// Prints `msg` as a single line of JSON on stdout.
+fn emit<T: Encodable>(config: &Config, msg: &T) -> CargoResult<()> {
+    try!(config.shell().say(json::encode(msg), BLACK));
+    Ok(())
+}
+
 impl<'a> Dependency for (&'a PackageId, Stage) {
     type Context = (&'a Resolve, &'a PackageSet);
 
@@ -244,12 +402,16 @@ impl<'a> Dependency for (&'a PackageId, Stage) {
         // packages (as determined by the resolve context).
         let (id, stage) = *self;
         let pkg = packages.iter().find(|p| p.get_package_id() == id).unwrap();
+        //
+        // Edges from the root of a workspace to members which the root doesn't
+        // list in its manifest only exist to resolve the workspace together,
+        // so they're not dependencies for the purpose of building.
         let deps = resolve.deps(id).into_iter().flat_map(|a| a)
                           .filter(|dep| *dep != id)
-                          .map(|dep| {
-                              (dep, pkg.get_dependencies().iter().find(|d| {
+                          .filter_map(|dep| {
+                              pkg.get_dependencies().iter().find(|d| {
                                   d.get_name() == dep.get_name()
-                              }).unwrap())
+                              }).map(|d| (dep, d))
                           });
         match stage {
             Stage::Start => Vec::new(),
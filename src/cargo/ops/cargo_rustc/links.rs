@@ -1,28 +1,14 @@
-use std::collections::HashMap;
-
 use core::PackageSet;
 use util::{CargoResult, human};
 
-// Validate that there are no duplicated native libraries among packages and
-// that all packages with `links` also have a build script.
+// Validate that all packages with `links` also have a build script. That no
+// two packages link to the same native library is ensured by the resolver.
 pub fn validate(deps: &PackageSet) -> CargoResult<()> {
-    let mut map = HashMap::new();
-
     for dep in deps.iter() {
         let lib = match dep.get_manifest().get_links() {
             Some(lib) => lib,
             None => continue,
         };
-        match map.get(&lib) {
-            Some(previous) => {
-                return Err(human(format!("native library `{}` is being linked \
-                                          to by more than one package, and \
-                                          can only be linked to by one \
-                                          package\n\n  {}\n  {}",
-                                         lib, previous, dep.get_package_id())))
-            }
-            None => {}
-        }
         if !dep.get_manifest().get_targets().iter().any(|t| {
             t.get_profile().is_custom_build()
         }) {
@@ -30,7 +16,6 @@ pub fn validate(deps: &PackageSet) -> CargoResult<()> {
                                       `{}` but does not have a custom build \
                                       script", dep.get_package_id(), lib)))
         }
-        map.insert(lib, dep.get_package_id());
     }
 
     Ok(())
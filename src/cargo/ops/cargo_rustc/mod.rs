@@ -9,9 +9,10 @@ use std::sync::Arc;
 use core::{SourceMap, Package, PackageId, PackageSet, Target, Resolve};
 use util::{self, CargoResult, human, caused_human};
 use util::{Config, internal, ChainError, Fresh, profile, join_paths, Human};
+use util::cfg::Cfg;
 
 use self::job::{Job, Work};
-use self::job_queue::{JobQueue, Stage};
+use self::job_queue::{JobQueue, Stage, Artifact};
 
 pub use self::compilation::Compilation;
 pub use self::context::Context;
@@ -37,6 +38,19 @@ pub enum Kind { Host, Target }
 pub struct BuildConfig {
     pub host: TargetConfig,
     pub target: TargetConfig,
+    pub message_format: MessageFormat,
+}
+
+/// How the progress of a build is reported.
+///
+/// With `Json` every event of the build is printed as a single-line JSON
+/// object on stdout instead of the usual status messages, so that other tools
+/// can follow along.
+#[derive(RustcDecodable, PartialEq, Eq, Clone, Copy, Show)]
+pub enum MessageFormat { Human, Json }
+
+impl Default for MessageFormat {
+    fn default() -> MessageFormat { MessageFormat::Human }
 }
 
 #[derive(Clone, Default)]
@@ -93,6 +107,57 @@ pub fn rustc_new_version() -> CargoResult<(String, String)> {
     Ok((output, triple))
 }
 
+/// Run `rustc` to discover the `cfg` values which are set when compiling for
+/// the target `triple`.
+///
+/// Compilers which don't support `--print cfg` have the values inferred from
+/// the components of the triple instead.
+pub fn rustc_cfg(triple: &str) -> CargoResult<Vec<Cfg>> {
+    let output = try!(util::process("rustc")).arg("--print").arg("cfg")
+                                             .arg("--target").arg(triple)
+                                             .exec_with_output();
+    let output = match output {
+        Ok(output) => output,
+        Err(..) => return Ok(cfg_from_triple(triple)),
+    };
+    let output = try!(String::from_utf8(output.output).map_err(|_| {
+        internal("rustc --print cfg didn't return utf8 output")
+    }));
+    output.as_slice().lines().map(|l| l.trim()).filter(|l| !l.is_empty())
+          .map(|l| Cfg::parse(l)).collect()
+}
+
+fn cfg_from_triple(triple: &str) -> Vec<Cfg> {
+    let arch = triple.split('-').next().unwrap_or("");
+    let arch = match arch {
+        "i386" | "i586" | "i686" => "x86",
+        s if s.starts_with("arm") => "arm",
+        s => s,
+    };
+    let os = ["windows", "darwin", "ios", "android", "linux", "freebsd",
+              "dragonfly", "bitrig", "openbsd"].iter().map(|s| *s).find(|os| {
+        triple.split('-').any(|part| part.starts_with(*os))
+    }).map(|os| if os == "darwin" {"macos"} else {os}).unwrap_or("unknown");
+    let family = if os == "windows" {"windows"} else {"unix"};
+    let env = ["gnu", "msvc", "musl"].iter().map(|s| *s).find(|env| {
+        triple.split('-').skip(1).any(|part| part.starts_with(*env))
+    }).unwrap_or("");
+    let (endian, width) = match arch {
+        "x86_64" | "aarch64" => ("little", "64"),
+        "mips" | "powerpc" => ("big", "32"),
+        _ => ("little", "32"),
+    };
+
+    let pair = |&: k: &str, v: &str| Cfg::KeyPair(k.to_string(), v.to_string());
+    vec![Cfg::Name(family.to_string()),
+         pair("target_family", family),
+         pair("target_os", os),
+         pair("target_arch", arch),
+         pair("target_env", env),
+         pair("target_endian", endian),
+         pair("target_pointer_width", width)]
+}
+
 // This is a temporary assert that ensures the consistency of the arguments
 // given the current limitations of Cargo. The long term fix is to have each
 // Target know the absolute path to the build location.
@@ -113,33 +178,46 @@ fn uniq_target_dest<'a>(targets: &[&'a Target]) -> Option<&'a str> {
 
 // Returns a mapping of the root package plus its immediate dependencies to
 // where the compiled libraries are all located.
-pub fn compile_targets<'a>(env: &str, targets: &[&'a Target], pkg: &'a Package,
+pub fn compile_targets<'a>(env: &str,
+                           pkg_targets: &[(&'a Package, Vec<&'a Target>)],
                            deps: &PackageSet, resolve: &'a Resolve,
                            sources: &'a SourceMap,
                            config: &'a Config<'a>,
                            build_config: BuildConfig,
                            exec_engine: Option<Arc<Box<ExecEngine>>>)
                            -> CargoResult<Compilation> {
-    if targets.is_empty() {
+    let pkg = pkg_targets[0].0;
+    let all_targets = pkg_targets.iter().flat_map(|&(_, ref t)| t.iter())
+                                 .map(|&t| t).collect::<Vec<&Target>>();
+    if all_targets.is_empty() {
         return Ok(Compilation::new(pkg))
     }
 
-    debug!("compile_targets; targets={:?}; pkg={}; deps={:?}", targets, pkg,
-           deps);
+    debug!("compile_targets; targets={:?}; pkgs={:?}; deps={:?}", all_targets,
+           pkg_targets.iter().map(|&(p, _)| p).collect::<Vec<_>>(), deps);
 
     try!(links::validate(deps));
 
-    let dest = uniq_target_dest(targets);
+    let dest = uniq_target_dest(all_targets.as_slice());
     let root = if resolve.root() == pkg.get_package_id() {
         pkg
     } else {
         deps.iter().find(|p| p.get_package_id() == resolve.root()).unwrap()
     };
-    let host_layout = Layout::new(root, None, dest);
-    let target_layout = config.target().map(|target| {
-        layout::Layout::new(root, Some(target), dest)
-    });
+    // Packages whose features differ between the host and the target are
+    // compiled twice, so the host then gets its own directory for them even
+    // when it is also the target.
+    let (host_layout, target_layout) = match config.target() {
+        Some(target) => (Layout::new(root, None, dest),
+                         Some(layout::Layout::new(root, Some(target), dest))),
+        None if !resolve.divergent_packages().is_empty() => {
+            (Layout::new(root, Some("host"), dest),
+             Some(Layout::new(root, None, dest)))
+        }
+        None => (Layout::new(root, None, dest), None),
+    };
 
+    let message_format = build_config.message_format;
     let mut cx = try!(Context::new(env, resolve, sources, deps, config,
                                    host_layout, target_layout, pkg,
                                    build_config));
@@ -147,21 +225,26 @@ pub fn compile_targets<'a>(env: &str, targets: &[&'a Target], pkg: &'a Package,
         cx.exec_engine = exec_engine.clone();
     }
 
-    let mut queue = JobQueue::new(cx.resolve, deps, cx.config);
+    let mut queue = JobQueue::new(cx.resolve, deps, cx.config,
+                                  cx.build_state.clone(), message_format);
 
     // First ensure that the destination directory exists
-    try!(cx.prepare(pkg));
+    for &(pkg, _) in pkg_targets.iter() {
+        try!(cx.prepare(pkg));
+    }
 
     // Build up a list of pending jobs, each of which represent compiling a
     // particular package. No actual work is executed as part of this, that's
     // all done later as part of the `execute` function which will run
     // everything in order with proper parallelism.
     let mut compiled = HashSet::new();
-    each_dep(pkg, &cx, |dep| {
-        compiled.insert(dep.get_package_id().clone());
-    });
+    for &(pkg, _) in pkg_targets.iter() {
+        each_dep(pkg, &cx, |dep| {
+            compiled.insert(dep.get_package_id().clone());
+        });
+    }
     for dep in deps.iter() {
-        if dep == pkg { continue }
+        if pkg_targets.iter().any(|&(pkg, _)| dep == pkg) { continue }
 
         // Only compile lib targets for dependencies
         let targets = dep.get_targets().iter().filter(|target| {
@@ -169,15 +252,20 @@ pub fn compile_targets<'a>(env: &str, targets: &[&'a Target], pkg: &'a Package,
                 cx.is_relevant_target(*target)
         }).collect::<Vec<&Target>>();
 
-        if targets.len() == 0 && dep.get_package_id() != resolve.root() {
+        // Packages which aren't needed for this build (such as the root of a
+        // workspace when building one of its members) may have no library.
+        let compiled = compiled.contains(dep.get_package_id());
+        if targets.len() == 0 && compiled {
             return Err(human(format!("Package `{}` has no library targets", dep)))
         }
 
-        let compiled = compiled.contains(dep.get_package_id());
         try!(compile(targets.as_slice(), dep, compiled, &mut cx, &mut queue));
     }
 
-    try!(compile(targets, pkg, true, &mut cx, &mut queue));
+    for &(pkg, ref targets) in pkg_targets.iter() {
+        try!(compile(targets.as_slice(), pkg, true, &mut cx, &mut queue));
+        cx.compilation.to_doc_test.push(pkg.clone());
+    }
 
     // Clean out any old files sticking around in directories.
     try!(cx.layout(pkg, Kind::Host).proxy().clean());
@@ -195,12 +283,27 @@ pub fn compile_targets<'a>(env: &str, targets: &[&'a Target], pkg: &'a Package,
         });
         if !any_dylib { continue }
         for dir in output.library_paths.iter() {
-            cx.compilation.native_dirs.insert(pkg.clone(), dir.clone());
+            cx.compilation.native_dirs.insert(pkg.clone(), native_dir(dir));
         }
     }
     Ok(cx.compilation)
 }
 
+/// Strips the kind of search path, such as `native=`, which paths passed
+/// through `rustc-link-search` may be prefixed with.
+fn native_dir(path: &Path) -> Path {
+    let s = match path.as_str() {
+        Some(s) => s,
+        None => return path.clone(),
+    };
+    for kind in ["dependency=", "crate=", "native=", "all="].iter() {
+        if s.starts_with(*kind) {
+            return Path::new(s.slice_from(kind.len()))
+        }
+    }
+    path.clone()
+}
+
 fn compile<'a, 'b>(targets: &[&'a Target], pkg: &'a Package,
                    compiled: bool,
                    cx: &mut Context<'a, 'b>,
@@ -227,7 +330,7 @@ fn compile<'a, 'b>(targets: &[&'a Target], pkg: &'a Package,
     // Prepare the fingerprint directory as the first step of building a package
     let (target1, target2) = fingerprint::prepare_init(cx, pkg, Kind::Target);
     let mut init = vec![(Job::new(target1, target2), Fresh)];
-    if cx.config.target().is_some() {
+    if cx.is_host_separate(pkg) {
         let (plugin1, plugin2) = fingerprint::prepare_init(cx, pkg, Kind::Host);
         init.push((Job::new(plugin1, plugin2), Fresh));
     }
@@ -251,17 +354,38 @@ fn compile<'a, 'b>(targets: &[&'a Target], pkg: &'a Package,
         };
 
         // Figure out what stage this work will go into
-        let dst = match (target.is_lib(),
-                         target.get_profile().is_test(),
-                         target.get_profile().is_custom_build()) {
-            (_, _, true) => &mut build_custom,
-            (true, true, _) => &mut lib_tests,
-            (false, true, _) => &mut bin_tests,
-            (true, false, _) => &mut libs,
-            (false, false, _) if target.get_profile().get_env() == "test" => &mut bin_tests,
-            (false, false, _) => &mut bins,
+        let (dst, stage) = match (target.is_lib(),
+                                  target.get_profile().is_test(),
+                                  target.get_profile().is_custom_build()) {
+            (_, _, true) => (&mut build_custom, Stage::BuildCustomBuild),
+            (true, true, _) => (&mut lib_tests, Stage::LibraryTests),
+            (false, true, _) => (&mut bin_tests, Stage::BinaryTests),
+            (true, false, _) => (&mut libs, Stage::Libraries),
+            (false, false, _) if target.get_profile().get_env() == "test" => {
+                (&mut bin_tests, Stage::BinaryTests)
+            }
+            (false, false, _) => (&mut bins, Stage::Binaries),
         };
         for (work, kind) in work.into_iter() {
+            // Remember what this work produces so it can be reported once its
+            // stage has finished. Build scripts and documentation are not
+            // reported as artifacts.
+            if !target.get_profile().is_custom_build() &&
+               !target.get_profile().is_doc() {
+                let root = cx.out_dir(pkg, kind, target);
+                let filenames = try!(cx.target_filenames(target));
+                jobs.artifact(pkg, stage, Artifact {
+                    target: target.get_name().to_string(),
+                    kind: target.rustc_crate_types().iter().map(|s| {
+                        s.to_string()
+                    }).collect(),
+                    profile: target.get_profile().get_env().to_string(),
+                    filenames: filenames.iter().map(|f| {
+                        root.join(f.as_slice()).display().to_string()
+                    }).collect(),
+                });
+            }
+
             let (freshness, dirty, fresh) =
                 try!(fingerprint::prepare_target(cx, pkg, target, kind));
 
@@ -288,7 +412,7 @@ fn compile<'a, 'b>(targets: &[&'a Target], pkg: &'a Package,
             Platform::Target => reqs.push(Platform::Target),
             Platform::Plugin => reqs.push(Platform::Plugin),
             Platform::PluginAndTarget => {
-                if cx.config.target().is_some() {
+                if cx.is_host_separate(pkg) {
                     reqs.push(Platform::Plugin);
                     reqs.push(Platform::Target);
                 } else {
@@ -388,7 +512,7 @@ fn compile_custom_old(pkg: &Package, cmd: &str,
     for arg in cmd {
         p = p.arg(arg);
     }
-    match cx.resolve.features(pkg.get_package_id()) {
+    match cx.features(pkg, Kind::Target) {
         Some(features) => {
             for feat in features.iter() {
                 p = p.env(format!("CARGO_FEATURE_{}",
@@ -495,6 +619,8 @@ fn rustc(package: &Package, target: &Target,
 
             // Only at runtime have we discovered what the extra -L and -l
             // arguments are for native libraries, so we process those here.
+            // The `--cfg` flags and environment variables requested by our
+            // own build script are also only known now.
             {
                 let build_state = build_state.outputs.lock().unwrap();
                 for id in native_lib_deps.into_iter() {
@@ -502,11 +628,19 @@ fn rustc(package: &Package, target: &Target,
                     for path in output.library_paths.iter() {
                         rustc = rustc.arg("-L").arg(path);
                     }
-                    if pass_l_flag && id == current_id {
+                    if id != current_id { continue }
+                    if pass_l_flag {
                         for name in output.library_links.iter() {
                             rustc = rustc.arg("-l").arg(name.as_slice());
                         }
                     }
+                    for cfg in output.cfgs.iter() {
+                        rustc = rustc.arg("--cfg").arg(cfg.as_slice());
+                    }
+                    for &(ref key, ref value) in output.env.iter() {
+                        rustc = rustc.env(key.as_slice(),
+                                          Some(value.as_slice()));
+                    }
                 }
             }
 
@@ -540,6 +674,8 @@ fn prepare_rustc(package: &Package, target: &Target, crate_types: Vec<&str>,
 
     let target_cmd = build_plugin_args(base.clone(), cx, package, target, Kind::Target);
     let plugin_cmd = build_plugin_args(base, cx, package, target, Kind::Host);
+    let target_cmd = build_feature_args(target_cmd, cx, package, Kind::Target);
+    let plugin_cmd = build_feature_args(plugin_cmd, cx, package, Kind::Host);
     let target_cmd = try!(build_deps_args(target_cmd, target, package, cx,
                                           Kind::Target));
     let plugin_cmd = try!(build_deps_args(plugin_cmd, target, package, cx,
@@ -548,7 +684,7 @@ fn prepare_rustc(package: &Package, target: &Target, crate_types: Vec<&str>,
     Ok(match req {
         Platform::Target => vec![(target_cmd, Kind::Target)],
         Platform::Plugin => vec![(plugin_cmd, Kind::Host)],
-        Platform::PluginAndTarget if cx.config.target().is_none() =>
+        Platform::PluginAndTarget if !cx.is_host_separate(package) =>
             vec![(target_cmd, Kind::Target)],
         Platform::PluginAndTarget => vec![(target_cmd, Kind::Target),
                                           (plugin_cmd, Kind::Host)],
@@ -567,7 +703,7 @@ fn rustdoc(package: &Package, target: &Target,
                          .arg("-o").arg(cx_root)
                          .arg("--crate-name").arg(target.get_name());
 
-    match cx.resolve.features(package.get_package_id()) {
+    match cx.features(package, kind) {
         Some(features) => {
             for feat in features.iter() {
                 rustdoc = rustdoc.arg("--cfg").arg(format!("feature=\"{}\"", feat));
@@ -673,15 +809,6 @@ fn build_base_args(cx: &Context,
         cmd = cmd.arg("--test");
     }
 
-    match cx.resolve.features(pkg.get_package_id()) {
-        Some(features) => {
-            for feat in features.iter() {
-                cmd = cmd.arg("--cfg").arg(format!("feature=\"{}\"", feat));
-            }
-        }
-        None => {}
-    }
-
     match metadata {
         Some(m) => {
             cmd = cmd.arg("-C").arg(format!("metadata={}", m.metadata));
@@ -725,6 +852,19 @@ fn build_plugin_args(mut cmd: CommandPrototype, cx: &Context, pkg: &Package,
     return cmd;
 }
 
+fn build_feature_args(mut cmd: CommandPrototype, cx: &Context, pkg: &Package,
+                      kind: Kind) -> CommandPrototype {
+    match cx.features(pkg, kind) {
+        Some(features) => {
+            for feat in features.iter() {
+                cmd = cmd.arg("--cfg").arg(format!("feature=\"{}\"", feat));
+            }
+        }
+        None => {}
+    }
+    cmd
+}
+
 fn build_deps_args(mut cmd: CommandPrototype, target: &Target, package: &Package,
                    cx: &Context,
                    kind: Kind) -> CargoResult<CommandPrototype> {
@@ -732,6 +872,15 @@ fn build_deps_args(mut cmd: CommandPrototype, target: &Target, package: &Package
     cmd = cmd.arg("-L").arg(format!("dependency={}", layout.root().display()));
     cmd = cmd.arg("-L").arg(format!("dependency={}", layout.deps().display()));
 
+    // The packages compiled once for both the host and the target are in the
+    // directory of the target, which is where the host finds them too.
+    if kind == Kind::Host && cx.is_host_separate(package) &&
+       cx.config.target().is_none() {
+        let shared = cx.layout(package, Kind::Target);
+        cmd = cmd.arg("-L").arg(format!("dependency={}",
+                                        shared.deps().display()));
+    }
+
     cmd = cmd.env("OUT_DIR", if package.has_custom_build() {
         Some(layout.build_out(package))
     } else {
@@ -1,7 +1,5 @@
 use std::os;
 
-use core::Source;
-use sources::PathSource;
 use ops::{self, ExecEngine, ProcessEngine};
 use util::{CargoResult, ProcessError};
 
@@ -14,26 +12,24 @@ pub struct TestOptions<'a> {
 pub fn run_tests(manifest_path: &Path,
                  options: &mut TestOptions,
                  test_args: &[String]) -> CargoResult<Option<ProcessError>> {
-    let mut source = try!(PathSource::for_path(&manifest_path.dir_path()));
-    try!(source.update());
-
     let mut compile = try!(ops::compile(manifest_path, &mut options.compile_opts));
     if options.no_run { return Ok(None) }
-    compile.tests.sort();
+    compile.tests.sort_by(|&(_, ref a, ref a_exe), &(_, ref b, ref b_exe)| {
+        (a, a_exe).cmp(&(b, b_exe))
+    });
 
     let target_name = options.name;
-    let mut tests_to_run = compile.tests.iter().filter(|&&(ref test_name, _)| {
+    let mut tests_to_run = compile.tests.iter().filter(|&&(_, ref test_name, _)| {
         target_name.map_or(true, |target_name| target_name == test_name.as_slice())
     });
 
     let cwd = try!(os::getcwd());
-    for &(_, ref exe) in tests_to_run {
+    for &(ref pkg, _, ref exe) in tests_to_run {
         let to_display = match exe.path_relative_from(&cwd) {
             Some(path) => path,
             None => exe.clone(),
         };
-        let cmd = try!(compile.target_process(exe, &compile.package))
-                  .args(test_args);
+        let cmd = try!(compile.target_process(exe, pkg)).args(test_args);
         try!(options.compile_opts.shell.concise(|shell| {
             shell.status("Running", to_display.display().to_string())
         }));
@@ -50,21 +46,23 @@ pub fn run_tests(manifest_path: &Path,
 
     if options.compile_opts.env == "bench" { return Ok(None) }
 
-    let mut libs = compile.package.get_targets().iter().filter_map(|target| {
-        if !target.get_profile().is_doctest() || !target.is_lib() {
-            return None
-        }
-        Some((target.get_src_path(), target.get_name()))
+    let mut libs = compile.to_doc_test.iter().flat_map(|package| {
+        package.get_targets().iter().filter_map(move |target| {
+            if !target.get_profile().is_doctest() || !target.is_lib() {
+                return None
+            }
+            Some((package, target.get_src_path(), target.get_name()))
+        })
     });
 
-    for (lib, name) in libs {
+    for (package, lib, name) in libs {
         try!(options.compile_opts.shell.status("Doc-tests", name));
-        let mut p = try!(compile.rustdoc_process(&compile.package))
+        let mut p = try!(compile.rustdoc_process(package))
                            .arg("--test").arg(lib)
                            .arg("--crate-name").arg(name)
                            .arg("-L").arg(&compile.root_output)
                            .arg("-L").arg(&compile.deps_output)
-                           .cwd(compile.package.get_root());
+                           .cwd(package.get_root());
 
         // FIXME(rust-lang/rust#16272): this should just always be passed.
         if test_args.len() > 0 {
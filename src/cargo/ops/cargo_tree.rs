@@ -0,0 +1,189 @@
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry::{Occupied, Vacant};
+use term::color::BLACK;
+
+use core::{MultiShell, Package, PackageId, Workspace};
+use core::dependency::Kind;
+use core::registry::PackageRegistry;
+use core::resolver::Resolve;
+use ops;
+use util::{CargoResult, ChainError, Config, Locked, human};
+
+pub struct TreeOptions<'a> {
+    pub manifest_path: &'a Path,
+    /// Print the packages depending on the package matching this spec
+    /// instead of the dependencies of the workspace.
+    pub invert: Option<&'a str>,
+    /// Print the packages depending on each package of which more than one
+    /// version is present in the graph.
+    pub duplicates: bool,
+    /// True if `Cargo.lock` must not be modified.
+    pub locked: Locked,
+    /// True if the network must not be accessed.
+    pub offline: bool,
+}
+
+/// The dependency edges between the resolved packages, in both directions.
+///
+/// Each edge carries the kinds of the dependencies which caused it, so a
+/// package listing another one both as a normal and a build dependency has
+/// a single edge with two kinds.
+struct Graph<'a> {
+    forward: HashMap<&'a PackageId, Vec<(&'a PackageId, Vec<Kind>)>>,
+    reverse: HashMap<&'a PackageId, Vec<(&'a PackageId, Vec<Kind>)>>,
+}
+
+/// Prints the dependency graph of the workspace containing `manifest_path`
+/// as an indented tree.
+///
+/// Packages which have already been printed along with their dependencies
+/// are marked with `(*)` instead of being expanded again.
+pub fn tree(opts: &TreeOptions, shell: &mut MultiShell) -> CargoResult<()> {
+    let ws = try!(Workspace::new(opts.manifest_path));
+    let mut config = try!(Config::new(shell, None, None));
+    config.set_locked(opts.locked);
+    config.set_offline(opts.offline);
+    let mut registry = PackageRegistry::new(&config);
+    let resolve = try!(ops::resolve_pkg(&mut registry, ws.root()));
+
+    let ids = resolve.iter().map(|id| id.clone()).collect::<Vec<PackageId>>();
+    let packages = try!(registry.get(ids.as_slice()).chain_error(|| {
+        human("Unable to get packages from source")
+    }));
+    let graph = Graph::new(&resolve, packages.as_slice());
+
+    let (roots, inverted) = if let Some(spec) = opts.invert {
+        (vec![try!(resolve.query(spec))], true)
+    } else if opts.duplicates {
+        (duplicates(&resolve), true)
+    } else {
+        (resolve.iter().filter(|id| {
+            ws.default_packages().iter().any(|p| p.get_package_id() == *id)
+        }).collect(), false)
+    };
+
+    let mut lines = Vec::new();
+    for (i, root) in roots.iter().enumerate() {
+        if i > 0 { lines.push(String::new()); }
+        let mut visited = HashSet::new();
+        graph.print(*root, None, inverted, &mut Vec::new(), &mut visited,
+                    &mut lines);
+    }
+    for line in lines.iter() {
+        try!(config.shell().say(line.as_slice(), BLACK));
+    }
+    Ok(())
+}
+
+/// Returns all packages sharing their name with another package of the graph.
+fn duplicates(resolve: &Resolve) -> Vec<&PackageId> {
+    let mut by_name = HashMap::new();
+    for id in resolve.iter() {
+        match by_name.entry(id.get_name()) {
+            Occupied(mut e) => e.get_mut().push(id),
+            Vacant(e) => { e.insert(vec![id]); }
+        }
+    }
+    let mut ret = by_name.into_iter().filter(|&(_, ref ids)| ids.len() > 1)
+                         .flat_map(|(_, ids)| ids.into_iter())
+                         .collect::<Vec<&PackageId>>();
+    ret.sort();
+    ret
+}
+
+impl<'a> Graph<'a> {
+    fn new(resolve: &'a Resolve, packages: &'a [Package]) -> Graph<'a> {
+        let mut graph = Graph {
+            forward: HashMap::new(),
+            reverse: HashMap::new(),
+        };
+        for pkg in packages.iter() {
+            let id = pkg.get_package_id();
+            let deps = match resolve.deps(id) {
+                Some(deps) => deps,
+                None => continue,
+            };
+            for dep in deps.filter(|dep| *dep != id) {
+                let mut kinds = Vec::new();
+                for d in pkg.get_dependencies().iter() {
+                    if d.get_name() == dep.get_name() &&
+                       !kinds.contains(&d.get_kind()) {
+                        kinds.push(d.get_kind());
+                    }
+                }
+                // Edges from the root of a workspace to members which it
+                // doesn't depend on don't correspond to any dependency.
+                if kinds.is_empty() { continue }
+
+                add_edge(&mut graph.forward, id, dep, kinds.clone());
+                add_edge(&mut graph.reverse, dep, id, kinds);
+            }
+        }
+        for edges in graph.forward.values_mut() {
+            edges.sort_by(|a, b| a.0.cmp(b.0));
+        }
+        for edges in graph.reverse.values_mut() {
+            edges.sort_by(|a, b| a.0.cmp(b.0));
+        }
+        return graph;
+
+        fn add_edge<'a>(edges: &mut HashMap<&'a PackageId,
+                                            Vec<(&'a PackageId, Vec<Kind>)>>,
+                        from: &'a PackageId, to: &'a PackageId,
+                        kinds: Vec<Kind>) {
+            match edges.entry(from) {
+                Occupied(mut e) => e.get_mut().push((to, kinds)),
+                Vacant(e) => { e.insert(vec![(to, kinds)]); }
+            }
+        }
+    }
+
+    /// Appends the tree rooted at `id` to `lines`.
+    ///
+    /// `levels` records for each ancestor whether it was the last child of
+    /// its parent, which determines the indentation.
+    fn print(&self, id: &'a PackageId, kinds: Option<&[Kind]>, inverted: bool,
+             levels: &mut Vec<bool>, visited: &mut HashSet<&'a PackageId>,
+             lines: &mut Vec<String>) {
+        let mut line = String::new();
+        if let Some(&last) = levels.last() {
+            for &ancestor_last in levels.slice_to(levels.len() - 1).iter() {
+                line.push_str(if ancestor_last {"    "} else {"|   "});
+            }
+            line.push_str(if last {"`-- "} else {"|-- "});
+        }
+        line.push_str(id.to_string().as_slice());
+        match kinds {
+            Some(kinds) if kinds.len() > 1 || kinds[0] != Kind::Normal => {
+                let kinds = kinds.iter().map(|k| match *k {
+                    Kind::Normal => "normal",
+                    Kind::Development => "dev",
+                    Kind::Build => "build",
+                }).collect::<Vec<&str>>();
+                line.push_str(format!(" ({})", kinds.connect(", ")).as_slice());
+            }
+            _ => {}
+        }
+
+        let edges = if inverted {
+            self.reverse.get(&id)
+        } else {
+            self.forward.get(&id)
+        };
+        let no_edges = Vec::new();
+        let edges = edges.unwrap_or(&no_edges);
+        if !visited.insert(id) && edges.len() > 0 {
+            line.push_str(" (*)");
+            lines.push(line);
+            return
+        }
+        lines.push(line);
+
+        for (i, &(dep, ref kinds)) in edges.iter().enumerate() {
+            levels.push(i == edges.len() - 1);
+            self.print(dep, Some(kinds.as_slice()), inverted, levels, visited,
+                       lines);
+            levels.pop();
+        }
+    }
+}
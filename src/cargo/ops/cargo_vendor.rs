@@ -0,0 +1,174 @@
+use std::collections::{HashMap, BTreeMap};
+use std::io::{self, File};
+use std::io::fs::{self, PathExtensions};
+use std::os;
+
+use rustc_serialize::json;
+use term::color::BLACK;
+
+use core::{MultiShell, Workspace, Package, PackageId, SourceId, Source};
+use core::GitReference;
+use core::registry::PackageRegistry;
+use ops;
+use sources::PathSource;
+use sources::directory::{self, Checksum, CHECKSUM_FILE};
+use util::{CargoResult, ChainError, Config, Locked, human};
+
+/// The name `cargo vendor` suggests for the replacement source.
+static VENDOR_SOURCE: &'static str = "vendored-sources";
+
+/// Copies every non-path dependency of the workspace containing
+/// `manifest_path` into `destination`, one directory per package. A relative
+/// `destination` is relative to the current directory.
+///
+/// Each copied package gets a checksum file listing its files so it can be
+/// used through a directory source. The returned string is the configuration
+/// which replaces all vendored sources with `destination`.
+pub fn vendor(manifest_path: &Path, destination: &Path, locked: Locked,
+              offline: bool, shell: &mut MultiShell) -> CargoResult<String> {
+    let ws = try!(Workspace::new(manifest_path));
+    let mut config = try!(Config::new(shell, None, None));
+    config.set_locked(locked);
+    config.set_offline(offline);
+    let mut registry = PackageRegistry::new(&config);
+    let resolve = try!(ops::resolve_pkg(&mut registry, ws.root()));
+    let dst_root = try!(os::getcwd()).join(destination);
+
+    let ids = resolve.iter().filter(|id| {
+        !id.get_source_id().is_path()
+    }).map(|id| id.clone()).collect::<Vec<PackageId>>();
+    let packages = try!(registry.get(ids.as_slice()).chain_error(|| {
+        human("unable to get packages from source")
+    }));
+
+    try!(fs::mkdir_recursive(&dst_root, io::USER_DIR));
+    let mut vendored: HashMap<Path, &PackageId> = HashMap::new();
+    let mut sources = Vec::new();
+    for pkg in packages.iter() {
+        let id = pkg.get_package_id();
+        let dst = dst_root.join(format!("{}-{}", id.get_name(),
+                                           id.get_version()));
+        // A directory source can only hold one package of a given name and
+        // version, wherever it came from.
+        if let Some(other) = vendored.get(&dst) {
+            return Err(human(format!("`{}` and `{}` would both be vendored \
+                                      to `{}`, packages of the same name and \
+                                      version from different sources can't \
+                                      be vendored together", other, id,
+                                     dst.display())))
+        }
+        vendored.insert(dst.clone(), id);
+        if !sources.contains(id.get_source_id()) {
+            sources.push(id.get_source_id().clone());
+        }
+
+        // Already vendored packages may be read from the destination itself
+        // when their source is replaced with it.
+        if dst == pkg.get_root() { continue }
+
+        try!(config.shell().status("Vendoring", id));
+        try!(vendor_package(pkg, resolve.checksum(id), &dst).chain_error(|| {
+            human(format!("failed to vendor `{}`", id))
+        }));
+    }
+
+    // Get rid of packages which were vendored previously but are no longer
+    // used, leaving any other files alone.
+    for dir in try!(fs::readdir(&dst_root)).iter() {
+        if vendored.contains_key(dir) || !dir.join(CHECKSUM_FILE).exists() {
+            continue
+        }
+        try!(fs::rmdir_recursive(dir));
+    }
+
+    sources.sort();
+    replacement_config(sources.as_slice(), destination)
+}
+
+/// Prints `config`, as returned by `vendor`, along with how to use it.
+pub fn print_vendor_config(config: &str,
+                           shell: &mut MultiShell) -> CargoResult<()> {
+    try!(shell.say("To use vendored sources, add this to your .cargo/config \
+                    for this project:\n", BLACK));
+    try!(shell.say(config.trim_right(), BLACK));
+    Ok(())
+}
+
+fn vendor_package(pkg: &Package, checksum: Option<&str>, dst: &Path)
+                  -> CargoResult<()> {
+    if dst.exists() {
+        try!(fs::rmdir_recursive(dst));
+    }
+
+    let mut src = PathSource::new(&pkg.get_root(),
+                                  pkg.get_package_id().get_source_id());
+    try!(src.update());
+    let root = pkg.get_root();
+    let source = pkg.get_package_id().get_source_id().with_precise(None);
+    let mut cksum = Checksum {
+        package: checksum.map(|s| s.to_string()),
+        source: Some(source.to_url()),
+        files: BTreeMap::new(),
+    };
+    for file in try!(src.list_files(pkg)).iter() {
+        let relative = file.path_relative_from(&root).unwrap();
+        let name = try!(relative.as_str().chain_error(|| {
+            human(format!("non-utf8 path in package: `{}`", file.display()))
+        }));
+        let target = dst.join(&relative);
+        try!(fs::mkdir_recursive(&target.dir_path(), io::USER_DIR));
+        try!(fs::copy(file, &target).chain_error(|| {
+            human(format!("failed to copy `{}` to `{}`", file.display(),
+                          target.display()))
+        }));
+        cksum.files.insert(name.replace("\\", "/"),
+                           try!(directory::file_checksum(&target)));
+    }
+
+    let contents = json::encode(&cksum);
+    try!(File::create(&dst.join(CHECKSUM_FILE)).write_str(contents.as_slice()));
+    Ok(())
+}
+
+fn replacement_config(sources: &[SourceId], destination: &Path)
+                      -> CargoResult<String> {
+    let central = try!(SourceId::for_central());
+    let mut out = String::new();
+    for id in sources.iter() {
+        if *id == central {
+            out.push_str("[source.crates-io]\n");
+        } else if id.is_registry() {
+            out.push_str(format!("[source.\"{}\"]\n", id.get_url()).as_slice());
+            out.push_str(format!("registry = \"{}\"\n",
+                                 id.get_url()).as_slice());
+        } else if id.is_local_registry() {
+            let path = try!(id.get_url().to_file_path().map_err(|()| {
+                human(format!("invalid local registry `{}`", id.get_url()))
+            }));
+            out.push_str(format!("[source.\"{}\"]\n", id.get_url()).as_slice());
+            out.push_str(format!("local-registry = \"{}\"\n",
+                                 path.display()).as_slice());
+        } else if id.is_git() {
+            out.push_str(format!("[source.\"{}\"]\n", id.get_url()).as_slice());
+            out.push_str(format!("git = \"{}\"\n", id.get_url()).as_slice());
+            let reference = match id.git_reference() {
+                Some(&GitReference::Branch(ref b)) if b.as_slice() != "master" => {
+                    Some(("branch", b))
+                }
+                Some(&GitReference::Tag(ref t)) => Some(("tag", t)),
+                Some(&GitReference::Rev(ref r)) => Some(("rev", r)),
+                _ => None,
+            };
+            if let Some((key, value)) = reference {
+                out.push_str(format!("{} = \"{}\"\n", key, value).as_slice());
+            }
+        } else {
+            continue
+        }
+        out.push_str(format!("replace-with = \"{}\"\n\n",
+                             VENDOR_SOURCE).as_slice());
+    }
+    out.push_str(format!("[source.{}]\ndirectory = \"{}\"\n", VENDOR_SOURCE,
+                         destination.display()).as_slice());
+    Ok(out)
+}
@@ -1,10 +1,11 @@
+use std::collections::HashSet;
 use std::io::File;
 
 use rustc_serialize::{Encodable, Decodable};
 use toml::{self, Encoder, Value};
 
-use core::{Resolve, resolver, Package, SourceId};
-use util::CargoResult;
+use core::{Resolve, resolver, Package, PackageId, SourceId};
+use util::{CargoResult, ChainError, Config, human};
 use util::toml as cargo_toml;
 
 pub fn load_pkg_lockfile(pkg: &Package) -> CargoResult<Option<Resolve>> {
@@ -34,6 +35,85 @@ pub fn write_pkg_lockfile(pkg: &Package, resolve: &Resolve) -> CargoResult<()> {
 }
 
 pub fn write_lockfile(dst: &Path, resolve: &Resolve) -> CargoResult<()> {
+    try!(File::create(dst).write_str(serialize_resolve(resolve).as_slice()));
+    Ok(())
+}
+
+/// Writes `resolve` to the lock file of `pkg`, unless `config` says the lock
+/// file is locked, in which case it's an error for `resolve` to differ from
+/// `previous`, the resolve loaded from that lock file.
+pub fn record_pkg_lockfile(config: &Config, pkg: &Package,
+                           previous: Option<&Resolve>,
+                           resolve: &Resolve) -> CargoResult<()> {
+    match config.lock_flag() {
+        Some(flag) => check_pkg_lockfile(pkg, previous, resolve, flag),
+        None => write_pkg_lockfile(pkg, resolve),
+    }
+}
+
+/// Checks that `resolve` is what the lock file of `pkg` already contains,
+/// for when it must not be modified.
+///
+/// `previous` is the resolve loaded from that lock file, which is used to
+/// describe what would change otherwise, and `flag` is the command line flag
+/// which prevented the modification.
+pub fn check_pkg_lockfile(pkg: &Package, previous: Option<&Resolve>,
+                          resolve: &Resolve, flag: &str) -> CargoResult<()> {
+    let loc = pkg.get_root().join("Cargo.lock");
+    let previous = try!(previous.chain_error(|| {
+        human(format!("the lock file {} needs to be generated but {} \
+                       was passed to prevent this", loc.display(), flag))
+    }));
+    if previous.same_lockfile(resolve) { return Ok(()) }
+
+    let mut msg = format!("the lock file {} needs to be updated but {} \
+                           was passed to prevent this", loc.display(), flag);
+    let changes = diff_resolves(previous, resolve);
+    if changes.is_empty() {
+        msg.push_str("\nno package would be added or removed, but the \
+                      revisions, checksums or metadata would change");
+    } else {
+        msg.push_str("\nchanges:");
+        for change in changes.iter() {
+            msg.push_str(format!("\n  {}", change).as_slice());
+        }
+    }
+    Err(human(msg))
+}
+
+/// Lists the packages which are only in one of `previous` and `resolve`,
+/// prefixed with `-` or `+`, as well as the packages whose set of
+/// dependencies changed, prefixed with `~`.
+fn diff_resolves(previous: &Resolve, resolve: &Resolve) -> Vec<String> {
+    let old = previous.iter().collect::<HashSet<&PackageId>>();
+    let new = resolve.iter().collect::<HashSet<&PackageId>>();
+    let mut ids = old.union(&new).map(|id| *id).collect::<Vec<&PackageId>>();
+    ids.sort();
+
+    let mut ret = Vec::new();
+    for id in ids.into_iter() {
+        if !new.contains(id) {
+            ret.push(format!("- {}", id));
+        } else if !old.contains(id) {
+            ret.push(format!("+ {}", id));
+        } else if dep_names(previous, id) != dep_names(resolve, id) {
+            ret.push(format!("~ {} (its dependencies changed)", id));
+        }
+    }
+    return ret;
+
+    fn dep_names(resolve: &Resolve, id: &PackageId) -> Vec<String> {
+        let mut names = resolve.deps(id).map(|deps| {
+            deps.filter(|dep| *dep != id).map(|dep| {
+                format!("{} ({})", dep.get_name(), dep.get_source_id())
+            }).collect::<Vec<String>>()
+        }).unwrap_or(Vec::new());
+        names.sort();
+        names
+    }
+}
+
+fn serialize_resolve(resolve: &Resolve) -> String {
     let mut e = Encoder::new();
     resolve.encode(&mut e).unwrap();
 
@@ -58,13 +138,15 @@ pub fn write_lockfile(dst: &Path, resolve: &Resolve) -> CargoResult<()> {
     match e.toml.get(&"metadata".to_string()) {
         Some(metadata) => {
             out.push_str("[metadata]\n");
-            out.push_str(metadata.to_string().as_slice());
+            for (k, v) in metadata.as_table().unwrap().iter() {
+                out.push_str(format!("{} = {}\n", emit_key(k.as_slice()),
+                                     v).as_slice());
+            }
         }
         None => {}
     }
 
-    try!(File::create(dst).write_str(out.as_slice()));
-    Ok(())
+    out
 }
 
 fn emit_package(dep: &toml::Table, out: &mut String) {
@@ -91,6 +173,19 @@ fn emit_package(dep: &toml::Table, out: &mut String) {
     }
 }
 
+/// Quotes `key` if it can't be written as a bare key, as is the case for the
+/// keys recording checksums which contain spaces.
+fn emit_key(key: &str) -> String {
+    let bare = !key.is_empty() && key.chars().all(|c| {
+        c.is_alphanumeric() || c == '_' || c == '-'
+    });
+    if bare {
+        key.to_string()
+    } else {
+        Value::String(key.to_string()).to_string()
+    }
+}
+
 fn lookup<'a>(table: &'a toml::Table, key: &str) -> &'a toml::Value {
     table.get(&key.to_string()).expect(format!("Didn't find {}", key).as_slice())
 }
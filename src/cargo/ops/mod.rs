@@ -1,10 +1,12 @@
-pub use self::cargo_clean::{clean, CleanOptions};
-pub use self::cargo_compile::{compile, compile_pkg, CompileOptions};
+pub use self::cargo_add::{add, remove, AddOptions};
+pub use self::cargo_clean::{clean, CleanOptions, gc, GcOptions};
+pub use self::cargo_compile::{compile, compile_pkg, compile_ws, CompileOptions};
 pub use self::cargo_read_manifest::{read_manifest,read_package,read_packages};
 pub use self::cargo_rustc::{compile_targets, Compilation, Layout, Kind, rustc_version};
+pub use self::cargo_rustc::rustc_cfg;
 pub use self::cargo_rustc::{Context, LayoutProxy};
 pub use self::cargo_rustc::Platform;
-pub use self::cargo_rustc::{BuildOutput, BuildConfig, TargetConfig};
+pub use self::cargo_rustc::{BuildOutput, BuildConfig, TargetConfig, MessageFormat};
 pub use self::cargo_rustc::{CommandType, CommandPrototype, ExecEngine, ProcessEngine};
 pub use self::cargo_run::run;
 pub use self::cargo_new::{new, NewOptions, VersionControl};
@@ -13,28 +15,43 @@ pub use self::cargo_generate_lockfile::{generate_lockfile};
 pub use self::cargo_generate_lockfile::{update_lockfile};
 pub use self::cargo_generate_lockfile::UpdateOptions;
 pub use self::lockfile::{load_lockfile, load_pkg_lockfile};
-pub use self::lockfile::{write_lockfile, write_pkg_lockfile};
+pub use self::lockfile::{write_lockfile, write_pkg_lockfile, check_pkg_lockfile};
+pub use self::lockfile::record_pkg_lockfile;
 pub use self::cargo_test::{run_tests, run_benches, TestOptions};
+pub use self::cargo_tree::{tree, TreeOptions};
 pub use self::cargo_package::package;
 pub use self::registry::{publish, registry_configuration, RegistryConfig};
 pub use self::registry::{registry_login, search, http_proxy, http_handle};
+pub use self::registry::{http_max_downloads, net_retry};
 pub use self::registry::{modify_owners, yank, OwnersOptions};
 pub use self::cargo_fetch::{fetch};
+pub use self::cargo_install::{install, uninstall};
+pub use self::cargo_metadata::{output_metadata, OutputMetadataOptions, ExportInfo};
+pub use self::cargo_outdated::{outdated, print_outdated, OutdatedOptions};
+pub use self::cargo_outdated::OutdatedReport;
 pub use self::cargo_pkgid::pkgid;
+pub use self::cargo_vendor::{vendor, print_vendor_config};
 pub use self::resolve::{resolve_pkg, resolve_with_previous};
+pub use self::resolve::{yanked_packages, warn_yanked};
 
+mod cargo_add;
 mod cargo_clean;
 mod cargo_compile;
 mod cargo_doc;
 mod cargo_fetch;
 mod cargo_generate_lockfile;
+mod cargo_install;
+mod cargo_metadata;
 mod cargo_new;
+mod cargo_outdated;
 mod cargo_package;
 mod cargo_pkgid;
 mod cargo_read_manifest;
 mod cargo_run;
 mod cargo_rustc;
 mod cargo_test;
+mod cargo_tree;
+mod cargo_vendor;
 mod lockfile;
 mod registry;
 mod resolve;
@@ -3,6 +3,7 @@ use std::io::File;
 use std::io::fs::PathExtensions;
 use std::iter::repeat;
 use std::os;
+use std::u32;
 
 use curl::http;
 use git2;
@@ -17,7 +18,7 @@ use ops;
 use sources::{PathSource, RegistrySource};
 use util::config;
 use util::{CargoResult, human, internal, ChainError, ToUrl};
-use util::config::{Config, ConfigValue, Location};
+use util::config::{Config, ConfigValue, Location, Locked};
 use util::important_paths::find_root_manifest_for_cwd;
 
 pub struct RegistryConfig {
@@ -29,21 +30,26 @@ pub fn publish(manifest_path: &Path,
                shell: &mut MultiShell,
                token: Option<String>,
                index: Option<String>,
-               verify: bool) -> CargoResult<()> {
+               registry_name: Option<String>,
+               verify: bool,
+               locked: Locked,
+               offline: bool) -> CargoResult<()> {
     let mut src = try!(PathSource::for_path(&manifest_path.dir_path()));
     try!(src.update());
     let pkg = try!(src.get_root_package());
 
-    let (mut registry, reg_id) = try!(registry(shell, token, index));
+    let (mut registry, reg_id) = try!(registry(shell, token, index,
+                                               registry_name));
     try!(verify_dependencies(&pkg, &reg_id));
 
     // Prepare a tarball, with a non-surpressable warning if metadata
     // is missing since this is being put online.
-    let tarball = try!(ops::package(manifest_path, shell, verify, false, true)).unwrap();
+    let tarball = try!(ops::package(manifest_path, shell, verify, false, true,
+                                    locked, offline, Some(&reg_id))).unwrap();
 
     // Upload said tarball to the specified destination
     try!(shell.status("Uploading", pkg.get_package_id().to_string()));
-    try!(transmit(&pkg, &tarball, &mut registry));
+    try!(transmit(&pkg, &tarball, &reg_id, &mut registry));
 
     Ok(())
 }
@@ -59,19 +65,40 @@ fn verify_dependencies(pkg: &Package, registry_src: &SourceId)
                                           dependency `{}` does not specify \
                                           a version", dep.get_name())))
             }
-        } else if dep.get_source_id() != registry_src {
-            return Err(human(format!("all dependencies must come from the \
-                                      same source.\ndependency `{}` comes \
+        } else if !dep.get_source_id().is_registry() {
+            return Err(human(format!("all dependencies must come from a \
+                                      registry.\ndependency `{}` comes \
                                       from {} instead", dep.get_name(),
                                      dep.get_source_id())))
+        } else if dep.get_source_id() != registry_src &&
+                  registry_src.get_url().to_string() ==
+                      RegistrySource::default_url() {
+            // Dependencies from other registries are fine in private
+            // registries, but crates.io must be self-contained.
+            return Err(human(format!("crates cannot be published to \
+                                      crates.io with dependencies from \
+                                      other registries.\ndependency `{}` \
+                                      comes from {}", dep.get_name(),
+                                     dep.get_source_id())))
         }
     }
     Ok(())
 }
 
-fn transmit(pkg: &Package, tarball: &Path, registry: &mut Registry)
-            -> CargoResult<()> {
+/// Uploads `tarball` to `registry`, whose index is `registry_src`.
+///
+/// The dependencies coming from other registries record the URL of their
+/// index, which ends up in the index entry of the package.
+fn transmit(pkg: &Package, tarball: &Path, registry_src: &SourceId,
+            registry: &mut Registry) -> CargoResult<()> {
     let deps = pkg.get_dependencies().iter().map(|dep| {
+        // Path dependencies are uploaded to the same registry.
+        let dep_registry = if dep.get_source_id().is_registry() &&
+                              dep.get_source_id() != registry_src {
+            Some(dep.get_source_id().get_url().to_string())
+        } else {
+            None
+        };
         NewCrateDependency {
             optional: dep.is_optional(),
             default_features: dep.uses_default_features(),
@@ -84,6 +111,7 @@ fn transmit(pkg: &Package, tarball: &Path, registry: &mut Registry)
                 Kind::Build => "build",
                 Kind::Development => "dev",
             }.to_string(),
+            registry: dep_registry,
         }
     }).collect::<Vec<NewCrateDependency>>();
     let manifest = pkg.get_manifest();
@@ -128,12 +156,28 @@ fn transmit(pkg: &Package, tarball: &Path, registry: &mut Registry)
     })
 }
 
-pub fn registry_configuration() -> CargoResult<RegistryConfig> {
+/// Reads the configuration of the default registry from the `registry`
+/// table, or of the registry called `name` from the `registries.<name>` table.
+pub fn registry_configuration(name: Option<&str>)
+                              -> CargoResult<RegistryConfig> {
     let configs = try!(config::all_configs(try!(os::getcwd())));
-    let registry = match configs.get("registry") {
+    let registry = match name {
+        Some(name) => {
+            let registries = match configs.get("registries") {
+                Some(registries) => try!(registries.table().chain_error(|| {
+                    internal("invalid configuration for the key `registries`")
+                })),
+                None => return Ok(RegistryConfig { index: None, token: None }),
+            };
+            registries.get(name)
+        }
+        None => configs.get("registry"),
+    };
+    let registry = match registry {
         None => return Ok(RegistryConfig { index: None, token: None }),
         Some(registry) => try!(registry.table().chain_error(|| {
-            internal("invalid configuration for the key `registry`")
+            internal(format!("invalid configuration for the key `{}`",
+                             registry_key(name)))
         })),
     };
     let index = match registry.get("index") {
@@ -155,17 +199,37 @@ pub fn registry_configuration() -> CargoResult<RegistryConfig> {
     Ok(RegistryConfig { index: index, token: token })
 }
 
+fn registry_key(name: Option<&str>) -> String {
+    match name {
+        Some(name) => format!("registries.{}", name),
+        None => "registry".to_string(),
+    }
+}
+
+/// Connects to the API of the registry whose index is `index`, or of the
+/// registry called `registry_name` in the configuration, or else of the
+/// default registry.
 pub fn registry(shell: &mut MultiShell,
                 token: Option<String>,
-                index: Option<String>) -> CargoResult<(Registry, SourceId)> {
+                index: Option<String>,
+                registry_name: Option<String>)
+                -> CargoResult<(Registry, SourceId)> {
     // Parse all configuration options
+    let name = registry_name.as_ref().map(|s| s.as_slice());
     let RegistryConfig {
         token: token_config,
-        index: index_config,
-    } = try!(registry_configuration());
+        index: _,
+    } = try!(registry_configuration(name));
     let token = token.or(token_config);
-    let index = index.or(index_config).unwrap_or(RegistrySource::default_url());
-    let index = try!(index.as_slice().to_url().map_err(human));
+    let index = match (index, name) {
+        (Some(..), Some(..)) => {
+            return Err(human("both an index and a registry name were given, \
+                              only one of them may be used"))
+        }
+        (Some(index), None) => try!(index.as_slice().to_url().map_err(human)),
+        (None, Some(name)) => try!(RegistrySource::named_url(name)),
+        (None, None) => try!(RegistrySource::url()),
+    };
     let sid = SourceId::for_registry(&index);
     let api_host = {
         let mut config = try!(Config::new(shell, None, None));
@@ -180,11 +244,118 @@ pub fn registry(shell: &mut MultiShell,
 }
 
 /// Create a new HTTP handle with appropriate global configuration for cargo.
+///
+/// Besides the proxy, this honors the `http.timeout`,
+/// `http.low-speed-limit`, `http.cainfo` and `http.check-revoke`
+/// configuration keys. Git fetches over HTTP use these handles as well.
 pub fn http_handle() -> CargoResult<http::Handle> {
-    Ok(match try!(http_proxy()) {
+    let mut handle = match try!(http_proxy()) {
         Some(proxy) => http::handle().proxy(proxy),
         None => http::handle(),
-    })
+    };
+
+    // A transfer is aborted once it has been slower than the low speed limit
+    // for as long as the timeout, which also bounds connecting.
+    let timeout = try!(positive_integer("http", "timeout"))
+                      .unwrap_or(DEFAULT_TIMEOUT);
+    let low_speed_limit = try!(positive_integer("http", "low-speed-limit"))
+                              .unwrap_or(DEFAULT_LOW_SPEED_LIMIT);
+    handle = handle.connect_timeout((timeout as usize).saturating_mul(1000))
+                   .low_speed_timeout(timeout as usize)
+                   .low_speed_limit(low_speed_limit as usize);
+
+    if let Some(cainfo) = try!(config_value("http", "cainfo")) {
+        let (cainfo, _) = try!(cainfo.string().chain_error(|| {
+            internal("invalid configuration for key `http.cainfo`")
+        }));
+        handle = handle.ssl_ca_info(&Path::new(cainfo));
+    }
+    if let Some(check) = try!(config_value("http", "check-revoke")) {
+        let (check, _) = try!(check.boolean().chain_error(|| {
+            internal("invalid configuration for key `http.check-revoke`")
+        }));
+        handle = handle.ssl_no_revoke(!check);
+    }
+    Ok(handle)
+}
+
+/// The number of seconds a transfer may stall for when none is configured.
+const DEFAULT_TIMEOUT: u32 = 30;
+
+/// The speed in bytes per second under which a transfer is considered to be
+/// stalled when none is configured.
+const DEFAULT_LOW_SPEED_LIMIT: u32 = 10;
+
+/// The number of packages downloaded at the same time when none is
+/// configured.
+const DEFAULT_MAX_DOWNLOADS: u32 = 4;
+
+/// The number of times network operations are retried when none is
+/// configured. Failures such as authentication errors can't be told apart
+/// from spurious ones, so retrying has to be asked for.
+const DEFAULT_RETRY: u32 = 0;
+
+/// Find how many packages may be downloaded at the same time, from the
+/// `http.max-downloads` configuration key.
+pub fn http_max_downloads() -> CargoResult<u32> {
+    Ok(try!(positive_integer("http", "max-downloads"))
+           .unwrap_or(DEFAULT_MAX_DOWNLOADS))
+}
+
+/// Find how many times a network operation which failed is retried, from
+/// the `net.retry` configuration key.
+pub fn net_retry() -> CargoResult<u32> {
+    match try!(config_value("net", "retry")) {
+        Some(retry) => {
+            let (retry, path) = try!(retry.integer().chain_error(|| {
+                internal("invalid configuration for key `net.retry`")
+            }));
+            if retry < 0 {
+                return Err(human(format!("`net.retry` can't be negative, \
+                                          found {} in {}", retry,
+                                         path.display())))
+            }
+            if retry > u32::MAX as i64 {
+                return Err(human(format!("`net.retry` is too large, found {} \
+                                          in {}", retry, path.display())))
+            }
+            Ok(retry as u32)
+        }
+        None => Ok(DEFAULT_RETRY),
+    }
+}
+
+/// Returns the value of `key` in the `table` table of the configuration.
+fn config_value(table: &str, key: &str) -> CargoResult<Option<ConfigValue>> {
+    let configs = try!(config::all_configs(try!(os::getcwd())));
+    let value = match configs.get(table) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let table = try!(value.table().chain_error(|| {
+        internal(format!("invalid configuration for the key `{}`", table))
+    }));
+    Ok(table.get(key).map(|v| v.clone()))
+}
+
+/// Returns the value of `table.key`, which must be an integer of at least 1.
+fn positive_integer(table: &str, key: &str) -> CargoResult<Option<u32>> {
+    let value = match try!(config_value(table, key)) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let (n, path) = try!(value.integer().chain_error(|| {
+        internal(format!("invalid configuration for key `{}.{}`", table, key))
+    }));
+    if n < 1 {
+        return Err(human(format!("`{}.{}` must be at least 1, found {} in {}",
+                                 table, key, n, path.display())))
+    }
+    if n > u32::MAX as i64 {
+        return Err(human(format!("`{}.{}` is too large, found {} in {}",
+                                 table, key, n, path.display())))
+    }
+    Ok(Some(n as u32))
 }
 
 /// Find a globally configured HTTP proxy if one is available.
@@ -221,9 +392,13 @@ pub fn http_proxy() -> CargoResult<Option<String>> {
     Ok(os::getenv("HTTP_PROXY"))
 }
 
-pub fn registry_login(shell: &mut MultiShell, token: String) -> CargoResult<()> {
+pub fn registry_login(shell: &mut MultiShell, token: String,
+                      registry_name: Option<String>) -> CargoResult<()> {
     let config = try!(Config::new(shell, None, None));
-    let RegistryConfig { index, token: _ } = try!(registry_configuration());
+    if let Some(name) = registry_name {
+        return named_registry_login(&config, token, name.as_slice())
+    }
+    let RegistryConfig { index, token: _ } = try!(registry_configuration(None));
     let mut map = HashMap::new();
     let p = try!(os::getcwd());
     match index {
@@ -238,10 +413,40 @@ pub fn registry_login(shell: &mut MultiShell, token: String) -> CargoResult<()>
                        ConfigValue::Table(map))
 }
 
+/// Saves `token` in the `registries.<name>` table, keeping the other entries
+/// of the table.
+fn named_registry_login(config: &Config, token: String,
+                        name: &str) -> CargoResult<()> {
+    // Make sure the registry is known before saving a token for it.
+    try!(RegistrySource::named_url(name));
+
+    let configs = try!(config::all_configs(try!(os::getcwd())));
+    let mut registries = match configs.get("registries") {
+        Some(registries) => try!(registries.table().chain_error(|| {
+            internal("invalid configuration for the key `registries`")
+        })).clone(),
+        None => HashMap::new(),
+    };
+    let mut entry = match registries.remove(name) {
+        Some(entry) => try!(entry.table().chain_error(|| {
+            internal(format!("invalid configuration for the key \
+                              `registries.{}`", name))
+        })).clone(),
+        None => HashMap::new(),
+    };
+    let p = try!(os::getcwd());
+    entry.insert("token".to_string(), ConfigValue::String(token, p));
+    registries.insert(name.to_string(), ConfigValue::Table(entry));
+
+    config::set_config(config, Location::Global, "registries",
+                       ConfigValue::Table(registries))
+}
+
 pub struct OwnersOptions {
     pub krate: Option<String>,
     pub token: Option<String>,
     pub index: Option<String>,
+    pub registry: Option<String>,
     pub to_add: Option<Vec<String>>,
     pub to_remove: Option<Vec<String>>,
     pub list: bool,
@@ -261,7 +466,8 @@ pub fn modify_owners(shell: &mut MultiShell,
     };
 
     let (mut registry, _) = try!(registry(shell, opts.token.clone(),
-                                          opts.index.clone()));
+                                          opts.index.clone(),
+                                          opts.registry.clone()));
 
     match opts.to_add {
         Some(ref v) => {
@@ -309,6 +515,7 @@ pub fn yank(shell: &mut MultiShell,
             version: Option<String>,
             token: Option<String>,
             index: Option<String>,
+            registry_name: Option<String>,
             undo: bool) -> CargoResult<()> {
     let name = match krate {
         Some(name) => name,
@@ -325,7 +532,7 @@ pub fn yank(shell: &mut MultiShell,
         None => return Err(human("a version must be specified to yank"))
     };
 
-    let (mut registry, _) = try!(registry(shell, token, index));
+    let (mut registry, _) = try!(registry(shell, token, index, registry_name));
 
     if undo {
         try!(shell.status("Unyank", format!("{}:{}", name, version)));
@@ -342,7 +549,8 @@ pub fn yank(shell: &mut MultiShell,
     Ok(())
 }
 
-pub fn search(query: &str, shell: &mut MultiShell, index: Option<String>) -> CargoResult<()> {
+pub fn search(query: &str, shell: &mut MultiShell, index: Option<String>,
+              registry_name: Option<String>) -> CargoResult<()> {
     fn truncate_with_ellipsis(s: &str, max_length: usize) -> String {
         if s.len() < max_length {
             s.to_string()
@@ -351,7 +559,7 @@ pub fn search(query: &str, shell: &mut MultiShell, index: Option<String>) -> Car
         }
     }
 
-    let (mut registry, _) = try!(registry(shell, None, index));
+    let (mut registry, _) = try!(registry(shell, None, index, registry_name));
 
     let crates = try!(registry.search(query).map_err(|e| {
         human(format!("failed to retrieve search results from the registry: {}", e))
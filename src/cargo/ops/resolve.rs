@@ -1,23 +1,29 @@
 use std::collections::{HashMap, HashSet};
 
-use core::{Package, PackageId, SourceId};
+use core::{Dependency, Package, PackageId, Registry, SourceId};
 use core::registry::PackageRegistry;
-use core::resolver::{self, Resolve, Method};
+use core::resolver::{self, Resolve, Method, Strategy};
 use ops;
 use util::CargoResult;
 
 /// Resolve all dependencies for the specified `package` using the previous
 /// lockfile as a guide if present.
 ///
+/// A warning is printed for each locked package which has been yanked from
+/// its registry since it was locked.
+///
 /// This function will also generate a write the result of resolution as a new
-/// lockfile.
+/// lockfile, unless the configuration says the lockfile is locked in which
+/// case it is an error for the resolution to differ from it.
 pub fn resolve_pkg(registry: &mut PackageRegistry, package: &Package)
                    -> CargoResult<Resolve> {
     let prev = try!(ops::load_pkg_lockfile(package));
-    let resolve = try!(resolve_with_previous(registry, package,
-                                             Method::Everything,
+    let method = Method::Everything(Strategy::Newest);
+    let resolve = try!(resolve_with_previous(registry, package, method,
                                              prev.as_ref(), None));
-    try!(ops::write_pkg_lockfile(package, &resolve));
+    try!(warn_yanked(registry, &resolve));
+    try!(ops::record_pkg_lockfile(registry.config(), package, prev.as_ref(),
+                                  &resolve));
     Ok(resolve)
 }
 
@@ -51,7 +57,7 @@ pub fn resolve_with_previous<'a>(registry: &mut PackageRegistry,
         Some(set) => {
             for package_id in set.iter() {
                 let source = package_id.get_source_id();
-                if !source.is_registry() {
+                if !source.is_registry() && !source.is_local_registry() {
                     to_avoid_sources.insert(source);
                 }
             }
@@ -107,7 +113,10 @@ pub fn resolve_with_previous<'a>(registry: &mut PackageRegistry,
 
     let mut resolved = try!(resolver::resolve(&summary, method, registry));
     match previous {
-        Some(r) => resolved.copy_metadata(r),
+        Some(r) => {
+            try!(resolved.check_checksums(r));
+            resolved.copy_metadata(r);
+        }
         None => {}
     }
     return Ok(resolved);
@@ -122,3 +131,36 @@ pub fn resolve_with_previous<'a>(registry: &mut PackageRegistry,
         }
     }
 }
+
+/// Returns the packages of `resolve` which have been yanked from the registry
+/// they come from.
+///
+/// Only what is already known about the registries is used, so a registry
+/// which hasn't been updated may not know about the latest yanks.
+pub fn yanked_packages<'a>(registry: &mut PackageRegistry,
+                           resolve: &'a Resolve)
+                           -> CargoResult<Vec<&'a PackageId>> {
+    let mut ret = Vec::new();
+    for id in resolve.iter().filter(|id| {
+        id.get_source_id().is_registry() || id.get_source_id().is_local_registry()
+    }) {
+        let dep = try!(Dependency::parse(id.get_name(), None,
+                                         id.get_source_id())).lock_to(id);
+        if try!(registry.query_yanked(&dep)).len() > 0 {
+            ret.push(id);
+        }
+    }
+    ret.sort();
+    Ok(ret)
+}
+
+/// Prints a warning for each package of `resolve` which has been yanked.
+pub fn warn_yanked(registry: &mut PackageRegistry, resolve: &Resolve)
+                   -> CargoResult<()> {
+    for id in try!(yanked_packages(registry, resolve)).iter() {
+        try!(registry.config().shell().warn(format!("warning: package `{}` \
+                                                     in Cargo.lock has been \
+                                                     yanked", id)));
+    }
+    Ok(())
+}
@@ -0,0 +1,173 @@
+//! Implementation of the `[source]` table of `.cargo/config`.
+//!
+//! Each entry of the table defines a named source, and may state that it is to
+//! be replaced by another named source:
+//!
+//! ```toml
+//! [source.crates-io]
+//! replace-with = "vendored"
+//!
+//! [source.vendored]
+//! directory = "vendor"
+//! ```
+//!
+//! Sources are defined with one of the `registry`, `local-registry`,
+//! `directory` or `git` keys, except for `crates-io` which always refers to the
+//! central registry.
+
+use std::collections::{HashMap, BTreeMap};
+
+use url::Url;
+
+use core::{SourceId, GitReference};
+use util::config::ConfigValue;
+use util::{CargoResult, ChainError, ToUrl, human};
+
+/// The set of sources that have been replaced, along with what they have been
+/// replaced with.
+pub struct SourceReplacements {
+    replacements: Vec<(SourceId, SourceId)>,
+}
+
+struct SourceConfig {
+    id: SourceId,
+    replace_with: Option<(String, Path)>,
+}
+
+impl SourceReplacements {
+    /// Returns a set of replacements which leaves every source alone.
+    pub fn empty() -> SourceReplacements {
+        SourceReplacements { replacements: Vec::new() }
+    }
+
+    /// Reads the `source` table out of a set of configuration values.
+    pub fn from_configs(configs: &HashMap<String, ConfigValue>)
+                        -> CargoResult<SourceReplacements> {
+        let table = match configs.get("source") {
+            Some(table) => try!(table.table().chain_error(|| {
+                human("invalid configuration for the key `source`")
+            })),
+            None => return Ok(SourceReplacements::empty()),
+        };
+
+        // Sorted so that errors are reported consistently.
+        let mut sources = BTreeMap::new();
+        for (name, value) in table.iter() {
+            let config = try!(source_config(name.as_slice(), value));
+            sources.insert(name.clone(), config);
+        }
+        if !sources.contains_key("crates-io") {
+            sources.insert("crates-io".to_string(), SourceConfig {
+                id: try!(SourceId::for_central()),
+                replace_with: None,
+            });
+        }
+
+        let mut replacements = Vec::new();
+        for (name, config) in sources.iter() {
+            if config.replace_with.is_none() { continue }
+            let replace_with = try!(follow(name.as_slice(), &sources));
+            replacements.push((config.id.clone(), replace_with));
+        }
+        Ok(SourceReplacements { replacements: replacements })
+    }
+
+    /// Returns the source that `id` has been replaced with, if any.
+    pub fn get(&self, id: &SourceId) -> Option<&SourceId> {
+        self.replacements.iter().find(|&&(ref from, _)| from == id)
+            .map(|&(_, ref to)| to)
+    }
+}
+
+fn source_config(name: &str, value: &ConfigValue) -> CargoResult<SourceConfig> {
+    let table = try!(value.table().chain_error(|| {
+        human(format!("invalid configuration for the key `source.{}`", name))
+    }));
+    let get = |&: key: &str| -> CargoResult<Option<(String, Path)>> {
+        match table.get(key) {
+            Some(v) => {
+                let (s, path) = try!(v.string().chain_error(|| {
+                    human(format!("invalid configuration for the key \
+                                   `source.{}.{}`", name, key))
+                }));
+                Ok(Some((s.to_string(), path.clone())))
+            }
+            None => Ok(None),
+        }
+    };
+
+    let mut ids = Vec::new();
+    if let Some((registry, _)) = try!(get("registry")) {
+        ids.push(SourceId::for_registry(&try!(url(name, registry.as_slice()))));
+    }
+    if let Some((dir, path)) = try!(get("directory")) {
+        // Relative directories are relative to the directory containing the
+        // `.cargo` directory in which the configuration was found.
+        let dir = path.dir_path().dir_path().join(dir);
+        ids.push(try!(SourceId::for_directory(&dir)));
+    }
+    if let Some((dir, path)) = try!(get("local-registry")) {
+        let dir = path.dir_path().dir_path().join(dir);
+        ids.push(try!(SourceId::for_local_registry(&dir)));
+    }
+    if let Some((git, _)) = try!(get("git")) {
+        let reference = if let Some((branch, _)) = try!(get("branch")) {
+            GitReference::Branch(branch)
+        } else if let Some((tag, _)) = try!(get("tag")) {
+            GitReference::Tag(tag)
+        } else if let Some((rev, _)) = try!(get("rev")) {
+            GitReference::Rev(rev)
+        } else {
+            GitReference::Branch("master".to_string())
+        };
+        let url = try!(url(name, git.as_slice()));
+        ids.push(SourceId::for_git(&url, reference));
+    }
+
+    let id = match (name, ids.len()) {
+        ("crates-io", 0) => try!(SourceId::for_central()),
+        (_, 1) => ids.pop().unwrap(),
+        (_, 0) => return Err(human(format!("no source URL specified for \
+                                            `source.{}`, need one of \
+                                            `registry`, `local-registry`, \
+                                            `directory` or `git` defined",
+                                           name))),
+        (_, _) => return Err(human(format!("more than one source URL \
+                                            specified for `source.{}`",
+                                           name))),
+    };
+    Ok(SourceConfig { id: id, replace_with: try!(get("replace-with")) })
+}
+
+/// Follows the chain of `replace-with` keys starting at the source `name`,
+/// returning the source which ends up being used in its place.
+fn follow(name: &str, sources: &BTreeMap<String, SourceConfig>)
+          -> CargoResult<SourceId> {
+    let mut seen = vec![name];
+    let mut current = sources.get(name).unwrap();
+    loop {
+        let (next, path) = match current.replace_with {
+            Some((ref next, ref path)) => (next.as_slice(), path),
+            None => return Ok(current.id.clone()),
+        };
+        if seen.contains(&next) {
+            return Err(human(format!("detected a cycle of `replace-with` \
+                                      sources, the source `{}` is eventually \
+                                      replaced with itself (configuration in \
+                                      `{}`)", next, path.display())))
+        }
+        current = try!(sources.get(next).chain_error(|| {
+            human(format!("could not find a configured source with the name \
+                           `{}` when attempting to lookup `{}` (configuration \
+                           in `{}`)", next, seen[seen.len() - 1],
+                          path.display()))
+        }));
+        seen.push(next);
+    }
+}
+
+fn url(name: &str, s: &str) -> CargoResult<Url> {
+    s.to_url().map_err(|e| {
+        human(format!("invalid URL `{}` for `source.{}`: {}", s, name, e))
+    })
+}
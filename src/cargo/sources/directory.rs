@@ -0,0 +1,168 @@
+//! A `Source` for a local directory of vendored packages.
+//!
+//! Each subdirectory holds the unpacked contents of one package, as written by
+//! `cargo vendor`, along with a `.cargo-checksum.json` file listing a SHA-256
+//! checksum for every file of the package. The checksums are verified before a
+//! package is handed out, as vendored sources are not meant to be modified in
+//! place.
+
+use std::collections::{HashMap, BTreeMap};
+use std::fmt::{self, Show, Formatter};
+use std::io::File;
+use std::io::fs::{self, PathExtensions};
+
+use rustc_serialize::hex::ToHex;
+use rustc_serialize::json;
+
+use core::{Package, PackageId, Summary, SourceId, Source, Dependency, Registry};
+use ops;
+use util::{CargoResult, ChainError, human, Sha256};
+
+/// The name of the file listing the checksums of a vendored package.
+pub static CHECKSUM_FILE: &'static str = ".cargo-checksum.json";
+
+pub struct DirectorySource {
+    id: SourceId,
+    root: Path,
+    packages: HashMap<PackageId, (Package, Checksum)>,
+}
+
+/// The contents of the checksum file of a vendored package.
+#[derive(RustcEncodable, RustcDecodable)]
+pub struct Checksum {
+    /// Checksum of the `.crate` file the package was unpacked from, if it was
+    /// downloaded from a registry.
+    pub package: Option<String>,
+    /// The source the package was vendored from, as a source id url. Path
+    /// dependencies of the package are taken from that source, just like
+    /// they were when it was vendored.
+    pub source: Option<String>,
+    /// Checksums of all files of the package, keyed by their path relative to
+    /// the root of the package.
+    pub files: BTreeMap<String, String>,
+}
+
+impl DirectorySource {
+    pub fn new(path: &Path, id: &SourceId) -> DirectorySource {
+        DirectorySource {
+            id: id.clone(),
+            root: path.clone(),
+            packages: HashMap::new(),
+        }
+    }
+}
+
+impl Show for DirectorySource {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "DirectorySource {{ root: {} }}", self.root.display())
+    }
+}
+
+impl Registry for DirectorySource {
+    fn query(&mut self, dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        let mut summaries = self.packages.values().map(|&(ref p, _)| {
+            p.get_summary().clone()
+        }).collect::<Vec<_>>();
+        summaries.query(dep)
+    }
+}
+
+impl Source for DirectorySource {
+    fn update(&mut self) -> CargoResult<()> {
+        self.packages.clear();
+        let entries = try!(fs::readdir(&self.root).chain_error(|| {
+            human(format!("failed to read the vendored sources in `{}`",
+                          self.root.display()))
+        }));
+
+        // Vendored packages may still list the path dependencies they were
+        // developed with. Those were taken from the same source as the
+        // package itself, such as a sibling crate of the same git repository
+        // or the registry the package was published to. Packages vendored
+        // before their source was recorded can only have come from crates.io.
+        let central = try!(SourceId::for_central());
+        for dir in entries.iter() {
+            let manifest = dir.join("Cargo.toml");
+            if !manifest.exists() { continue }
+
+            let (pkg, _) = try!(ops::read_package(&manifest, &self.id));
+            let path = dir.join(CHECKSUM_FILE);
+            let cksum = try!(File::open(&path).read_to_string().chain_error(|| {
+                human(format!("failed to load checksum `{}` of vendored \
+                               package `{}`", path.display(),
+                              pkg.get_package_id()))
+            }));
+            let cksum: Checksum = try!(json::decode(cksum.as_slice()));
+
+            let origin = match cksum.source {
+                Some(ref url) => SourceId::from_url(url.clone()),
+                None => central.clone(),
+            };
+            let mut summary = pkg.get_summary().clone().map_dependencies(|d| {
+                if !d.get_source_id().is_path() { return d }
+                d.source_id(origin.clone())
+            });
+            // The checksum of the original `.crate` is what gets recorded in
+            // lockfiles, so that a vendored copy can be checked against them.
+            if let Some(ref package) = cksum.package {
+                summary = summary.set_checksum(package.clone());
+            }
+            let mut manifest = pkg.get_manifest().clone();
+            manifest.set_summary(summary);
+            let pkg = Package::new(manifest, pkg.get_manifest_path(), &self.id);
+
+            self.packages.insert(pkg.get_package_id().clone(), (pkg, cksum));
+        }
+        Ok(())
+    }
+
+    fn download(&mut self, ids: &[PackageId]) -> CargoResult<()> {
+        for id in ids.iter() {
+            if *id.get_source_id() != self.id { continue }
+            let pair = self.packages.get(id);
+            let &(ref pkg, ref cksum) = try!(pair.chain_error(|| {
+                human(format!("failed to find package `{}` in `{}`", id,
+                              self.root.display()))
+            }));
+
+            let root = pkg.get_root();
+            for (file, expected) in cksum.files.iter() {
+                let actual = try!(file_checksum(&root.join(file.as_slice())));
+                if actual != *expected {
+                    return Err(human(format!("the listed checksum of `{}` has \
+                                              changed:\nexpected: {}\n\
+                                              actual:   {}\n\n\
+                                              directory sources are not \
+                                              intended to be edited, if \
+                                              modifications are required then \
+                                              a copy of the package should be \
+                                              listed in `paths` instead",
+                                             root.join(file.as_slice())
+                                                 .display(),
+                                             expected, actual)))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn get(&self, ids: &[PackageId]) -> CargoResult<Vec<Package>> {
+        Ok(ids.iter().filter_map(|id| self.packages.get(id)).map(|&(ref p, _)| {
+            p.clone()
+        }).collect())
+    }
+
+    fn fingerprint(&self, pkg: &Package) -> CargoResult<String> {
+        Ok(pkg.get_package_id().get_version().to_string())
+    }
+}
+
+/// Returns the hex encoded SHA-256 checksum of the file at `path`.
+pub fn file_checksum(path: &Path) -> CargoResult<String> {
+    let contents = try!(File::open(path).read_to_end().chain_error(|| {
+        human(format!("failed to read `{}`", path.display()))
+    }));
+    let mut state = Sha256::new();
+    state.update(contents.as_slice());
+    Ok(state.finish().as_slice().to_hex())
+}
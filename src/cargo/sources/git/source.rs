@@ -6,7 +6,7 @@ use url::{self, Url};
 use core::source::{Source, SourceId};
 use core::GitReference;
 use core::{Package, PackageId, Summary, Registry, Dependency};
-use util::{CargoResult, Config, to_hex};
+use util::{CargoResult, Config, human, mark_used, network, to_hex};
 use sources::PathSource;
 use sources::git::utils::{GitRemote, GitRevision};
 
@@ -168,12 +168,27 @@ impl<'a, 'b> Source for GitSource<'a, 'b> {
         let should_update = actual_rev.is_err() ||
                             self.source_id.get_precise().is_none();
 
-        let (repo, actual_rev) = if should_update {
+        // When offline, the database is used as it was last fetched.
+        if self.config.offline() && actual_rev.is_err() {
+            let reference = match self.reference {
+                GitReference::Branch(ref s) |
+                GitReference::Tag(ref s) |
+                GitReference::Rev(ref s) => s.as_slice(),
+            };
+            return Err(human(format!("git repository `{}` needs to be fetched \
+                                      to find `{}`, which can't be done while \
+                                      offline", self.remote.get_url(),
+                                     reference)))
+        }
+
+        let (repo, actual_rev) = if should_update && !self.config.offline() {
             try!(self.config.shell().status("Updating",
                 format!("git repository `{}`", self.remote.get_url())));
 
             log!(5, "updating git source `{:?}`", self.remote);
-            let repo = try!(self.remote.checkout(&self.db_path));
+            let repo = try!(network::with_retry(self.config, || {
+                self.remote.checkout(&self.db_path)
+            }));
             let rev = try!(repo.rev_for(&self.reference));
             (repo, rev)
         } else {
@@ -181,6 +196,8 @@ impl<'a, 'b> Source for GitSource<'a, 'b> {
         };
 
         try!(repo.copy_to(actual_rev.clone(), &self.checkout_path));
+        mark_used(&self.db_path);
+        mark_used(&self.checkout_path);
 
         let source_id = self.source_id.with_precise(Some(actual_rev.to_string()));
         let path_source = PathSource::new(&self.checkout_path, &source_id);
@@ -0,0 +1,141 @@
+//! A smart HTTP transport for libgit2 which makes its requests with curl, so
+//! that git repositories are fetched with the same `[http]` configuration as
+//! registries: the proxy, the timeouts and the TLS settings.
+
+use std::io::{self, IoError, IoResult, MemReader};
+use std::sync::{Once, ONCE_INIT};
+
+use git2;
+use git2::transport::{self, Service, SmartSubtransport};
+use git2::transport::{SmartSubtransportStream, Transport};
+
+use ops;
+
+/// Makes libgit2 use curl for `http` and `https` URLs. This only needs to
+/// happen once per process, before the first fetch.
+pub fn register() {
+    static INIT: Once = ONCE_INIT;
+    INIT.call_once(|| unsafe {
+        // Registering only fails for a prefix which already has a custom
+        // transport, and nothing else registers these.
+        transport::register("http", factory).unwrap();
+        transport::register("https", factory).unwrap();
+    });
+}
+
+fn factory(remote: &git2::Remote) -> Result<Transport, git2::Error> {
+    Transport::smart(remote, true, CurlTransport)
+}
+
+struct CurlTransport;
+
+impl SmartSubtransport for CurlTransport {
+    fn action(&self, url: &str, action: Service)
+              -> Result<Box<SmartSubtransportStream>, git2::Error> {
+        let (service, path, post) = match action {
+            Service::UploadPackLs => {
+                ("upload-pack", "/info/refs?service=git-upload-pack", false)
+            }
+            Service::UploadPack => ("upload-pack", "/git-upload-pack", true),
+            Service::ReceivePackLs => {
+                ("receive-pack", "/info/refs?service=git-receive-pack", false)
+            }
+            Service::ReceivePack => ("receive-pack", "/git-receive-pack", true),
+        };
+        Ok(Box::new(CurlSubtransport {
+            url: format!("{}{}", url.trim_right_matches('/'), path),
+            service: service,
+            post: post,
+            response: None,
+        }) as Box<SmartSubtransportStream>)
+    }
+
+    fn close(&self) -> Result<(), git2::Error> {
+        Ok(())
+    }
+}
+
+/// A single request of the smart protocol. A `GET` is sent on the first
+/// read, and a `POST` on the first write with the written data as its body.
+struct CurlSubtransport {
+    url: String,
+    service: &'static str,
+    post: bool,
+    response: Option<MemReader>,
+}
+
+impl CurlSubtransport {
+    fn execute(&mut self, data: &[u8]) -> IoResult<()> {
+        let mut handle = try!(ops::http_handle().map_err(|e| {
+            err(e.to_string())
+        }));
+        let request_type = format!("application/x-git-{}-request",
+                                   self.service);
+        let accept = format!("application/x-git-{}-result", self.service);
+        let req = if self.post {
+            handle.post(self.url.as_slice(), data)
+                  .content_type(request_type.as_slice())
+                  .header("Accept", accept.as_slice())
+        } else {
+            handle.get(self.url.as_slice()).header("Accept", "*/*")
+        };
+        let resp = try!(req.header("User-Agent", "git/1.0 (cargo)")
+                           .follow_redirects(true)
+                           .exec()
+                           .map_err(|e| {
+            err(format!("failed to fetch `{}`: {}", self.url, e))
+        }));
+
+        if resp.get_code() != 200 {
+            return Err(err(format!("failed to fetch `{}`, status code: {}",
+                                   self.url, resp.get_code())))
+        }
+
+        // A server which doesn't speak the smart protocol answers with the
+        // wrong content type, which is better reported than misparsed.
+        let expected = if self.post {
+            accept
+        } else {
+            format!("application/x-git-{}-advertisement", self.service)
+        };
+        let content_type = resp.get_header("content-type").first()
+                               .map(|s| s.as_slice().trim().to_string());
+        if content_type.as_ref().map(|s| s.as_slice()) !=
+           Some(expected.as_slice()) {
+            return Err(err(format!("`{}` is not a smart git repository, \
+                                    expected content type `{}` but found {}",
+                                   self.url, expected,
+                                   content_type.map(|s| format!("`{}`", s))
+                                               .unwrap_or("none".to_string()))))
+        }
+
+        self.response = Some(MemReader::new(resp.move_body()));
+        Ok(())
+    }
+}
+
+impl Reader for CurlSubtransport {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        if self.response.is_none() {
+            try!(self.execute(&[]));
+        }
+        self.response.as_mut().unwrap().read(buf)
+    }
+}
+
+impl Writer for CurlSubtransport {
+    fn write(&mut self, data: &[u8]) -> IoResult<()> {
+        if self.response.is_none() {
+            try!(self.execute(data));
+        }
+        Ok(())
+    }
+}
+
+fn err(detail: String) -> IoError {
+    IoError {
+        kind: io::OtherIoError,
+        desc: "unknown error",
+        detail: Some(detail),
+    }
+}
@@ -6,6 +6,8 @@ use url::Url;
 use git2::{self, ObjectType};
 
 use core::GitReference;
+use ops;
+use sources::git::transport;
 use util::{CargoResult, ChainError, human, ToUrl, internal};
 
 #[derive(PartialEq, Clone, Show)]
@@ -394,6 +396,14 @@ fn with_authentication<T, F>(url: &str, cfg: &git2::Config, mut f: F)
 pub fn fetch(repo: &git2::Repository, url: &str,
              refspec: &str) -> CargoResult<()> {
     // Create a local anonymous remote in the repository to fetch the url
+    //
+    // HTTP requests go through curl with the `[http]` configuration. The
+    // configuration is checked here as well, since errors from within the
+    // transport only reach us as a message from libgit2.
+    if url.starts_with("http://") || url.starts_with("https://") {
+        try!(ops::http_handle());
+        transport::register();
+    }
 
     with_authentication(url, &try!(repo.config()), |f| {
         let mut cb = git2::RemoteCallbacks::new();
@@ -0,0 +1,121 @@
+//! A `Source` for a registry laid out in a local directory.
+//!
+//! A local registry is a directory with the `.crate` tarballs of the packages
+//! it contains, named `<pkg>-<version>.crate`, next to an `index` directory
+//! following the format of the index of a remote registry (see
+//! `sources::registry`):
+//!
+//! ```notrust
+//! .
+//! ├── index
+//! │   ├── 3
+//! │   │   └── u
+//! │   │       └── url
+//! │   └── li
+//! │       └── bg
+//! │           └── libgit2
+//! ├── libgit2-0.1.0.crate
+//! └── url-0.2.0.crate
+//! ```
+//!
+//! Nothing is ever fetched from the network, so the whole registry can be
+//! copied to a machine without access to the remote one. The tarballs are
+//! checked against the checksums listed in the index before being unpacked.
+
+use std::io::File;
+use std::io::fs::PathExtensions;
+
+use core::{Source, SourceId, PackageId, Package, Summary, Registry};
+use core::dependency::Dependency;
+use sources::PathSource;
+use sources::registry::{RegistryIndex, unpack_package};
+use util::{CargoResult, Config, ChainError, human, internal};
+use util::{hex, mark_used};
+
+pub struct LocalRegistrySource {
+    source_id: SourceId,
+    root: Path,
+    src_path: Path,
+    index: RegistryIndex,
+    sources: Vec<PathSource>,
+}
+
+impl LocalRegistrySource {
+    pub fn new(root: &Path, source_id: &SourceId,
+               config: &Config) -> LocalRegistrySource {
+        let hash = hex::short_hash(source_id);
+        let ident = root.filename_str().unwrap_or("local");
+        let part = format!("{}-{}", ident, hash);
+        LocalRegistrySource {
+            source_id: source_id.clone(),
+            root: root.clone(),
+            src_path: config.registry_source_path().join(part.as_slice()),
+            index: RegistryIndex::new(source_id, &root.join("index")),
+            sources: Vec::new(),
+        }
+    }
+
+    /// Path to the tarball of `pkg` in the registry.
+    fn crate_path(&self, pkg: &PackageId) -> Path {
+        let filename = format!("{}-{}.crate", pkg.get_name(), pkg.get_version());
+        self.root.join(filename)
+    }
+}
+
+impl Registry for LocalRegistrySource {
+    fn query(&mut self, dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        self.index.query(dep)
+    }
+
+    fn query_yanked(&mut self, dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        self.index.query_yanked(dep)
+    }
+}
+
+impl Source for LocalRegistrySource {
+    fn update(&mut self) -> CargoResult<()> {
+        if !self.root.join("index").is_dir() {
+            return Err(human(format!("local registry path is not a directory \
+                                      with an index: {}",
+                                     self.root.display())))
+        }
+        self.index.clear();
+        Ok(())
+    }
+
+    fn download(&mut self, packages: &[PackageId]) -> CargoResult<()> {
+        for package in packages.iter() {
+            if self.source_id != *package.get_source_id() { continue }
+
+            let tarball = self.crate_path(package);
+            let contents = try!(File::open(&tarball).read_to_end()
+                                    .chain_error(|| {
+                human(format!("failed to read `{}` for package `{}`",
+                              tarball.display(), package))
+            }));
+            try!(self.index.verify(package, contents.as_slice()));
+
+            let path = try!(unpack_package(package, &tarball, &self.src_path)
+                                .chain_error(|| {
+                internal(format!("Failed to unpack package `{}`", package))
+            }));
+            mark_used(&path);
+            let mut src = PathSource::new(&path, &self.source_id);
+            try!(src.update());
+            self.sources.push(src);
+        }
+        Ok(())
+    }
+
+    fn get(&self, packages: &[PackageId]) -> CargoResult<Vec<Package>> {
+        let mut ret = Vec::new();
+        for src in self.sources.iter() {
+            ret.extend(try!(src.get(packages)).into_iter());
+        }
+        return Ok(ret);
+    }
+
+    fn fingerprint(&self, pkg: &Package) -> CargoResult<String> {
+        Ok(pkg.get_package_id().get_version().to_string())
+    }
+}
@@ -1,7 +1,14 @@
 pub use self::path::PathSource;
 pub use self::git::GitSource;
 pub use self::registry::RegistrySource;
+pub use self::directory::DirectorySource;
+pub use self::local_registry::LocalRegistrySource;
+pub use self::replaced::ReplacedSource;
 
+pub mod config;
+pub mod directory;
 pub mod path;
 pub mod git;
+pub mod local_registry;
 pub mod registry;
+pub mod replaced;
@@ -109,6 +109,19 @@
 //! modifications to this file that should happen over time are yanks of a
 //! particular version.
 //!
+//! ## Sparse indexes
+//!
+//! Fetching the whole git repository of an index is wasteful when only a few
+//! crates are needed. An index whose URL is prefixed with `sparse+`, such as
+//! `sparse+https://example.com/index/`, is instead served over plain HTTP with
+//! the same layout: `config.json` is fetched when the registry is updated, and
+//! the file of each crate is fetched the first time the crate is queried.
+//!
+//! The fetched files are kept in the same place as the checkout of a git
+//! index, along with the `ETag` and `Last-Modified` headers they were served
+//! with. These are sent back in conditional requests so that a file which
+//! hasn't changed isn't transferred again.
+//!
 //! # Downloading Packages
 //!
 //! The purpose of the Index was to provide an efficient method to resolve the
@@ -158,9 +171,13 @@
 //!         ...
 //! ```
 
+use std::cmp;
+use std::error::Error;
 use std::io::{self, fs, File};
 use std::io::fs::PathExtensions;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::TaskPool;
+use std::sync::mpsc::channel;
 
 use curl::http;
 use git2;
@@ -171,10 +188,10 @@ use tar::Archive;
 use url::Url;
 
 use core::{Source, SourceId, PackageId, Package, Summary, Registry};
-use core::dependency::{Dependency, Kind};
+use core::dependency::{Dependency, Kind, Platform};
 use sources::{PathSource, git};
 use util::{CargoResult, Config, internal, ChainError, ToUrl, human};
-use util::{hex, Sha256};
+use util::{CargoError, hex, mark_used, network, Sha256};
 use ops;
 
 static DEFAULT: &'static str = "https://github.com/rust-lang/crates.io-index";
@@ -187,9 +204,22 @@ pub struct RegistrySource<'a, 'b:'a> {
     config: &'a Config<'b>,
     handle: Option<http::Handle>,
     sources: Vec<PathSource>,
+    index: RegistryIndex,
+    updated: bool,
+    /// The HTTP URL of a sparse index, if this is one.
+    sparse: Option<Url>,
+    /// The crates whose index file has been fetched from a sparse index.
+    fetched: HashSet<String>,
+}
+
+/// The contents of the index of a registry, parsed lazily from the files laid
+/// out as described above. Shared by remote registries, whose index is a git
+/// checkout, and local registries.
+pub struct RegistryIndex {
+    source_id: SourceId,
+    path: Path,
     hashes: HashMap<(String, String), String>, // (name, vers) => cksum
     cache: HashMap<String, Vec<(Summary, bool)>>,
-    updated: bool,
 }
 
 #[derive(RustcDecodable)]
@@ -223,25 +253,32 @@ struct RegistryDependency {
     default_features: bool,
     target: Option<String>,
     kind: Option<String>,
+    /// The URL of the index of the registry the dependency comes from, when
+    /// it isn't the registry of the package depending on it.
+    registry: Option<String>,
 }
 
 impl<'a, 'b> RegistrySource<'a, 'b> {
     pub fn new(source_id: &SourceId,
                config: &'a Config<'b>) -> RegistrySource<'a, 'b> {
         let hash = hex::short_hash(source_id);
-        let ident = source_id.get_url().host().unwrap().to_string();
+        let sparse = sparse_url(source_id.get_url());
+        let ident = sparse.as_ref().unwrap_or(source_id.get_url())
+                          .host().unwrap().to_string();
         let part = format!("{}-{}", ident, hash);
+        let checkout_path = config.registry_index_path().join(part.as_slice());
         RegistrySource {
-            checkout_path: config.registry_index_path().join(part.as_slice()),
+            index: RegistryIndex::new(source_id, &checkout_path),
+            checkout_path: checkout_path,
             cache_path: config.registry_cache_path().join(part.as_slice()),
             src_path: config.registry_source_path().join(part.as_slice()),
             config: config,
             source_id: source_id.clone(),
             handle: None,
             sources: Vec::new(),
-            hashes: HashMap::new(),
-            cache: HashMap::new(),
             updated: false,
+            sparse: sparse,
+            fetched: HashSet::new(),
         }
     }
 
@@ -250,11 +287,23 @@ impl<'a, 'b> RegistrySource<'a, 'b> {
     /// This is the main cargo registry by default, but it can be overridden in
     /// a .cargo/config
     pub fn url() -> CargoResult<Url> {
-        let config = try!(ops::registry_configuration());
+        let config = try!(ops::registry_configuration(None));
         let url = config.index.unwrap_or(DEFAULT.to_string());
         url.as_slice().to_url().map_err(human)
     }
 
+    /// Get the URL of the index of the registry called `name` in the
+    /// `registries` table of a .cargo/config
+    pub fn named_url(name: &str) -> CargoResult<Url> {
+        let config = try!(ops::registry_configuration(Some(name)));
+        let url = try!(config.index.chain_error(|| {
+            human(format!("no index found for registry: `{}`", name))
+        }));
+        url.as_slice().to_url().map_err(|e| {
+            human(format!("invalid index URL for registry `{}`: {}", name, e))
+        })
+    }
+
     /// Get the default url for the registry
     pub fn default_url() -> String {
         DEFAULT.to_string()
@@ -287,162 +336,241 @@ impl<'a, 'b> RegistrySource<'a, 'b> {
         Ok(repo)
     }
 
-    /// Download the given package from the given url into the local cache.
-    ///
-    /// This will perform the HTTP request to fetch the package. This function
-    /// will only succeed if the HTTP download was successful and the file is
-    /// then ready for inspection.
+    /// Download the given packages from their urls into the local cache.
     ///
-    /// No action is taken if the package is already downloaded.
-    fn download_package(&mut self, pkg: &PackageId, url: &Url)
-                        -> CargoResult<Path> {
-        // TODO: should discover from the S3 redirect
-        let filename = format!("{}-{}.crate", pkg.get_name(), pkg.get_version());
-        let dst = self.cache_path.join(filename);
-        if dst.exists() { return Ok(dst) }
-        try!(self.config.shell().status("Downloading", pkg));
-
-        try!(fs::mkdir_recursive(&dst.dir_path(), io::USER_DIR));
-        let handle = match self.handle {
-            Some(ref mut handle) => handle,
-            None => {
-                self.handle = Some(try!(ops::http_handle()));
-                self.handle.as_mut().unwrap()
+    /// Up to `http.max-downloads` packages are downloaded at the same time,
+    /// each with its own HTTP handle, the next download being started as soon
+    /// as one finishes. A package is only written to the cache once its
+    /// checksum has been verified, and this function will only succeed if
+    /// every package could be downloaded, otherwise all of the failures are
+    /// reported together.
+    fn download_packages(&mut self, downloads: Vec<(PackageId, Url)>)
+                         -> CargoResult<()> {
+        let total = downloads.len();
+        if total == 0 { return Ok(()) }
+
+        let max = cmp::min(try!(ops::http_max_downloads()) as usize, total);
+        let retries = try!(ops::net_retry());
+        let pool = TaskPool::new(max);
+        let (tx, rx) = channel();
+
+        let mut failures = Vec::new();
+        let mut started = 0;
+        let mut done = 0;
+        while done < total {
+            while started < total && started - done < max {
+                let (ref pkg, ref url) = downloads[started];
+                try!(self.config.shell().status("Downloading", pkg));
+                let tx = tx.clone();
+                let url = url.clone();
+                let i = started;
+                pool.execute(move|| {
+                    let body = network::retry(retries, |msg| {
+                        let _ = tx.send(Progress::Retrying(msg));
+                    }, || fetch(&url));
+                    let _ = tx.send(Progress::Done(i, body));
+                });
+                started += 1;
             }
-        };
-        // TODO: don't download into memory (curl-rust doesn't expose it)
-        let resp = try!(handle.get(url.to_string()).follow_redirects(true).exec());
-        if resp.get_code() != 200 && resp.get_code() != 0 {
-            return Err(internal(format!("Failed to get 200 reponse from {}\n{}",
-                                        url, resp)))
-        }
 
-        // Verify what we just downloaded
-        let expected = self.hashes.get(&(pkg.get_name().to_string(),
-                                         pkg.get_version().to_string()));
-        let expected = try!(expected.chain_error(|| {
-            internal(format!("no hash listed for {}", pkg))
-        }));
-        let actual = {
-            let mut state = Sha256::new();
-            state.update(resp.get_body());
-            state.finish()
-        };
-        if actual.as_slice().to_hex() != *expected {
-            return Err(human(format!("Failed to verify the checksum of `{}`",
-                                     pkg)))
+            let (i, body) = match rx.recv().unwrap() {
+                Progress::Retrying(msg) => {
+                    try!(self.config.shell().warn(format!("warning: {}", msg)));
+                    continue
+                }
+                Progress::Done(i, body) => (i, body),
+            };
+            done += 1;
+            let (ref pkg, ref url) = downloads[i];
+            let res = body.and_then(|body| {
+                // Verify what we just downloaded
+                try!(self.index.verify(pkg, body.as_slice()));
+                let dst = self.crate_path(pkg);
+                try!(fs::mkdir_recursive(&dst.dir_path(), io::USER_DIR));
+                try!(File::create(&dst).write(body.as_slice()));
+                Ok(body.len())
+            }).chain_error(|| {
+                internal(format!("Failed to download package `{}` from {}",
+                                 pkg, url))
+            });
+            match res {
+                Ok(size) => {
+                    let msg = if self.config.shell().get_verbose() {
+                        format!("{} ({} bytes) [{}/{}]", pkg, size, done,
+                                total)
+                    } else {
+                        format!("{} [{}/{}]", pkg, done, total)
+                    };
+                    try!(self.config.shell().status("Downloaded", msg));
+                }
+                Err(e) => failures.push((i, e)),
+            }
         }
 
-        try!(File::create(&dst).write(resp.get_body()));
-        Ok(dst)
+        // Report the failures in the order the packages were asked for.
+        failures.sort_by(|a, b| a.0.cmp(&b.0));
+        if failures.len() == 1 {
+            return Err(failures.pop().unwrap().1)
+        }
+        if failures.len() > 1 {
+            let msg = failures.iter().map(|&(_, ref e)| {
+                format!("  {}", describe(&**e))
+            }).collect::<Vec<_>>().connect("\n");
+            return Err(human(format!("failed to download {} of {} \
+                                      packages:\n{}", failures.len(), total,
+                                     msg)))
+        }
+        Ok(())
     }
 
-    /// Unpacks a downloaded package into a location where it's ready to be
-    /// compiled.
-    ///
-    /// No action is taken if the source looks like it's already unpacked.
-    fn unpack_package(&self, pkg: &PackageId, tarball: Path)
-                      -> CargoResult<Path> {
-        let dst = self.src_path.join(format!("{}-{}", pkg.get_name(),
-                                             pkg.get_version()));
-        if dst.join(".cargo-ok").exists() { return Ok(dst) }
-
-        try!(fs::mkdir_recursive(&dst.dir_path(), io::USER_DIR));
-        let f = try!(File::open(&tarball));
-        let gz = try!(GzDecoder::new(f));
-        let mut tar = Archive::new(gz);
-        try!(tar.unpack(&dst.dir_path()));
-        try!(File::create(&dst.join(".cargo-ok")));
-        Ok(dst)
+    /// Returns the HTTP handle of this source, creating it on first use.
+    fn handle(&mut self) -> CargoResult<&mut http::Handle> {
+        if self.handle.is_none() {
+            self.handle = Some(try!(ops::http_handle()));
+        }
+        Ok(self.handle.as_mut().unwrap())
     }
 
-    /// Parse the on-disk metadata for the package provided
-    fn summaries(&mut self, name: &str) -> CargoResult<&Vec<(Summary, bool)>> {
-        if self.cache.contains_key(name) {
-            return Ok(self.cache.get(name).unwrap());
+    /// Fetches `file` from a sparse index at `base` into the local copy of
+    /// the index, unless it hasn't changed since it was last fetched.
+    ///
+    /// Returns whether the file exists in the index.
+    fn fetch_sparse(&mut self, base: &Url, file: &Path) -> CargoResult<bool> {
+        let dst = self.checkout_path.join(file);
+        let headers = headers_path(&dst);
+        let mut url = base.clone();
+        {
+            let path = url.path_mut().unwrap();
+            if path.last().map(|s| s.is_empty()).unwrap_or(false) {
+                path.pop();
+            }
+            path.extend(file.str_components().map(|c| {
+                c.unwrap().to_string()
+            }));
         }
-        // see module comment for why this is structured the way it is
-        let path = self.checkout_path.clone();
-        let path = match name.len() {
-            1 => path.join("1").join(name),
-            2 => path.join("2").join(name),
-            3 => path.join("3").join(name.slice_to(1)).join(name),
-            _ => path.join(name.slice(0, 2))
-                     .join(name.slice(2, 4))
-                     .join(name),
+
+        // Only use the validators of the previous response if the file it
+        // came with is still there.
+        let cached = if dst.exists() {
+            File::open(&headers).read_to_string().unwrap_or(String::new())
+        } else {
+            String::new()
         };
-        let summaries = match File::open(&path) {
-            Ok(mut f) => {
-                let contents = try!(f.read_to_string());
-                let ret: CargoResult<Vec<(Summary, bool)>>;
-                ret = contents.as_slice().lines().filter(|l| l.trim().len() > 0)
-                              .map(|l| self.parse_registry_package(l))
-                              .collect();
-                try!(ret.chain_error(|| {
-                    internal(format!("Failed to parse registry's information \
-                                      for: {}", name))
-                }))
+        let config = self.config;
+        let resp = try!(network::with_retry(config, || {
+            let mut req = try!(self.handle()).get(url.to_string())
+                                             .follow_redirects(true);
+            for line in cached.as_slice().lines() {
+                let mut parts = line.splitn(1, ':');
+                let (name, value) = match (parts.next(), parts.next()) {
+                    (Some(name), Some(value)) => (name, value.trim()),
+                    _ => continue,
+                };
+                req = match name {
+                    "etag" => req.header("If-None-Match", value),
+                    "last-modified" => req.header("If-Modified-Since", value),
+                    _ => req,
+                };
             }
-            Err(..) => Vec::new(),
-        };
-        self.cache.insert(name.to_string(), summaries);
-        Ok(self.cache.get(name).unwrap())
-    }
+            let resp = try!(req.exec().chain_error(|| {
+                human(format!("failed to fetch `{}`", url))
+            }));
+            // Errors of the server are worth retrying, unlike the others.
+            if resp.get_code() >= 500 {
+                return Err(human(format!("failed to fetch `{}`, got {}", url,
+                                         resp.get_code())))
+            }
+            Ok(resp)
+        }));
 
-    /// Parse a line from the registry's index file into a Summary for a
-    /// package.
-    ///
-    /// The returned boolean is whether or not the summary has been yanked.
-    fn parse_registry_package(&mut self, line: &str)
-                              -> CargoResult<(Summary, bool)> {
-        let RegistryPackage {
-            name, vers, cksum, deps, features, yanked
-        } = try!(json::decode::<RegistryPackage>(line));
-        let pkgid = try!(PackageId::new(name.as_slice(),
-                                        vers.as_slice(),
-                                        &self.source_id));
-        let deps: CargoResult<Vec<Dependency>> = deps.into_iter().map(|dep| {
-            self.parse_registry_dependency(dep)
-        }).collect();
-        let deps = try!(deps);
-        self.hashes.insert((name, vers), cksum);
-        Ok((try!(Summary::new(pkgid, deps, features)), yanked.unwrap_or(false)))
+        match resp.get_code() {
+            304 => Ok(true),
+            404 | 410 => {
+                if dst.exists() { try!(fs::unlink(&dst)); }
+                Ok(false)
+            }
+            // Local files come back without any status code.
+            0 | 200 => {
+                try!(fs::mkdir_recursive(&dst.dir_path(), io::USER_DIR));
+                try!(File::create(&dst).write(resp.get_body()));
+                let mut validators = String::new();
+                for name in ["etag", "last-modified"].iter() {
+                    for value in resp.get_header(*name).iter() {
+                        validators.push_str(format!("{}: {}\n", name,
+                                                    value).as_slice());
+                    }
+                }
+                try!(File::create(&headers).write_str(validators.as_slice()));
+                Ok(true)
+            }
+            code => Err(human(format!("failed to fetch `{}`, got {}", url,
+                                      code))),
+        }
     }
 
-    /// Converts an encoded dependency in the registry to a cargo dependency
-    fn parse_registry_dependency(&self, dep: RegistryDependency)
-                                 -> CargoResult<Dependency> {
-        let RegistryDependency {
-            name, req, features, optional, default_features, target, kind
-        } = dep;
-
-        let dep = try!(Dependency::parse(name.as_slice(), Some(req.as_slice()),
-                                         &self.source_id));
-        let kind = match kind.as_ref().map(|s| s.as_slice()).unwrap_or("") {
-            "dev" => Kind::Development,
-            "build" => Kind::Build,
-            _ => Kind::Normal,
+    /// Makes sure the local copy of the index file of `name` is up to date.
+    ///
+    /// A git index is updated as a whole, whereas a sparse index only fetches
+    /// the file of `name`, once per session.
+    fn update_index_file(&mut self, name: &str) -> CargoResult<()> {
+        try!(self.do_update());
+        let base = match self.sparse {
+            Some(ref base) => base.clone(),
+            None => return Ok(()),
         };
+        if self.config.offline() || !self.fetched.insert(name.to_string()) {
+            return Ok(())
+        }
+        try!(self.fetch_sparse(&base, &index_file(name)));
+        self.index.forget(name);
+        Ok(())
+    }
 
-        Ok(dep.optional(optional)
-              .default_features(default_features)
-              .features(features)
-              .only_for_platform(target)
-              .kind(kind))
+    /// Path to which the tarball of `pkg` is downloaded.
+    fn crate_path(&self, pkg: &PackageId) -> Path {
+        // TODO: should discover from the S3 redirect
+        let filename = format!("{}-{}.crate", pkg.get_name(), pkg.get_version());
+        self.cache_path.join(filename)
     }
 
     /// Actually perform network operations to update the registry
     fn do_update(&mut self) -> CargoResult<()> {
         if self.updated { return Ok(()) }
 
+        // When offline, whatever copy of the index was fetched last is used.
+        if self.config.offline() {
+            if !self.checkout_path.join("config.json").exists() {
+                return Err(human(format!("the index of `{}` has never been \
+                                          downloaded, and can't be while \
+                                          offline", self.source_id.get_url())))
+            }
+            self.updated = true;
+            return Ok(())
+        }
+
         try!(self.config.shell().status("Updating",
              format!("registry `{}`", self.source_id.get_url())));
+
+        // A sparse index only has its configuration fetched up front, the
+        // files of the crates are fetched as they're queried.
+        if let Some(base) = self.sparse.clone() {
+            let found = try!(self.fetch_sparse(&base, &Path::new("config.json")));
+            if !found {
+                return Err(human(format!("`{}` has no `config.json`, it \
+                                          isn't a registry index", base)))
+            }
+            self.updated = true;
+            return Ok(())
+        }
+
         let repo = try!(self.open());
 
         // git fetch origin
         let url = self.source_id.get_url().to_string();
         let refspec = "refs/heads/*:refs/remotes/origin/*";
-        try!(git::fetch(&repo, url.as_slice(), refspec).chain_error(|| {
+        try!(network::with_retry(self.config, || {
+            git::fetch(&repo, url.as_slice(), refspec)
+        }).chain_error(|| {
             internal(format!("failed to fetch `{}`", url))
         }));
 
@@ -453,7 +581,7 @@ impl<'a, 'b> RegistrySource<'a, 'b> {
         let object = try!(repo.find_object(oid, None));
         try!(repo.reset(&object, git2::ResetType::Hard, None, None));
         self.updated = true;
-        self.cache.clear();
+        self.index.clear();
         Ok(())
     }
 }
@@ -465,19 +593,34 @@ impl<'a, 'b> Registry for RegistrySource<'a, 'b> {
         // come back with no summaries, then our registry may need to be
         // updated, so we fall back to performing a lazy update.
         if dep.get_source_id().get_precise().is_some() {
-            let mut summaries = try!(self.summaries(dep.get_name())).iter().map(|s| {
+            let summaries = try!(self.index.summaries(dep.get_name()));
+            let mut summaries = summaries.iter().map(|s| {
                 s.0.clone()
             }).collect::<Vec<_>>();
             if try!(summaries.query(dep)).len() == 0 {
-                try!(self.do_update());
+                try!(self.update_index_file(dep.get_name()));
             }
+        } else if self.sparse.is_some() {
+            try!(self.update_index_file(dep.get_name()));
         }
 
-        let summaries = try!(self.summaries(dep.get_name()));
-        let mut summaries = summaries.iter().filter(|&&(_, yanked)| {
-            dep.get_source_id().get_precise().is_some() || !yanked
-        }).map(|&(ref s, _)| s.clone()).collect::<Vec<_>>();
-        summaries.query(dep)
+        self.index.query(dep)
+    }
+
+    fn query_yanked(&mut self, dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        self.index.query_yanked(dep)
+    }
+
+    // The local copy of the index may just be out of date, but it can't be
+    // updated to find out.
+    fn missing_hint(&mut self, _dep: &Dependency) -> Option<String> {
+        if self.config.offline() {
+            Some(format!("the downloaded index of `{}` may be out of date, \
+                          and it can't be updated while offline",
+                         self.source_id.get_url()))
+        } else {
+            None
+        }
     }
 }
 
@@ -497,20 +640,35 @@ impl<'a, 'b> Source for RegistrySource<'a, 'b> {
     fn download(&mut self, packages: &[PackageId]) -> CargoResult<()> {
         let config = try!(self.config());
         let url = try!(config.dl.as_slice().to_url().map_err(internal));
-        for package in packages.iter() {
-            if self.source_id != *package.get_source_id() { continue }
+        let packages = packages.iter().filter(|package| {
+            self.source_id == *package.get_source_id()
+        }).collect::<Vec<_>>();
+
+        let mut downloads = Vec::new();
+        for &package in packages.iter() {
+            if self.crate_path(package).exists() { continue }
+            if self.config.offline() {
+                return Err(human(format!("package `{}` needs to be \
+                                          downloaded, which can't be done \
+                                          while offline", package)))
+            }
 
             let mut url = url.clone();
             url.path_mut().unwrap().push(package.get_name().to_string());
             url.path_mut().unwrap().push(package.get_version().to_string());
             url.path_mut().unwrap().push("download".to_string());
-            let path = try!(self.download_package(package, &url).chain_error(|| {
-                internal(format!("Failed to download package `{}` from {}",
-                                 package, url))
-            }));
-            let path = try!(self.unpack_package(package, path).chain_error(|| {
+            downloads.push((package.clone(), url));
+        }
+        try!(self.download_packages(downloads));
+
+        for &package in packages.iter() {
+            let tarball = self.crate_path(package);
+            let path = try!(unpack_package(package, &tarball, &self.src_path)
+                                .chain_error(|| {
                 internal(format!("Failed to unpack package `{}`", package))
             }));
+            mark_used(&tarball);
+            mark_used(&path);
             let mut src = PathSource::new(&path, &self.source_id);
             try!(src.update());
             self.sources.push(src);
@@ -530,3 +688,223 @@ impl<'a, 'b> Source for RegistrySource<'a, 'b> {
         Ok(pkg.get_package_id().get_version().to_string())
     }
 }
+
+impl RegistryIndex {
+    pub fn new(source_id: &SourceId, path: &Path) -> RegistryIndex {
+        RegistryIndex {
+            source_id: source_id.clone(),
+            path: path.clone(),
+            hashes: HashMap::new(),
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Forgets what was parsed so far, as the files of the index changed.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Forgets what was parsed of the file of `name`, as it changed.
+    pub fn forget(&mut self, name: &str) {
+        self.cache.remove(name);
+    }
+
+    /// Returns the summaries matching `dep`. Yanked versions are only
+    /// returned to a dependency locked to them.
+    pub fn query(&mut self, dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        let summaries = try!(self.summaries(dep.get_name()));
+        let mut summaries = summaries.iter().filter(|&&(_, yanked)| {
+            dep.get_source_id().get_precise().is_some() || !yanked
+        }).map(|&(ref s, _)| s.clone()).collect::<Vec<_>>();
+        summaries.query(dep)
+    }
+
+    /// Returns the yanked summaries matching `dep`.
+    pub fn query_yanked(&mut self, dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        let summaries = try!(self.summaries(dep.get_name()));
+        let mut summaries = summaries.iter().filter(|&&(_, yanked)| {
+            yanked
+        }).map(|&(ref s, _)| s.clone()).collect::<Vec<_>>();
+        summaries.query(dep)
+    }
+
+    /// Checks that `contents` has the checksum listed in the index for the
+    /// tarball of `pkg`.
+    pub fn verify(&self, pkg: &PackageId, contents: &[u8]) -> CargoResult<()> {
+        let expected = self.hashes.get(&(pkg.get_name().to_string(),
+                                         pkg.get_version().to_string()));
+        let expected = try!(expected.chain_error(|| {
+            internal(format!("no hash listed for {}", pkg))
+        }));
+        let actual = {
+            let mut state = Sha256::new();
+            state.update(contents);
+            state.finish()
+        };
+        if actual.as_slice().to_hex() != *expected {
+            return Err(human(format!("Failed to verify the checksum of `{}`",
+                                     pkg)))
+        }
+        Ok(())
+    }
+
+    /// Parse the on-disk metadata for the package provided
+    fn summaries(&mut self, name: &str) -> CargoResult<&Vec<(Summary, bool)>> {
+        if self.cache.contains_key(name) {
+            return Ok(self.cache.get(name).unwrap());
+        }
+        let path = self.path.join(index_file(name));
+        let summaries = match File::open(&path) {
+            Ok(mut f) => {
+                let contents = try!(f.read_to_string());
+                let ret: CargoResult<Vec<(Summary, bool)>>;
+                ret = contents.as_slice().lines().filter(|l| l.trim().len() > 0)
+                              .map(|l| self.parse_registry_package(l))
+                              .collect();
+                try!(ret.chain_error(|| {
+                    internal(format!("Failed to parse registry's information \
+                                      for: {}", name))
+                }))
+            }
+            Err(..) => Vec::new(),
+        };
+        self.cache.insert(name.to_string(), summaries);
+        Ok(self.cache.get(name).unwrap())
+    }
+
+    /// Parse a line from the registry's index file into a Summary for a
+    /// package.
+    ///
+    /// The returned boolean is whether or not the summary has been yanked.
+    fn parse_registry_package(&mut self, line: &str)
+                              -> CargoResult<(Summary, bool)> {
+        let RegistryPackage {
+            name, vers, cksum, deps, features, yanked
+        } = try!(json::decode::<RegistryPackage>(line));
+        let pkgid = try!(PackageId::new(name.as_slice(),
+                                        vers.as_slice(),
+                                        &self.source_id));
+        let deps: CargoResult<Vec<Dependency>> = deps.into_iter().map(|dep| {
+            self.parse_registry_dependency(dep)
+        }).collect();
+        let deps = try!(deps);
+        let summary = try!(Summary::new(pkgid, deps, features));
+        let summary = summary.set_checksum(cksum.clone());
+        self.hashes.insert((name, vers), cksum);
+        Ok((summary, yanked.unwrap_or(false)))
+    }
+
+    /// Converts an encoded dependency in the registry to a cargo dependency
+    fn parse_registry_dependency(&self, dep: RegistryDependency)
+                                 -> CargoResult<Dependency> {
+        let RegistryDependency {
+            name, req, features, optional, default_features, target, kind,
+            registry
+        } = dep;
+
+        let source_id = match registry {
+            Some(registry) => {
+                let url = try!(registry.as_slice().to_url().map_err(|e| {
+                    human(format!("invalid registry URL `{}` for dependency \
+                                   `{}`: {}", registry, name, e))
+                }));
+                SourceId::for_registry(&url)
+            }
+            None => self.source_id.clone(),
+        };
+        let dep = try!(Dependency::parse(name.as_slice(), Some(req.as_slice()),
+                                         &source_id));
+        let platform = match target {
+            Some(target) => Some(try!(Platform::parse(target.as_slice()))),
+            None => None,
+        };
+        let kind = match kind.as_ref().map(|s| s.as_slice()).unwrap_or("") {
+            "dev" => Kind::Development,
+            "build" => Kind::Build,
+            _ => Kind::Normal,
+        };
+
+        Ok(dep.optional(optional)
+              .default_features(default_features)
+              .features(features)
+              .only_for_platform(platform)
+              .kind(kind))
+    }
+}
+
+/// Unpacks the tarball of a package into a directory named after it in
+/// `src_path`, where it's ready to be compiled.
+///
+/// No action is taken if the source looks like it's already unpacked.
+pub fn unpack_package(pkg: &PackageId, tarball: &Path, src_path: &Path)
+                      -> CargoResult<Path> {
+    let dst = src_path.join(format!("{}-{}", pkg.get_name(),
+                                    pkg.get_version()));
+    if dst.join(".cargo-ok").exists() { return Ok(dst) }
+
+    try!(fs::mkdir_recursive(&dst.dir_path(), io::USER_DIR));
+    let f = try!(File::open(tarball));
+    let gz = try!(GzDecoder::new(f));
+    let mut tar = Archive::new(gz);
+    try!(tar.unpack(&dst.dir_path()));
+    try!(File::create(&dst.join(".cargo-ok")));
+    Ok(dst)
+}
+
+/// What the threads downloading packages report back.
+enum Progress {
+    /// A download failed and is about to be retried.
+    Retrying(String),
+    /// The download of the package at the given index has finished.
+    Done(usize, CargoResult<Vec<u8>>),
+}
+
+/// Fetches the body at `url` with a new HTTP handle.
+fn fetch(url: &Url) -> CargoResult<Vec<u8>> {
+    // TODO: don't download into memory (curl-rust doesn't expose it)
+    let mut handle = try!(ops::http_handle());
+    let resp = try!(handle.get(url.to_string()).follow_redirects(true).exec());
+    if resp.get_code() != 200 && resp.get_code() != 0 {
+        return Err(human(format!("failed to fetch `{}`, got {}", url,
+                                 resp.get_code())))
+    }
+    Ok(resp.get_body().to_vec())
+}
+
+/// Describes `err` along with everything that caused it, on a single line.
+fn describe(err: &CargoError) -> String {
+    let mut desc = err.description().to_string();
+    let mut cause = err.cause();
+    while let Some(e) = cause {
+        desc.push_str(": ");
+        desc.push_str(e.description());
+        cause = e.cause();
+    }
+    desc
+}
+
+/// Returns the path of the file of `name` relative to the root of an index.
+fn index_file(name: &str) -> Path {
+    // see module comment for why this is structured the way it is
+    match name.len() {
+        1 => Path::new("1").join(name),
+        2 => Path::new("2").join(name),
+        3 => Path::new("3").join(name.slice_to(1)).join(name),
+        _ => Path::new(name.slice(0, 2))
+                 .join(name.slice(2, 4))
+                 .join(name),
+    }
+}
+
+/// Returns where the validators of the response `file` came with are kept.
+fn headers_path(file: &Path) -> Path {
+    let name = format!("{}.headers", file.filename_str().unwrap());
+    file.dir_path().join(name)
+}
+
+/// Returns the HTTP URL of the index at `url` if it's a sparse index.
+fn sparse_url(url: &Url) -> Option<Url> {
+    let url = url.to_string();
+    if !url.as_slice().starts_with("sparse+") { return None }
+    url.as_slice().slice_from(7).to_url().ok()
+}
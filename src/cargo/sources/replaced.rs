@@ -0,0 +1,88 @@
+use core::{Source, Registry, PackageId, Package, Summary, Dependency, SourceId};
+use util::CargoResult;
+
+/// A source standing in for another one, as configured through the `[source]`
+/// table of `.cargo/config`.
+///
+/// All queries are forwarded to the replacement, and the summaries and
+/// packages it returns are rewritten to look like they came from the original
+/// source. This keeps lockfiles and output file names the same regardless of
+/// whether a source has been replaced or not.
+pub struct ReplacedSource<'a> {
+    to_replace: SourceId,
+    replace_with: SourceId,
+    inner: Box<Source + 'a>,
+}
+
+impl<'a> ReplacedSource<'a> {
+    pub fn new(to_replace: &SourceId,
+               replace_with: &SourceId,
+               inner: Box<Source + 'a>) -> ReplacedSource<'a> {
+        ReplacedSource {
+            to_replace: to_replace.clone(),
+            replace_with: replace_with.clone(),
+            inner: inner,
+        }
+    }
+
+    /// Returns the id of the replacement source, carrying over the precise
+    /// revision of `id` (an id from the original source).
+    fn inner_id(&self, id: &SourceId) -> SourceId {
+        self.replace_with.with_precise(id.get_precise().map(|s| s.to_string()))
+    }
+}
+
+impl<'a> Registry for ReplacedSource<'a> {
+    fn query(&mut self, dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        let outer = dep.get_source_id();
+        let inner = self.inner_id(outer);
+        let dep = dep.clone().map_source(outer, &inner);
+        let ret = try!(self.inner.query(&dep));
+        Ok(ret.into_iter().map(|s| s.map_source(&inner, outer)).collect())
+    }
+
+    fn query_yanked(&mut self, dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        let outer = dep.get_source_id();
+        let inner = self.inner_id(outer);
+        let dep = dep.clone().map_source(outer, &inner);
+        let ret = try!(self.inner.query_yanked(&dep));
+        Ok(ret.into_iter().map(|s| s.map_source(&inner, outer)).collect())
+    }
+
+    fn missing_hint(&mut self, dep: &Dependency) -> Option<String> {
+        let outer = dep.get_source_id();
+        let inner = self.inner_id(outer);
+        self.inner.missing_hint(&dep.clone().map_source(outer, &inner))
+    }
+}
+
+impl<'a> Source for ReplacedSource<'a> {
+    fn update(&mut self) -> CargoResult<()> {
+        self.inner.update()
+    }
+
+    fn download(&mut self, ids: &[PackageId]) -> CargoResult<()> {
+        let ids = ids.iter().filter(|id| {
+            *id.get_source_id() == self.to_replace
+        }).map(|id| {
+            id.with_source_id(&self.inner_id(id.get_source_id()))
+        }).collect::<Vec<_>>();
+        self.inner.download(ids.as_slice())
+    }
+
+    fn get(&self, ids: &[PackageId]) -> CargoResult<Vec<Package>> {
+        let mut ret = Vec::new();
+        for id in ids.iter() {
+            let outer = id.get_source_id();
+            if *outer != self.to_replace { continue }
+            let inner = self.inner_id(outer);
+            let pkgs = try!(self.inner.get(&[id.with_source_id(&inner)]));
+            ret.extend(pkgs.into_iter().map(|p| p.map_source(&inner, outer)));
+        }
+        Ok(ret)
+    }
+
+    fn fingerprint(&self, pkg: &Package) -> CargoResult<String> {
+        self.inner.fingerprint(pkg)
+    }
+}
@@ -0,0 +1,321 @@
+//! Parsing and evaluation of `cfg(...)` expressions.
+//!
+//! These are used in manifests to declare dependencies which are only needed
+//! on some platforms, for example `[target.'cfg(unix)'.dependencies]`. An
+//! expression is matched against the list of `cfg` values that rustc reports
+//! for the platform being compiled for, such as `unix` or
+//! `target_os = "linux"`.
+
+use std::fmt;
+
+use util::{CargoResult, human};
+
+/// A single `cfg` value, either a bare name like `unix` or a key/value pair
+/// like `target_os = "linux"`.
+#[derive(Eq, PartialEq, Clone, Show)]
+pub enum Cfg {
+    Name(String),
+    KeyPair(String, String),
+}
+
+/// A `cfg` expression as written in `cfg(...)`.
+#[derive(Eq, PartialEq, Clone, Show)]
+pub enum CfgExpr {
+    Not(Box<CfgExpr>),
+    All(Vec<CfgExpr>),
+    Any(Vec<CfgExpr>),
+    Value(Cfg),
+}
+
+#[derive(PartialEq, Show, Copy)]
+enum Token<'a> {
+    LeftParen,
+    RightParen,
+    Ident(&'a str),
+    Comma,
+    Equals,
+    String(&'a str),
+}
+
+struct Tokenizer<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+impl Cfg {
+    /// Parses a single `cfg` value, such as one line of the output of
+    /// `rustc --print cfg`.
+    pub fn parse(s: &str) -> CargoResult<Cfg> {
+        let mut p = try!(Parser::new(s));
+        let cfg = try!(p.cfg());
+        try!(p.finish());
+        Ok(cfg)
+    }
+}
+
+impl fmt::String for Cfg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Cfg::Name(ref s) => write!(f, "{}", s),
+            Cfg::KeyPair(ref k, ref v) => write!(f, "{} = \"{}\"", k, v),
+        }
+    }
+}
+
+impl CfgExpr {
+    /// Parses the contents of a `cfg(...)` expression, without the surrounding
+    /// `cfg(` and `)`.
+    pub fn parse(s: &str) -> CargoResult<CfgExpr> {
+        let mut p = try!(Parser::new(s));
+        let e = try!(p.expr());
+        try!(p.finish());
+        Ok(e)
+    }
+
+    /// Returns whether this expression holds for a platform with the given
+    /// `cfg` values.
+    pub fn matches(&self, cfg: &[Cfg]) -> bool {
+        match *self {
+            CfgExpr::Not(ref e) => !e.matches(cfg),
+            CfgExpr::All(ref e) => e.iter().all(|e| e.matches(cfg)),
+            CfgExpr::Any(ref e) => e.iter().any(|e| e.matches(cfg)),
+            CfgExpr::Value(ref e) => cfg.contains(e),
+        }
+    }
+}
+
+impl fmt::String for CfgExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CfgExpr::Not(ref e) => write!(f, "not({})", e),
+            CfgExpr::All(ref e) => write!(f, "all({})", CommaSep(e.as_slice())),
+            CfgExpr::Any(ref e) => write!(f, "any({})", CommaSep(e.as_slice())),
+            CfgExpr::Value(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+struct CommaSep<'a>(&'a [CfgExpr]);
+
+impl<'a> fmt::String for CommaSep<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, e) in self.0.iter().enumerate() {
+            if i > 0 { try!(write!(f, ", ")); }
+            try!(write!(f, "{}", e));
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Parser<'a> {
+    fn new(s: &'a str) -> CargoResult<Parser<'a>> {
+        let tokens = try!(Tokenizer { s: s, pos: 0 }.collect());
+        Ok(Parser { s: s, tokens: tokens, pos: 0 })
+    }
+
+    fn expr(&mut self) -> CargoResult<CfgExpr> {
+        let op = match self.tokens.get(self.pos) {
+            Some(&Token::Ident(op)) if op == "all" || op == "any" ||
+                                       op == "not" => op,
+            _ => return Ok(CfgExpr::Value(try!(self.cfg()))),
+        };
+        self.pos += 1;
+        try!(self.eat(Token::LeftParen));
+        let mut e = Vec::new();
+        while !self.try_eat(Token::RightParen) {
+            e.push(try!(self.expr()));
+            if !self.try_eat(Token::Comma) {
+                try!(self.eat(Token::RightParen));
+                break
+            }
+        }
+        match op {
+            "all" => Ok(CfgExpr::All(e)),
+            "any" => Ok(CfgExpr::Any(e)),
+            _ if e.len() == 1 => Ok(CfgExpr::Not(Box::new(e.pop().unwrap()))),
+            _ => Err(human(format!("`not` takes exactly one argument in \
+                                    cfg expression `{}`", self.s))),
+        }
+    }
+
+    fn cfg(&mut self) -> CargoResult<Cfg> {
+        let name = match self.tokens.get(self.pos) {
+            Some(&Token::Ident(name)) => name,
+            t => return Err(self.unexpected("an identifier", t)),
+        };
+        self.pos += 1;
+        if !self.try_eat(Token::Equals) {
+            return Ok(Cfg::Name(name.to_string()))
+        }
+        let value = match self.tokens.get(self.pos) {
+            Some(&Token::String(value)) => value,
+            t => return Err(self.unexpected("a string", t)),
+        };
+        self.pos += 1;
+        Ok(Cfg::KeyPair(name.to_string(), value.to_string()))
+    }
+
+    fn try_eat(&mut self, token: Token<'a>) -> bool {
+        if self.tokens.get(self.pos) == Some(&token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat(&mut self, token: Token<'a>) -> CargoResult<()> {
+        if self.try_eat(token) {
+            Ok(())
+        } else {
+            let t = self.tokens.get(self.pos);
+            Err(self.unexpected(token.describe(), t))
+        }
+    }
+
+    fn finish(&self) -> CargoResult<()> {
+        match self.tokens.get(self.pos) {
+            None => Ok(()),
+            t => Err(self.unexpected("the end of the expression", t)),
+        }
+    }
+
+    fn unexpected(&self, expected: &str, found: Option<&Token>)
+                  -> Box<::util::CargoError> {
+        let found = found.map(|t| t.describe()).unwrap_or("the end of input");
+        human(format!("expected {}, found {} in cfg expression `{}`",
+                      expected, found, self.s))
+    }
+}
+
+impl<'a> Token<'a> {
+    fn describe(&self) -> &'static str {
+        match *self {
+            Token::LeftParen => "`(`",
+            Token::RightParen => "`)`",
+            Token::Ident(..) => "an identifier",
+            Token::Comma => "`,`",
+            Token::Equals => "`=`",
+            Token::String(..) => "a string",
+        }
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = CargoResult<Token<'a>>;
+
+    fn next(&mut self) -> Option<CargoResult<Token<'a>>> {
+        let bytes = self.s.as_bytes();
+        while self.pos < bytes.len() && (bytes[self.pos] as char).is_whitespace() {
+            self.pos += 1;
+        }
+        if self.pos == bytes.len() { return None }
+
+        let start = self.pos;
+        self.pos += 1;
+        Some(match bytes[start] {
+            b'(' => Ok(Token::LeftParen),
+            b')' => Ok(Token::RightParen),
+            b',' => Ok(Token::Comma),
+            b'=' => Ok(Token::Equals),
+            b'"' => match self.s.slice_from(self.pos).find('"') {
+                Some(len) => {
+                    let s = self.s.slice(self.pos, self.pos + len);
+                    self.pos += len + 1;
+                    Ok(Token::String(s))
+                }
+                None => Err(human(format!("unterminated string in cfg \
+                                           expression `{}`", self.s))),
+            },
+            b if is_ident_start(b) => {
+                while self.pos < bytes.len() && is_ident_rest(bytes[self.pos]) {
+                    self.pos += 1;
+                }
+                Ok(Token::Ident(self.s.slice(start, self.pos)))
+            }
+            _ => {
+                let c = self.s.slice_from(start).chars().next().unwrap();
+                Err(human(format!("unexpected character `{}` in cfg \
+                                   expression `{}`", c, self.s)))
+            }
+        })
+    }
+}
+
+fn is_ident_start(b: u8) -> bool {
+    b == b'_' || (b as char).is_alphabetic()
+}
+
+fn is_ident_rest(b: u8) -> bool {
+    is_ident_start(b) || (b as char).is_numeric()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cfg, CfgExpr};
+
+    fn name(s: &str) -> CfgExpr {
+        CfgExpr::Value(Cfg::Name(s.to_string()))
+    }
+
+    fn pair(k: &str, v: &str) -> CfgExpr {
+        CfgExpr::Value(Cfg::KeyPair(k.to_string(), v.to_string()))
+    }
+
+    #[test]
+    fn parse_values() {
+        assert_eq!(CfgExpr::parse("unix").unwrap(), name("unix"));
+        assert_eq!(CfgExpr::parse("target_os = \"linux\"").unwrap(),
+                   pair("target_os", "linux"));
+        assert_eq!(CfgExpr::parse(" foo_1 ").unwrap(), name("foo_1"));
+    }
+
+    #[test]
+    fn parse_exprs() {
+        assert_eq!(CfgExpr::parse("not(windows)").unwrap(),
+                   CfgExpr::Not(Box::new(name("windows"))));
+        assert_eq!(CfgExpr::parse("all(unix, target_arch = \"x86_64\")").unwrap(),
+                   CfgExpr::All(vec![name("unix"), pair("target_arch", "x86_64")]));
+        assert_eq!(CfgExpr::parse("any(a, all(b,),)").unwrap(),
+                   CfgExpr::Any(vec![name("a"), CfgExpr::All(vec![name("b")])]));
+        assert_eq!(CfgExpr::parse("all()").unwrap(), CfgExpr::All(vec![]));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(CfgExpr::parse("").is_err());
+        assert!(CfgExpr::parse("unix windows").is_err());
+        assert!(CfgExpr::parse("not(a, b)").is_err());
+        assert!(CfgExpr::parse("all(unix").is_err());
+        assert!(CfgExpr::parse("target_os = linux").is_err());
+        assert!(CfgExpr::parse("target_os = \"linux").is_err());
+        assert!(CfgExpr::parse("1unix").is_err());
+    }
+
+    #[test]
+    fn matches() {
+        let cfg = [Cfg::Name("unix".to_string()),
+                   Cfg::KeyPair("target_os".to_string(), "linux".to_string())];
+        let m = |&: s: &str| CfgExpr::parse(s).unwrap().matches(&cfg);
+        assert!(m("unix"));
+        assert!(!m("windows"));
+        assert!(m("not(windows)"));
+        assert!(m("all(unix, target_os = \"linux\")"));
+        assert!(!m("all(unix, target_os = \"macos\")"));
+        assert!(m("any(windows, target_os = \"linux\")"));
+        assert!(m("all()"));
+        assert!(!m("any()"));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let s = "all(unix, not(target_os = \"macos\"), any(a, b))";
+        assert_eq!(CfgExpr::parse(s).unwrap().to_string(), s);
+    }
+}
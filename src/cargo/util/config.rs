@@ -8,14 +8,45 @@ use std::string;
 
 use rustc_serialize::{Encodable,Encoder};
 use toml;
-use core::MultiShell;
+use core::{MultiShell, SourceId};
+use core::dependency::PlatformInfo;
 use ops;
+use sources::config::SourceReplacements;
 use util::{CargoResult, ChainError, internal, human};
 
 use util::toml as cargo_toml;
 
 use self::ConfigValue as CV;
 
+/// Whether `Cargo.lock` may be modified, and if not which flag said so.
+#[derive(Copy, Clone, PartialEq, Show)]
+pub enum Locked {
+    No,
+    Locked,
+    Frozen,
+}
+
+impl Locked {
+    /// Combines the `--locked` and `--frozen` flags, the latter winning.
+    pub fn from_flags(locked: bool, frozen: bool) -> Locked {
+        if frozen {
+            Locked::Frozen
+        } else if locked {
+            Locked::Locked
+        } else {
+            Locked::No
+        }
+    }
+
+    pub fn flag(&self) -> Option<&'static str> {
+        match *self {
+            Locked::No => None,
+            Locked::Locked => Some("--locked"),
+            Locked::Frozen => Some("--frozen"),
+        }
+    }
+}
+
 pub struct Config<'a> {
     home_path: Path,
     shell: RefCell<&'a mut MultiShell>,
@@ -24,6 +55,12 @@ pub struct Config<'a> {
     rustc_version: string::String,
     /// The current host and default target of rustc
     rustc_host: string::String,
+    /// Sources replaced through the `[source]` table
+    source_replacements: SourceReplacements,
+    /// Whether `Cargo.lock` must be left untouched
+    locked: Locked,
+    /// Whether the network must not be accessed
+    offline: bool,
 }
 
 impl<'a> Config<'a> {
@@ -35,6 +72,8 @@ impl<'a> Config<'a> {
         }
 
         let (rustc_version, rustc_host) = try!(ops::rustc_version());
+        let configs = try!(all_configs(try!(os::getcwd())));
+        let source_replacements = try!(SourceReplacements::from_configs(&configs));
 
         Ok(Config {
             home_path: try!(homedir().chain_error(|| {
@@ -46,6 +85,9 @@ impl<'a> Config<'a> {
             target: target,
             rustc_version: rustc_version,
             rustc_host: rustc_host,
+            source_replacements: source_replacements,
+            locked: Locked::No,
+            offline: false,
         })
     }
 
@@ -92,6 +134,56 @@ impl<'a> Config<'a> {
     pub fn rustc_host(&self) -> &str {
         self.rustc_host.as_slice()
     }
+
+    /// Return whether resolution is forbidden to change `Cargo.lock`, which
+    /// is then an error
+    pub fn locked(&self) -> bool {
+        self.locked != Locked::No
+    }
+
+    /// Return the command line flag which locked `Cargo.lock`, if any
+    pub fn lock_flag(&self) -> Option<&'static str> {
+        self.locked.flag()
+    }
+
+    pub fn set_locked(&mut self, locked: Locked) {
+        self.locked = locked;
+    }
+
+    /// Return whether sources must make do with what has already been
+    /// downloaded instead of accessing the network
+    pub fn offline(&self) -> bool {
+        self.offline
+    }
+
+    pub fn set_offline(&mut self, offline: bool) {
+        self.offline = offline;
+    }
+
+    /// Return the source that `id` has been replaced with in the `[source]`
+    /// table of the configuration, if any
+    pub fn get_source_replacement(&self, id: &SourceId) -> Option<&SourceId> {
+        self.source_replacements.get(id)
+    }
+
+    /// Return the triple and `cfg` values of the host platform, which build
+    /// scripts and plugins are compiled for.
+    pub fn host_platform(&self) -> CargoResult<PlatformInfo> {
+        let cfg = try!(ops::rustc_cfg(self.rustc_host()));
+        Ok(PlatformInfo::new(self.rustc_host.clone(), cfg))
+    }
+
+    /// Return the triple and `cfg` values of the platform that is being
+    /// compiled for, which is the host unless `--target` was passed.
+    pub fn target_platform(&self) -> CargoResult<PlatformInfo> {
+        match self.target() {
+            Some(target) => {
+                let cfg = try!(ops::rustc_cfg(target));
+                Ok(PlatformInfo::new(target.to_string(), cfg))
+            }
+            None => self.host_platform(),
+        }
+    }
 }
 
 #[derive(Eq, PartialEq, Clone, RustcEncodable, RustcDecodable, Copy)]
@@ -103,6 +195,7 @@ pub enum Location {
 #[derive(Eq,PartialEq,Clone,RustcDecodable)]
 pub enum ConfigValue {
     String(string::String, Path),
+    Integer(i64, Path),
     List(Vec<(string::String, Path)>),
     Table(HashMap<string::String, ConfigValue>),
     Boolean(bool, Path),
@@ -114,6 +207,9 @@ impl fmt::Show for ConfigValue {
             CV::String(ref string, ref path) => {
                 write!(f, "{} (from {})", string, path.display())
             }
+            CV::Integer(i, ref path) => {
+                write!(f, "{} (from {})", i, path.display())
+            }
             CV::List(ref list) => {
                 try!(write!(f, "["));
                 for (i, &(ref s, ref path)) in list.iter().enumerate() {
@@ -134,6 +230,7 @@ impl Encodable for ConfigValue {
     fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
         match *self {
             CV::String(ref string, _) => string.encode(s),
+            CV::Integer(i, _) => i.encode(s),
             CV::List(ref list) => {
                 let list: Vec<&string::String> = list.iter().map(|s| &s.0).collect();
                 list.encode(s)
@@ -149,6 +246,7 @@ impl ConfigValue {
         match toml {
             toml::Value::String(val) => Ok(CV::String(val, path.clone())),
             toml::Value::Boolean(b) => Ok(CV::Boolean(b, path.clone())),
+            toml::Value::Integer(i) => Ok(CV::Integer(i, path.clone())),
             toml::Value::Array(val) => {
                 Ok(CV::List(try!(val.into_iter().map(|toml| {
                     match toml {
@@ -170,6 +268,7 @@ impl ConfigValue {
     fn merge(&mut self, from: ConfigValue) -> CargoResult<()> {
         match (self, from) {
             (&mut CV::String(..), CV::String(..)) |
+            (&mut CV::Integer(..), CV::Integer(..)) |
             (&mut CV::Boolean(..), CV::Boolean(..)) => {}
             (&mut CV::List(ref mut old), CV::List(ref mut new)) => {
                 let new = mem::replace(new, Vec::new());
@@ -201,6 +300,14 @@ impl ConfigValue {
         }
     }
 
+    pub fn integer(&self) -> CargoResult<(i64, &Path)> {
+        match *self {
+            CV::Integer(i, ref p) => Ok((i, p)),
+            _ => Err(internal(format!("expected an integer, but found a {}",
+                                      self.desc()))),
+        }
+    }
+
     pub fn table(&self) -> CargoResult<&HashMap<string::String, ConfigValue>> {
         match *self {
             CV::Table(ref table) => Ok(table),
@@ -230,6 +337,7 @@ impl ConfigValue {
             CV::Table(..) => "table",
             CV::List(..) => "array",
             CV::String(..) => "string",
+            CV::Integer(..) => "integer",
             CV::Boolean(..) => "boolean",
         }
     }
@@ -238,6 +346,7 @@ impl ConfigValue {
         match self {
             CV::Boolean(s, _) => toml::Value::Boolean(s),
             CV::String(s, _) => toml::Value::String(s),
+            CV::Integer(i, _) => toml::Value::Integer(i),
             CV::List(l) => toml::Value::Array(l
                                         .into_iter()
                                         .map(|(s, _)| toml::Value::String(s))
@@ -249,7 +358,7 @@ impl ConfigValue {
     }
 }
 
-fn homedir() -> Option<Path> {
+pub fn homedir() -> Option<Path> {
     let cargo_home = os::getenv("CARGO_HOME").map(|p| Path::new(p));
     let user_home = os::homedir().map(|p| p.join(".cargo"));
     return cargo_home.or(user_home);
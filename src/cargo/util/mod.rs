@@ -1,10 +1,10 @@
-pub use self::config::Config;
+pub use self::config::{Config, Locked};
 pub use self::process_builder::{process, ProcessBuilder};
 pub use self::errors::{CargoResult, CargoError, ChainError, CliResult};
 pub use self::errors::{CliError, ProcessError};
 pub use self::errors::{process_error, internal_error, internal, human};
 pub use self::errors::{Human, caused_human};
-pub use self::paths::{realpath, join_paths};
+pub use self::paths::{realpath, join_paths, mark_used};
 pub use self::lev_distance::{lev_distance};
 pub use self::hex::{to_hex, short_hash};
 pub use self::dependency_queue::{DependencyQueue, Fresh, Dirty, Freshness};
@@ -15,17 +15,20 @@ pub use self::to_semver::ToSemver;
 pub use self::vcs::{GitRepo, HgRepo};
 pub use self::sha256::Sha256;
 
+pub mod cfg;
 pub mod config;
 pub mod errors;
 pub mod graph;
 pub mod hex;
 pub mod important_paths;
+pub mod network;
 pub mod paths;
 pub mod process_builder;
 pub mod profile;
 pub mod to_semver;
 pub mod to_url;
 pub mod toml;
+pub mod toml_document;
 pub mod lev_distance;
 mod dependency_queue;
 mod sha256;
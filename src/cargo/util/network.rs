@@ -0,0 +1,49 @@
+//! Retrying network operations which may fail spuriously.
+//!
+//! How many times an operation is retried is configured with `net.retry`.
+//! The delay between two attempts doubles each time, starting from
+//! `INITIAL_DELAY` milliseconds.
+
+use std::io::timer;
+use std::time::Duration;
+
+use ops;
+use util::{CargoResult, Config};
+
+const INITIAL_DELAY: i64 = 500;
+
+/// Runs `f` until it succeeds or has been retried `net.retry` times, warning
+/// about each failure that is retried.
+pub fn with_retry<T, F>(config: &Config, f: F) -> CargoResult<T>
+    where F: FnMut() -> CargoResult<T>
+{
+    let retries = try!(ops::net_retry());
+    retry(retries, |msg| {
+        let _ = config.shell().warn(format!("warning: {}", msg));
+    }, f)
+}
+
+/// Runs `f` until it succeeds or has been retried `retries` times, passing
+/// the description of each failure that is retried to `warn`.
+///
+/// This is for when the shell can't be reached, as is the case from another
+/// thread.
+pub fn retry<T, F, W>(retries: u32, mut warn: W, mut f: F) -> CargoResult<T>
+    where F: FnMut() -> CargoResult<T>, W: FnMut(String)
+{
+    let mut remaining = retries;
+    let mut delay = INITIAL_DELAY;
+    loop {
+        match f() {
+            Ok(t) => return Ok(t),
+            Err(ref e) if remaining > 0 => {
+                warn(format!("spurious network error ({} tries remaining): \
+                              {}", remaining, e));
+                timer::sleep(Duration::milliseconds(delay));
+                remaining -= 1;
+                delay *= 2;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use std::{io,os};
 use std::io::fs;
 use std::path::BytesContainer;
+use time;
 
-use util::{human, CargoResult};
+use util::{human, CargoResult, ChainError};
 
 pub fn realpath(original: &Path) -> io::IoResult<Path> {
     const MAX_LINKS_FOLLOWED: usize = 256;
@@ -48,3 +49,15 @@ pub fn join_paths<T: BytesContainer>(paths: &[T], env: &str)
                        Does ${} have an unterminated quote character?", e, env))
     })
 }
+
+/// Records that `path`, an entry of one of the caches in the cargo home
+/// directory, was just used by setting its modification time to now.
+///
+/// `cargo clean --gc` removes the entries which haven't been used for the
+/// longest time. This is only a hint for it, so failing to record the use,
+/// as happens when the cargo home is read-only, is ignored.
+pub fn mark_used(path: &Path) {
+    let now = time::get_time();
+    let now = (now.sec as u64) * 1000 + (now.nsec as u64) / 1_000_000;
+    let _ = fs::change_file_times(path, now, now);
+}
@@ -12,7 +12,7 @@ use rustc_serialize::{Decodable, Decoder};
 
 use core::SourceId;
 use core::{Summary, Manifest, Target, Dependency, PackageId, GitReference};
-use core::dependency::Kind;
+use core::dependency::{Kind, Platform};
 use core::manifest::{LibKind, Profile, ManifestMetadata};
 use core::package_id::Metadata;
 use util::{CargoResult, human, ToUrl, ToSemver, ChainError};
@@ -197,6 +197,7 @@ pub struct DetailedTomlDependency {
     branch: Option<String>,
     tag: Option<String>,
     rev: Option<String>,
+    registry: Option<String>,
     features: Option<Vec<String>>,
     optional: Option<bool>,
     default_features: Option<bool>,
@@ -217,6 +218,7 @@ pub struct TomlManifest {
     build_dependencies: Option<HashMap<String, TomlDependency>>,
     features: Option<HashMap<String, Vec<String>>>,
     target: Option<HashMap<String, TomlPlatform>>,
+    workspace: Option<TomlWorkspace>,
 }
 
 #[derive(RustcDecodable, Clone, Default)]
@@ -499,15 +501,25 @@ impl TomlManifest {
 
             if let Some(targets) = self.target.as_ref() {
                 for (name, platform) in targets.iter() {
+                    let spec = try!(Platform::parse(name.as_slice()));
                     try!(process_dependencies(&mut cx,
                                               platform.dependencies.as_ref(),
                                               |dep| {
-                        dep.only_for_platform(Some(name.clone()))
+                        dep.only_for_platform(Some(spec.clone()))
                     }));
                 }
             }
         }
 
+        // Workspace members are loaded from the same path source as the root,
+        // so make sure they're discovered alongside any path dependencies.
+        let workspace_members = self.workspace.as_ref().map(|ws| {
+            ws.members.clone().unwrap_or(Vec::new())
+        });
+        if let Some(ref members) = workspace_members {
+            nested_paths.extend(members.iter().map(|m| Path::new(m.as_slice())));
+        }
+
         let exclude = project.exclude.clone().unwrap_or(Vec::new());
         let include = project.include.clone().unwrap_or(Vec::new());
 
@@ -516,6 +528,7 @@ impl TomlManifest {
         let summary = try!(Summary::new(pkgid, deps,
                                         self.features.clone()
                                             .unwrap_or(HashMap::new())));
+        let summary = summary.set_links(project.links.clone());
         let metadata = ManifestMetadata {
             description: project.description.clone(),
             homepage: project.homepage.clone(),
@@ -535,7 +548,8 @@ impl TomlManifest {
                                          exclude,
                                          include,
                                          project.links.clone(),
-                                         metadata);
+                                         metadata,
+                                         workspace_members);
         if used_deprecated_lib {
             manifest.add_warning(format!("the [[lib]] section has been \
                                           deprecated in favor of [lib]"));
@@ -593,7 +607,17 @@ fn process_dependencies<F>(cx: &mut Context,
                     cx.source_id.clone()
                 })
             }
-        }.unwrap_or(try!(SourceId::for_central()));
+        };
+        let new_source_id = match (new_source_id, details.registry.as_ref()) {
+            (Some(..), Some(..)) => {
+                return Err(human(format!("dependency `{}` can't be taken \
+                                          from a registry as well as from \
+                                          `git` or `path`", n)))
+            }
+            (Some(id), None) => id,
+            (None, Some(name)) => try!(SourceId::alt_registry(name.as_slice())),
+            (None, None) => try!(SourceId::for_central()),
+        };
 
         let dep = try!(Dependency::parse(n.as_slice(),
                                          details.version.as_ref()
@@ -628,6 +652,12 @@ enum PathValue {
     Path(Path),
 }
 
+/// Corresponds to the `[workspace]` section of a root manifest.
+#[derive(RustcDecodable, Clone)]
+struct TomlWorkspace {
+    members: Option<Vec<String>>,
+}
+
 /// Corresponds to a `target` entry, but `TomlTarget` is already used.
 #[derive(RustcDecodable)]
 struct TomlPlatform {
@@ -0,0 +1,495 @@
+//! A TOML document which can be edited without losing its formatting.
+//!
+//! `util::toml` decodes manifests into plain values, which throws away
+//! comments, the order of keys and whitespace. A `Document` instead keeps the
+//! source text around, split into items which are either table headers,
+//! key/value pairs, or anything else such as comments and blank lines. Edits
+//! only replace the items they concern, so the rest of the document is written
+//! back exactly as it was read.
+//!
+//! Only the subset of editing needed for manifests is supported: setting and
+//! removing keys of a table, and removing whole tables.
+
+use std::fmt;
+
+use util::{CargoResult, human};
+
+pub struct Document {
+    items: Vec<Item>,
+}
+
+struct Item {
+    kind: ItemKind,
+    /// The source text of the item, including its trailing newline if any.
+    text: String,
+}
+
+#[derive(PartialEq)]
+enum ItemKind {
+    /// A `[a.b]` header, or a `[[a.b]]` one if the flag is set.
+    Header(Vec<String>, bool),
+    /// A `key = value` pair, with the dotted path of the key.
+    KeyValue(Vec<String>),
+    /// Blank lines and comments.
+    Trivia,
+}
+
+/// A value which can be written into a `Document`.
+///
+/// Tables are written inline and keep the order of their keys.
+pub enum Value {
+    String(String),
+    Boolean(bool),
+    Array(Vec<Value>),
+    Table(Vec<(String, Value)>),
+}
+
+impl Document {
+    pub fn parse(s: &str) -> CargoResult<Document> {
+        let mut parser = Parser { input: s, bytes: s.as_bytes(), pos: 0 };
+        let mut items = Vec::new();
+        while parser.pos < parser.bytes.len() {
+            let start = parser.pos;
+            let kind = try!(parser.item().map_err(|msg| {
+                human(format!("{} on line {}", msg, parser.line()))
+            }));
+            items.push(Item {
+                kind: kind,
+                text: s.slice(start, parser.pos).to_string(),
+            });
+        }
+        Ok(Document { items: items })
+    }
+
+    /// Sets `key` in the table at `table` to `value`.
+    ///
+    /// An existing entry is replaced in place. Otherwise the entry is added
+    /// at the end of the table, or in sorted position if the keys of the
+    /// table are already sorted, and the table itself is appended to the
+    /// document if it doesn't exist yet. Any `[table.key]` section previously
+    /// defining the entry is removed.
+    pub fn set(&mut self, table: &[&str], key: &str, value: &Value) {
+        let mut sub = table.to_vec();
+        sub.push(key);
+        self.remove_table(sub.as_slice());
+
+        let line = format!("{} = {}", encode_key(key), value);
+        let (start, end) = match self.table_range(table) {
+            Some(range) => range,
+            None => {
+                self.append_table(table);
+                let len = self.items.len();
+                (len, len)
+            }
+        };
+
+        // Dotted keys such as `key.version = "..."` are merged into the new
+        // entry.
+        let mut existing = None;
+        let mut i = start;
+        let mut end = end;
+        while i < end {
+            match self.items[i].kind {
+                ItemKind::KeyValue(ref path) if path[0] == key => {}
+                _ => { i += 1; continue }
+            }
+            if existing.is_none() {
+                existing = Some(i);
+                i += 1;
+            } else {
+                self.items.remove(i);
+                end -= 1;
+            }
+        }
+        if let Some(i) = existing {
+            let text = format!("{}{}\n", indent(self.items[i].text.as_slice()),
+                               line);
+            self.items[i] = Item {
+                kind: ItemKind::KeyValue(vec![key.to_string()]),
+                text: text,
+            };
+            return
+        }
+
+        let (pos, prefix) = {
+            let keys = range(start, end).filter_map(|i| {
+                match self.items[i].kind {
+                    ItemKind::KeyValue(ref path) => {
+                        Some((i, path[0].as_slice()))
+                    }
+                    _ => None,
+                }
+            }).collect::<Vec<(uint, &str)>>();
+            let sorted = keys.windows(2).all(|w| w[0].1 <= w[1].1);
+            let pos = match keys.last() {
+                Some(&(last, _)) if sorted => {
+                    keys.iter().find(|&&(_, k)| k > key).map(|&(i, _)| i)
+                        .unwrap_or(last + 1)
+                }
+                Some(&(last, _)) => last + 1,
+                None => start,
+            };
+            let prefix = keys.first().map(|&(i, _)| {
+                indent(self.items[i].text.as_slice()).to_string()
+            }).unwrap_or(String::new());
+            (pos, prefix)
+        };
+        self.insert(pos, Item {
+            kind: ItemKind::KeyValue(vec![key.to_string()]),
+            text: format!("{}{}\n", prefix, line),
+        });
+    }
+
+    /// Removes `key` from the table at `table`, whether it is defined there
+    /// or in a `[table.key]` section of its own.
+    ///
+    /// Returns whether anything was removed.
+    pub fn remove(&mut self, table: &[&str], key: &str) -> bool {
+        let mut sub = table.to_vec();
+        sub.push(key);
+        let mut removed = self.remove_table(sub.as_slice());
+
+        if let Some((start, mut end)) = self.table_range(table) {
+            let mut i = start;
+            while i < end {
+                let matches = match self.items[i].kind {
+                    ItemKind::KeyValue(ref path) => path[0] == key,
+                    _ => false,
+                };
+                if matches {
+                    self.items.remove(i);
+                    end -= 1;
+                    removed = true;
+                } else {
+                    i += 1;
+                }
+            }
+        }
+        removed
+    }
+
+    /// Removes the `[table]` section along with all of its entries.
+    ///
+    /// Returns whether the section existed.
+    pub fn remove_table(&mut self, table: &[&str]) -> bool {
+        match self.table_range(table) {
+            Some((start, end)) => {
+                for _ in range(start - 1, end) {
+                    self.items.remove(start - 1);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the range of items following the header of `table`, up to the
+    /// next header. The root table has no header and starts at the beginning
+    /// of the document.
+    fn table_range(&self, table: &[&str]) -> Option<(uint, uint)> {
+        let start = if table.is_empty() {
+            0
+        } else {
+            let header = self.items.iter().position(|item| {
+                match item.kind {
+                    ItemKind::Header(ref path, false) => path_eq(path, table),
+                    _ => false,
+                }
+            });
+            match header {
+                Some(i) => i + 1,
+                None => return None,
+            }
+        };
+        let end = self.items.iter().skip(start).position(|item| {
+            match item.kind {
+                ItemKind::Header(..) => true,
+                _ => false,
+            }
+        }).map(|i| start + i).unwrap_or(self.items.len());
+        Some((start, end))
+    }
+
+    fn append_table(&mut self, table: &[&str]) {
+        if let Some(last) = self.items.last_mut() {
+            if !last.text.ends_with("\n") {
+                last.text.push('\n');
+            }
+        }
+        if !self.items.is_empty() {
+            self.items.push(Item { kind: ItemKind::Trivia, text: "\n".to_string() });
+        }
+        let path = table.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let header = table.iter().map(|s| encode_key(*s))
+                          .collect::<Vec<String>>().connect(".");
+        self.items.push(Item {
+            kind: ItemKind::Header(path, false),
+            text: format!("[{}]\n", header),
+        });
+    }
+
+    fn insert(&mut self, pos: uint, item: Item) {
+        // The item before may be the last line of a document without a
+        // trailing newline.
+        if pos > 0 && !self.items[pos - 1].text.ends_with("\n") {
+            self.items[pos - 1].text.push('\n');
+        }
+        self.items.insert(pos, item);
+    }
+}
+
+impl fmt::String for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for item in self.items.iter() {
+            try!(write!(f, "{}", item.text));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::String for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::String(ref s) => {
+                try!(write!(f, "\""));
+                for c in s.chars() {
+                    match c {
+                        '"' => try!(write!(f, "\\\"")),
+                        '\\' => try!(write!(f, "\\\\")),
+                        '\n' => try!(write!(f, "\\n")),
+                        '\r' => try!(write!(f, "\\r")),
+                        '\t' => try!(write!(f, "\\t")),
+                        c => try!(write!(f, "{}", c)),
+                    }
+                }
+                write!(f, "\"")
+            }
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::Array(ref values) => {
+                try!(write!(f, "["));
+                for (i, v) in values.iter().enumerate() {
+                    if i > 0 { try!(write!(f, ", ")); }
+                    try!(write!(f, "{}", v));
+                }
+                write!(f, "]")
+            }
+            Value::Table(ref pairs) => {
+                try!(write!(f, "{{"));
+                for (i, &(ref k, ref v)) in pairs.iter().enumerate() {
+                    if i > 0 { try!(write!(f, ",")); }
+                    try!(write!(f, " {} = {}", encode_key(k.as_slice()), v));
+                }
+                write!(f, " }}")
+            }
+        }
+    }
+}
+
+fn path_eq(path: &[String], other: &[&str]) -> bool {
+    path.len() == other.len() &&
+        path.iter().zip(other.iter()).all(|(a, b)| a.as_slice() == *b)
+}
+
+/// Returns the leading whitespace of `line`.
+fn indent(line: &str) -> &str {
+    let len = line.len() - line.trim_left_matches(|&: c: char| {
+        c == ' ' || c == '\t'
+    }).len();
+    line.slice_to(len)
+}
+
+/// Writes `key` as a bare key if possible, and quoted otherwise.
+fn encode_key(key: &str) -> String {
+    if !key.is_empty() && key.bytes().all(is_bare_key_byte) {
+        key.to_string()
+    } else {
+        Value::String(key.to_string()).to_string()
+    }
+}
+
+fn is_bare_key_byte(b: u8) -> bool {
+    match b {
+        b'a' ... b'z' | b'A' ... b'Z' | b'0' ... b'9' | b'_' | b'-' => true,
+        _ => false,
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    bytes: &'a [u8],
+    pos: uint,
+}
+
+impl<'a> Parser<'a> {
+    fn line(&self) -> uint {
+        self.bytes.slice_to(self.pos).iter().filter(|&&b| b == b'\n').count() + 1
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).map(|b| *b)
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        if self.input.slice_from(self.pos).starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn whitespace(&mut self) {
+        loop {
+            match self.peek() {
+                Some(b' ') | Some(b'\t') => self.pos += 1,
+                _ => break,
+            }
+        }
+    }
+
+    /// Parses one item, leaving the position after its trailing newline.
+    fn item(&mut self) -> Result<ItemKind, String> {
+        self.whitespace();
+        let kind = match self.peek() {
+            None | Some(b'\n') | Some(b'\r') | Some(b'#') => ItemKind::Trivia,
+            Some(b'[') => {
+                self.pos += 1;
+                let array = self.eat("[");
+                let path = try!(self.key_path());
+                if !self.eat("]") || (array && !self.eat("]")) {
+                    return Err("expected `]` after table name".to_string())
+                }
+                ItemKind::Header(path, array)
+            }
+            Some(..) => {
+                let path = try!(self.key_path());
+                if !self.eat("=") {
+                    return Err("expected `=` after key".to_string())
+                }
+                try!(self.value());
+                ItemKind::KeyValue(path)
+            }
+        };
+        self.end_of_line();
+        Ok(kind)
+    }
+
+    /// Skips over the rest of the line, which may only contain a comment.
+    fn end_of_line(&mut self) {
+        while let Some(b) = self.peek() {
+            self.pos += 1;
+            if b == b'\n' { break }
+        }
+    }
+
+    fn key_path(&mut self) -> Result<Vec<String>, String> {
+        let mut path = Vec::new();
+        loop {
+            self.whitespace();
+            path.push(try!(self.key()));
+            self.whitespace();
+            if !self.eat(".") { return Ok(path) }
+        }
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some(b'"') | Some(b'\'') => {
+                let start = self.pos;
+                try!(self.string());
+                let raw = self.input.slice(start + 1, self.pos - 1);
+                Ok(unescape(raw, self.bytes[start] == b'"'))
+            }
+            _ => {
+                let start = self.pos;
+                while self.peek().map(is_bare_key_byte).unwrap_or(false) {
+                    self.pos += 1;
+                }
+                if start == self.pos {
+                    Err("expected a key".to_string())
+                } else {
+                    Ok(self.input.slice(start, self.pos).to_string())
+                }
+            }
+        }
+    }
+
+    /// Skips over a value, which may span several lines if it contains
+    /// arrays, inline tables or multi-line strings.
+    fn value(&mut self) -> Result<(), String> {
+        let mut depth = 0u;
+        loop {
+            match self.peek() {
+                None => break,
+                Some(b'\n') if depth == 0 => break,
+                Some(b'"') | Some(b'\'') => { try!(self.string()); continue }
+                Some(b'#') => {
+                    while let Some(b) = self.peek() {
+                        if b == b'\n' { break }
+                        self.pos += 1;
+                    }
+                    continue
+                }
+                Some(b'[') | Some(b'{') => depth += 1,
+                Some(b']') | Some(b'}') => {
+                    if depth == 0 {
+                        return Err("unexpected closing delimiter".to_string())
+                    }
+                    depth -= 1;
+                }
+                Some(..) => {}
+            }
+            self.pos += 1;
+        }
+        if depth > 0 {
+            return Err("unterminated array or table".to_string())
+        }
+        Ok(())
+    }
+
+    /// Skips over a basic, literal or multi-line string.
+    fn string(&mut self) -> Result<(), String> {
+        let quote = self.bytes[self.pos];
+        let basic = quote == b'"';
+        let delim = if basic { "\"\"\"" } else { "'''" };
+        let multiline = self.eat(delim);
+        if !multiline { self.pos += 1; }
+        loop {
+            match self.peek() {
+                None => return Err("unterminated string".to_string()),
+                Some(b'\n') if !multiline => {
+                    return Err("newline in string".to_string())
+                }
+                Some(b'\\') if basic => self.pos += 2,
+                Some(b) if b == quote => {
+                    if !multiline {
+                        self.pos += 1;
+                        return Ok(())
+                    }
+                    if self.eat(delim) { return Ok(()) }
+                    self.pos += 1;
+                }
+                Some(..) => self.pos += 1,
+            }
+        }
+    }
+}
+
+/// Decodes the contents of a quoted key. Only the escapes which may sensibly
+/// appear in keys are handled.
+fn unescape(raw: &str, basic: bool) -> String {
+    if !basic { return raw.to_string() }
+    let mut ret = String::new();
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' { ret.push(c); continue }
+        match chars.next() {
+            Some('n') => ret.push('\n'),
+            Some('t') => ret.push('\t'),
+            Some('r') => ret.push('\r'),
+            Some(c) => ret.push(c),
+            None => {}
+        }
+    }
+    ret
+}
@@ -72,6 +72,7 @@ pub struct NewCrateDependency {
     pub version_req: String,
     pub target: Option<String>,
     pub kind: String,
+    pub registry: Option<String>,
 }
 
 #[derive(RustcDecodable)]
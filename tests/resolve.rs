@@ -11,13 +11,19 @@ use cargo::core::source::{SourceId, GitReference};
 use cargo::core::dependency::Kind::Development;
 use cargo::core::{Dependency, PackageId, Summary, Registry};
 use cargo::util::{CargoResult, ToUrl};
-use cargo::core::resolver::{self, Method};
+use cargo::core::resolver::{self, Method, Strategy};
 
 fn resolve<R: Registry>(pkg: PackageId, deps: Vec<Dependency>,
                         registry: &mut R)
                         -> CargoResult<Vec<PackageId>> {
+    resolve_with_strategy(pkg, deps, Strategy::Newest, registry)
+}
+
+fn resolve_with_strategy<R: Registry>(pkg: PackageId, deps: Vec<Dependency>,
+                                      strategy: Strategy, registry: &mut R)
+                                      -> CargoResult<Vec<PackageId>> {
     let summary = Summary::new(pkg, deps, HashMap::new()).unwrap();
-    let method = Method::Everything;
+    let method = Method::Everything(strategy);
     Ok(try!(resolver::resolve(&summary, method, registry)).iter().map(|p| {
         p.clone()
     }).collect())
@@ -220,6 +226,26 @@ fn resolving_with_many_versions() {
                                        ("foo", "1.0.2")])));
 }
 
+#[test]
+fn resolving_with_oldest_versions() {
+    let mut reg = registry(vec!(
+        pkg!(("foo", "1.0.0")),
+        pkg!(("foo", "1.0.1")),
+        pkg!(("foo", "1.1.0")),
+        pkg!(("bar", "1.0.0") => [dep_req("foo", "1")]),
+        pkg!(("bar", "1.0.1") => [dep_req("foo", "1.0.1")]),
+    ));
+
+    let res = resolve_with_strategy(pkg_id("root"), vec![
+        dep_req("foo", "1.0.1"),
+        dep_req("bar", "1"),
+    ], Strategy::Oldest, &mut reg).unwrap();
+
+    assert_that(&res, contains(names(&[("root", "1.0.0"),
+                                       ("foo", "1.0.1"),
+                                       ("bar", "1.0.0")])));
+}
+
 #[test]
 fn resolving_with_specific_version() {
     let mut reg = registry(vec!(
@@ -248,6 +274,40 @@ fn resolving_incompat_versions() {
     ], &mut reg).is_err());
 }
 
+#[test]
+fn resolving_incompat_versions_explained() {
+    let mut reg = registry(vec!(
+        pkg!(("foo", "1.0.1")),
+        pkg!(("foo", "1.0.2")),
+        pkg!("bar" => [dep_req("foo", "=1.0.2")]),
+        pkg!(("baz", "1.0.0") => [dep_req("foo", "=1.0.1")]),
+        pkg!(("baz", "1.0.1") => [dep_req("foo", "=1.0.1")]),
+    ));
+
+    let res = resolve(pkg_id("root"), vec![
+        dep("bar"),
+        dep_req("baz", "1"),
+    ], &mut reg);
+
+    assert_eq!(res.unwrap_err().to_string(), "\
+failed to select a version for `foo` (required by `baz`):
+all possible versions conflict with previously selected packages
+
+  `foo = 1.0.1` is required through:
+    root v1.0.0 (registry http://example.com/)
+      requires `baz ^1`: baz v1.0.0 (registry http://example.com/)
+      requires `foo = 1.0.1`
+
+  previously selected `foo v1.0.2 (registry http://example.com/)` through:
+    root v1.0.0 (registry http://example.com/)
+      requires `bar ^1.0.0`: bar v1.0.0 (registry http://example.com/)
+      requires `foo = 1.0.2`: foo v1.0.2 (registry http://example.com/)
+
+  rejected versions of `foo`:
+    1.0.1: semver-compatible with the previously selected version 1.0.2\
+");
+}
+
 #[test]
 fn resolving_backtrack() {
     let mut reg = registry(vec!(
@@ -324,6 +384,97 @@ fn resolving_with_deep_backtracking() {
                                        ("baz", "1.0.1")])));
 }
 
+/// A registry counting the queries made to it, which is how many candidates
+/// the resolver activated.
+struct CountingRegistry {
+    summaries: Vec<Summary>,
+    queries: usize,
+}
+
+impl Registry for CountingRegistry {
+    fn query(&mut self, dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        self.queries += 1;
+        self.summaries.query(dep)
+    }
+}
+
+/// Returns a registry with 4 packages of 6 versions each along with
+/// dependencies on them. Those are resolved before any dependency with more
+/// candidates, and trying all of their combinations activates 6^4 = 1296 of
+/// them.
+fn fillers(mut summaries: Vec<Summary>)
+           -> (CountingRegistry, Vec<Dependency>) {
+    let mut deps = Vec::new();
+    for name in ["f0", "f1", "f2", "f3"].iter() {
+        for v in range(0u, 6) {
+            let id = PackageId::new(*name, format!("1.0.{}", v).as_slice(),
+                                    &registry_loc()).unwrap();
+            summaries.push(Summary::new(id, Vec::new(),
+                                        HashMap::new()).unwrap());
+        }
+        deps.push(dep_req(*name, "1"));
+    }
+    (CountingRegistry { summaries: summaries, queries: 0 }, deps)
+}
+
+#[test]
+fn resolving_with_backjumping() {
+    // Every version of `bad` conflicts with the newest `pin`, which is
+    // selected before all the fillers. Backtracking chronologically would
+    // try all versions of `bad` for each combination of the fillers before
+    // getting back to `pin`.
+    let (mut reg, fillers) = fillers(vec!(
+        pkg!(("c", "1.0.0")),
+        pkg!(("c", "1.0.1")),
+        pkg!(("pin", "1.0.0") => [dep_req("c", "=1.0.0")]),
+        pkg!(("pin", "1.0.1") => [dep_req("c", "=1.0.1")]),
+        pkg!(("bad", "1.0.0") => [dep_req("c", "=1.0.0")]),
+        pkg!(("bad", "1.0.1") => [dep_req("c", "=1.0.0")]),
+        pkg!(("bad", "1.0.2") => [dep_req("c", "=1.0.0")]),
+        pkg!(("bad", "1.0.3") => [dep_req("c", "=1.0.0")]),
+        pkg!(("bad", "1.0.4") => [dep_req("c", "=1.0.0")]),
+        pkg!(("bad", "1.0.5") => [dep_req("c", "=1.0.0")]),
+        pkg!(("bad", "1.0.6") => [dep_req("c", "=1.0.0")]),
+    ));
+    let mut deps = vec![dep_req("pin", "1")];
+    deps.extend(fillers.into_iter());
+    deps.push(dep_req("bad", "1"));
+
+    let res = resolve(pkg_id("root"), deps, &mut reg).unwrap();
+
+    assert_that(&res, contains(names(&[("root", "1.0.0"),
+                                       ("pin", "1.0.0"),
+                                       ("c", "1.0.0"),
+                                       ("f0", "1.0.5"),
+                                       ("f3", "1.0.5"),
+                                       ("bad", "1.0.6")])));
+    assert!(reg.queries < 50, "{} candidates activated", reg.queries);
+}
+
+#[test]
+fn backjumping_to_a_missing_dependency() {
+    // No version of `bad` can be resolved whatever the fillers are.
+    let (mut reg, mut deps) = fillers(range(0u, 7).map(|v| {
+        let id = PackageId::new("bad", format!("1.0.{}", v).as_slice(),
+                                &registry_loc()).unwrap();
+        Summary::new(id, vec![dep_req("missing", "1")],
+                     HashMap::new()).unwrap()
+    }).collect());
+    deps.push(dep_req("bad", "1"));
+
+    let res = resolve(pkg_id("root"), deps, &mut reg);
+
+    assert_eq!(res.unwrap_err().to_string(), "\
+no package named `missing` found (required by `bad`)
+location searched: registry http://example.com/
+version required: ^1
+required through:
+    root v1.0.0 (registry http://example.com/)
+      requires `bad ^1`: bad v1.0.0 (registry http://example.com/)\
+");
+    assert!(reg.queries < 50, "{} candidates activated", reg.queries);
+}
+
 #[test]
 fn resolving_but_no_exists() {
     let mut reg = registry(vec!(
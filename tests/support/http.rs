@@ -0,0 +1,144 @@
+//! A tiny HTTP server serving the files of a directory, with just enough of
+//! HTTP to exercise conditional requests, failing requests and stalled
+//! transfers.
+
+use std::io::net::tcp::TcpAcceptor;
+use std::io::net::ip::SocketAddr;
+use std::io::{TcpListener, Listener, Acceptor, BufferedStream, File};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread::Thread;
+
+use support::registry::cksum;
+
+pub struct FileServer {
+    addr: SocketAddr,
+    acceptor: TcpAcceptor,
+    requests: Receiver<String>,
+}
+
+impl FileServer {
+    /// Starts serving the files under `root`, as they are at the time of each
+    /// request.
+    pub fn new(root: &Path) -> FileServer {
+        FileServer::start(root, 0, 0)
+    }
+
+    /// Like `new`, except that the first `failures` requests are answered
+    /// with `503 Service Unavailable`.
+    pub fn failing(root: &Path, failures: usize) -> FileServer {
+        FileServer::start(root, failures, 0)
+    }
+
+    /// Like `new`, except that the first `stalls` requests are answered with
+    /// headers promising a body which is never sent. The connection is held
+    /// open until the client gives up on it.
+    pub fn stalling(root: &Path, stalls: usize) -> FileServer {
+        FileServer::start(root, 0, stalls)
+    }
+
+    fn start(root: &Path, failures: usize, stalls: usize) -> FileServer {
+        let mut listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.socket_name().unwrap();
+        let mut a = listener.listen().unwrap();
+        let acceptor = a.clone();
+        let (tx, rx) = channel();
+        let root = root.clone();
+
+        Thread::spawn(move|| {
+            let mut failures = failures;
+            let mut stalls = stalls;
+            for stream in a.incoming() {
+                let mut s = match stream {
+                    Ok(s) => BufferedStream::new(s),
+                    Err(..) => break,
+                };
+                let lines = s.lines().map(|l| l.unwrap())
+                             .take_while(|l| l.as_slice().trim().len() > 0)
+                             .collect::<Vec<String>>();
+                let path = lines[0].as_slice().words().nth(1).unwrap()
+                                   .to_string();
+                let etag = lines.iter().filter_map(|l| {
+                    let l = l.as_slice().trim();
+                    if l.starts_with("If-None-Match: ") {
+                        Some(l.slice_from(15).to_string())
+                    } else {
+                        None
+                    }
+                }).next();
+
+                if stalls > 0 {
+                    stalls -= 1;
+                    tx.send(format!("stalled {}", path)).unwrap();
+                    s.write(b"HTTP/1.1 200 OK\r\n\
+                              Content-Length: 1000\r\n\
+                              Connection: close\r\n\
+                              \r\n").unwrap();
+                    s.flush().unwrap();
+                    let _ = s.read_to_end();
+                    continue
+                }
+
+                let file = root.join(path.as_slice().slice_from(1));
+                let (status, response) = match File::open(&file).read_to_end() {
+                    _ if failures > 0 => {
+                        failures -= 1;
+                        (503, b"HTTP/1.1 503 Service Unavailable\r\n\
+                                Content-Length: 0\r\n\
+                                Connection: close\r\n\
+                                \r\n".to_vec())
+                    }
+                    Ok(body) => {
+                        let tag = format!("\"{}\"", cksum(body.as_slice()));
+                        if etag.as_ref() == Some(&tag) {
+                            (304, format!("HTTP/1.1 304 Not Modified\r\n\
+                                           ETag: {}\r\n\
+                                           Connection: close\r\n\
+                                           \r\n", tag).into_bytes())
+                        } else {
+                            let mut r = format!("HTTP/1.1 200 OK\r\n\
+                                                 Content-Length: {}\r\n\
+                                                 ETag: {}\r\n\
+                                                 Connection: close\r\n\
+                                                 \r\n", body.len(), tag)
+                                            .into_bytes();
+                            r.push_all(body.as_slice());
+                            (200, r)
+                        }
+                    }
+                    Err(..) => (404, b"HTTP/1.1 404 Not Found\r\n\
+                                       Content-Length: 0\r\n\
+                                       Connection: close\r\n\
+                                       \r\n".to_vec()),
+                };
+                // Record the request before answering it, so it's known by
+                // the time cargo exits.
+                tx.send(format!("{} {}", status, path)).unwrap();
+                s.write(response.as_slice()).unwrap();
+                s.flush().unwrap();
+            }
+        });
+
+        FileServer { addr: addr, acceptor: acceptor, requests: rx }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}/", self.addr)
+    }
+
+    /// Returns the requests served since the last call, as the status of
+    /// the response followed by the requested path, e.g. `304 /3/b/bar`.
+    /// Stalled requests have `stalled` in place of the status.
+    pub fn requests(&self) -> Vec<String> {
+        let mut ret = Vec::new();
+        while let Ok(req) = self.requests.try_recv() {
+            ret.push(req);
+        }
+        ret
+    }
+}
+
+impl Drop for FileServer {
+    fn drop(&mut self) {
+        let _ = self.acceptor.close_accept();
+    }
+}
@@ -16,6 +16,7 @@ use support::paths::PathExt;
 
 pub mod paths;
 pub mod git;
+pub mod http;
 pub mod registry;
 
 /*
@@ -495,6 +496,11 @@ pub static UPDATING:    &'static str = "    Updating";
 pub static DOCTEST:     &'static str = "   Doc-tests";
 pub static PACKAGING:   &'static str = "   Packaging";
 pub static DOWNLOADING: &'static str = " Downloading";
+pub static DOWNLOADED:  &'static str = "  Downloaded";
 pub static UPLOADING:   &'static str = "   Uploading";
 pub static VERIFYING:   &'static str = "   Verifying";
 pub static ARCHIVING:   &'static str = "   Archiving";
+pub static INSTALLING:  &'static str = "  Installing";
+pub static REMOVING:    &'static str = "    Removing";
+pub static ADDING:      &'static str = "      Adding";
+pub static VENDORING:   &'static str = "   Vendoring";
@@ -1,4 +1,5 @@
 use std::io::{self, fs, File};
+use std::io::fs::PathExtensions;
 
 use flate2::CompressionLevel::Default;
 use flate2::writer::GzEncoder;
@@ -16,6 +17,12 @@ pub fn registry_path() -> Path { paths::root().join("registry") }
 pub fn registry() -> Url { Url::from_file_path(&registry_path()).unwrap() }
 pub fn dl_path() -> Path { paths::root().join("dl") }
 pub fn dl_url() -> Url { Url::from_file_path(&dl_path()).unwrap() }
+pub fn alt_registry_path() -> Path { paths::root().join("alt-registry") }
+pub fn alt_registry() -> Url { Url::from_file_path(&alt_registry_path()).unwrap() }
+pub fn alt_dl_path() -> Path { paths::root().join("alt-dl") }
+pub fn alt_dl_url() -> Url { Url::from_file_path(&alt_dl_path()).unwrap() }
+pub fn alt_api_path() -> Path { paths::root().join("alt-api") }
+pub fn alt_api_url() -> Url { Url::from_file_path(&alt_api_path()).unwrap() }
 
 pub fn init() {
     let config = paths::home().join(".cargo/config");
@@ -34,7 +41,32 @@ pub fn init() {
         .build();
 }
 
+/// Sets up a second registry, configured under the name `alternative`, which
+/// packages can also be published to.
+pub fn alt_init() {
+    let config = paths::home().join(".cargo/config");
+    let prev = File::open(&config).read_to_string().unwrap_or(String::new());
+    File::create(&config).write_str(format!(r#"{prev}
+        [registries.alternative]
+            index = "{reg}"
+    "#, prev = prev, reg = alt_registry()).as_slice()).unwrap();
+
+    fs::mkdir_recursive(&alt_api_path().join("api/v1/crates"),
+                        io::USER_DIR).unwrap();
+
+    repo(&alt_registry_path())
+        .file("config.json", format!(r#"
+            {{"dl":"{}","api":"{}"}}
+        "#, alt_dl_url(), alt_api_url()).as_slice())
+        .build();
+}
+
 pub fn mock_archive(name: &str, version: &str, deps: &[(&str, &str, &str)]) {
+    mock_archive_at(&mock_archive_dst(name, version), name, version, deps)
+}
+
+fn mock_archive_at(dst: &Path, name: &str, version: &str,
+                   deps: &[(&str, &str, &str)]) {
     let mut manifest = format!(r#"
         [package]
         name = "{}"
@@ -56,9 +88,8 @@ pub fn mock_archive(name: &str, version: &str, deps: &[(&str, &str, &str)]) {
         .file("src/lib.rs", "");
     p.build();
 
-    let dst = mock_archive_dst(name, version);
     fs::mkdir_recursive(&dst.dir_path(), io::USER_DIR).unwrap();
-    let f = File::create(&dst).unwrap();
+    let f = File::create(dst).unwrap();
     let a = Archive::new(GzEncoder::new(f, Default));
     a.append(format!("{}-{}/Cargo.toml", name, version).as_slice(),
              &mut File::open(&p.root().join("Cargo.toml")).unwrap()).unwrap();
@@ -81,20 +112,94 @@ pub fn mock_pkg_yank(name: &str, version: &str, deps: &[(&str, &str, &str)],
     let c = File::open(&mock_archive_dst(name, version)).read_to_end().unwrap();
     let line = pkg(name, version, deps, cksum(c.as_slice()).as_slice(), yanked);
 
-    let file = match name.len() {
+    publish(index_file(name).as_slice(), line.as_slice());
+}
+
+/// Publishes a package to the registry set up by `alt_init`.
+pub fn mock_alt_pkg(name: &str, version: &str, deps: &[(&str, &str, &str)]) {
+    let dst = alt_dl_path().join(name).join(version).join("download");
+    mock_archive_at(&dst, name, version, deps);
+    let c = File::open(&dst).read_to_end().unwrap();
+    let line = pkg(name, version, deps, cksum(c.as_slice()).as_slice(), false);
+
+    publish_to(&alt_registry_path(), index_file(name).as_slice(),
+               line.as_slice());
+}
+
+/// Publishes a package to the registry set up by `alt_init`, whose
+/// dependencies all come from the default registry.
+pub fn mock_alt_pkg_with_central_deps(name: &str, version: &str,
+                                      deps: &[(&str, &str, &str)]) {
+    let dst = alt_dl_path().join(name).join(version).join("download");
+    mock_archive_at(&dst, name, version, deps);
+    let c = File::open(&dst).read_to_end().unwrap();
+    let deps = deps.iter().map(|&(a, b, c)| {
+        dep_from(a, b, c, &registry())
+    }).collect::<Vec<String>>();
+    let line = pkg_line(name, version, deps.as_slice(),
+                        cksum(c.as_slice()).as_slice(), false);
+
+    publish_to(&alt_registry_path(), index_file(name).as_slice(),
+               line.as_slice());
+}
+
+/// Marks a published version as yanked, leaving its archive untouched.
+pub fn yank(name: &str, version: &str) {
+    let file = index_file(name);
+    let dst = registry_path().join(file.as_slice());
+    let prev = File::open(&dst).read_to_string().unwrap();
+    let vers = format!("\"vers\":\"{}\"", version);
+    let lines = prev.as_slice().lines().map(|line| {
+        if line.contains(vers.as_slice()) {
+            line.replace("\"yanked\":false", "\"yanked\":true")
+        } else {
+            line.to_string()
+        }
+    }).collect::<Vec<String>>();
+    fs::unlink(&dst).unwrap();
+    publish(file.as_slice(), lines.connect("\n").as_slice());
+}
+
+/// Lays out everything published so far as a local registry in `dst`.
+pub fn local_registry(dst: &Path) {
+    let root = registry_path();
+    for file in fs::walk_dir(&root).unwrap() {
+        if !file.is_file() { continue }
+        let rel = file.path_relative_from(&root).unwrap();
+        let name = rel.as_str().unwrap();
+        if name.starts_with(".git") || name == "config.json" { continue }
+        let out = dst.join("index").join(&rel);
+        fs::mkdir_recursive(&out.dir_path(), io::USER_DIR).unwrap();
+        fs::copy(&file, &out).unwrap();
+    }
+    for file in fs::walk_dir(&dl_path()).unwrap() {
+        if !file.is_file() { continue }
+        let version = file.dir_path();
+        let name = version.dir_path();
+        let out = dst.join(format!("{}-{}.crate", name.filename_str().unwrap(),
+                                   version.filename_str().unwrap()));
+        fs::copy(&file, &out).unwrap();
+    }
+}
+
+fn index_file(name: &str) -> String {
+    match name.len() {
         1 => format!("1/{}", name),
         2 => format!("2/{}", name),
         3 => format!("3/{}/{}", name.slice_to(1), name),
         _ => format!("{}/{}/{}", name.slice(0, 2), name.slice(2, 4), name),
-    };
-    publish(file.as_slice(), line.as_slice());
+    }
 }
 
 pub fn publish(file: &str, line: &str) {
-    let repo = git2::Repository::open(&registry_path()).unwrap();
+    publish_to(&registry_path(), file, line)
+}
+
+fn publish_to(registry: &Path, file: &str, line: &str) {
+    let repo = git2::Repository::open(registry).unwrap();
     let mut index = repo.index().unwrap();
     {
-        let dst = registry_path().join(file);
+        let dst = registry.join(file);
         let prev = File::open(&dst).read_to_string().unwrap_or(String::new());
         fs::mkdir_recursive(&dst.dir_path(), io::USER_DIR).unwrap();
         File::create(&dst).write_str((prev + line + "\n").as_slice()).unwrap();
@@ -114,6 +219,11 @@ pub fn publish(file: &str, line: &str) {
 pub fn pkg(name: &str, vers: &str, deps: &[(&str, &str, &str)], cksum: &str,
            yanked: bool) -> String {
     let deps = deps.iter().map(|&(a, b, c)| dep(a, b, c)).collect::<Vec<String>>();
+    pkg_line(name, vers, deps.as_slice(), cksum, yanked)
+}
+
+fn pkg_line(name: &str, vers: &str, deps: &[String], cksum: &str,
+            yanked: bool) -> String {
     format!("{{\"name\":\"{}\",\"vers\":\"{}\",\
                \"deps\":[{}],\"cksum\":\"{}\",\"features\":{{}},\
                \"yanked\":{}}}",
@@ -130,6 +240,13 @@ pub fn dep(name: &str, req: &str, kind: &str) -> String {
                \"kind\":\"{}\"}}", name, req, kind)
 }
 
+/// A dependency taken from the registry whose index is `registry`.
+pub fn dep_from(name: &str, req: &str, kind: &str, registry: &Url) -> String {
+    let fields = dep(name, req, kind);
+    format!("{},\"registry\":\"{}\"}}",
+            fields.as_slice().slice_to(fields.len() - 1), registry)
+}
+
 pub fn cksum(s: &[u8]) -> String {
     let mut sha = Sha256::new();
     sha.update(s);
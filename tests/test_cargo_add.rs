@@ -0,0 +1,190 @@
+use std::io::File;
+
+use support::{project, execs, cargo_dir, ProjectBuilder};
+use support::{UPDATING, ADDING, REMOVING};
+use support::registry as r;
+use hamcrest::assert_that;
+
+fn setup() {
+    r::init();
+}
+
+fn manifest(p: &ProjectBuilder) -> String {
+    File::open(&p.root().join("Cargo.toml")).read_to_string().unwrap()
+}
+
+static MANIFEST: &'static str = "\
+# The manifest of foo
+[package]
+name = \"foo\"
+version = \"0.5.0\"
+authors = []
+
+[dependencies]
+# Comments are kept
+bar = \"0.1\"   # and so is this one
+qux = { version = \"0.2\", optional = true }
+
+[features]
+default = []
+";
+
+test!(add_latest_version {
+    r::mock_pkg("baz", "0.1.0", &[]);
+    r::mock_pkg("baz", "0.2.1", &[]);
+    r::mock_pkg_yank("baz", "0.3.0", &[], true);
+
+    let p = project("foo")
+        .file("Cargo.toml", MANIFEST)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("add").arg("baz"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `[..]`
+{adding} baz 0.2.1 to dependencies
+", updating = UPDATING, adding = ADDING).as_slice()));
+
+    assert_eq!(manifest(&p).as_slice(), "\
+# The manifest of foo
+[package]
+name = \"foo\"
+version = \"0.5.0\"
+authors = []
+
+[dependencies]
+# Comments are kept
+bar = \"0.1\"   # and so is this one
+baz = \"0.2.1\"
+qux = { version = \"0.2\", optional = true }
+
+[features]
+default = []
+");
+});
+
+test!(add_with_requirement_and_kind {
+    let p = project("foo")
+        .file("Cargo.toml", MANIFEST)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("add").arg("baz@0.1").arg("--dev")
+                 .arg("--features").arg("a b"),
+                execs().with_status(0).with_stdout(format!("\
+{adding} baz 0.1 to dev-dependencies
+", adding = ADDING).as_slice()));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("add")
+                 .arg("gen").arg("--build").arg("--path").arg("gen"),
+                execs().with_status(0).with_stdout(format!("\
+{adding} gen to build-dependencies
+", adding = ADDING).as_slice()));
+
+    assert_eq!(manifest(&p).as_slice(), "\
+# The manifest of foo
+[package]
+name = \"foo\"
+version = \"0.5.0\"
+authors = []
+
+[dependencies]
+# Comments are kept
+bar = \"0.1\"   # and so is this one
+qux = { version = \"0.2\", optional = true }
+
+[features]
+default = []
+
+[dev-dependencies]
+baz = { version = \"0.1\", features = [\"a\", \"b\"] }
+
+[build-dependencies]
+gen = { path = \"gen\" }
+");
+});
+
+test!(add_replaces_existing {
+    let p = project("foo")
+        .file("Cargo.toml", MANIFEST)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("add").arg("qux@0.3").arg("--optional"),
+                execs().with_status(0));
+
+    assert_eq!(manifest(&p).as_slice(), "\
+# The manifest of foo
+[package]
+name = \"foo\"
+version = \"0.5.0\"
+authors = []
+
+[dependencies]
+# Comments are kept
+bar = \"0.1\"   # and so is this one
+qux = { version = \"0.3\", optional = true }
+
+[features]
+default = []
+");
+});
+
+test!(add_invalid_requirement {
+    let p = project("foo")
+        .file("Cargo.toml", MANIFEST)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("add").arg("baz@not-a-version"),
+                execs().with_status(101));
+    assert_eq!(manifest(&p).as_slice(), MANIFEST);
+});
+
+test!(rm {
+    let p = project("foo")
+        .file("Cargo.toml", "\
+[package]
+name = \"foo\"
+version = \"0.5.0\"
+authors = []
+
+[dependencies]
+bar = \"0.1\"
+qux = \"0.2\"
+
+[dependencies.baz]
+# A table of its own
+version = \"0.1\"
+
+[dev-dependencies]
+bar = \"0.1\"
+")
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("rm").arg("bar"),
+                execs().with_status(0).with_stdout(format!("\
+{removing} bar from dependencies
+", removing = REMOVING).as_slice()));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("rm").arg("baz"),
+                execs().with_status(0));
+
+    assert_eq!(manifest(&p).as_slice(), "\
+[package]
+name = \"foo\"
+version = \"0.5.0\"
+authors = []
+
+[dependencies]
+qux = \"0.2\"
+
+[dev-dependencies]
+bar = \"0.1\"
+");
+});
+
+test!(rm_missing {
+    let p = project("foo")
+        .file("Cargo.toml", MANIFEST)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("rm").arg("baz").arg("--build"),
+                execs().with_status(101).with_stderr("\
+the dependency `baz` could not be found in `build-dependencies`
+"));
+});
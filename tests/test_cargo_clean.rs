@@ -1,4 +1,8 @@
+use std::io::fs::{self, PathExtensions};
+
 use support::{project, execs, main_file, basic_bin_manifest, cargo_dir};
+use support::{paths, ProjectBuilder};
+use support::registry as r;
 use hamcrest::{assert_that, existing_dir, is_not};
 
 fn setup() {
@@ -31,3 +35,131 @@ test!(different_dir {
                 execs().with_status(0).with_stdout(""));
     assert_that(&p.build_dir(), is_not(existing_dir()));
 });
+
+/// Finds the entry named `name` in the registry caches of the cargo home
+/// directory.
+fn registry_entry(dir: &str, name: &str) -> Path {
+    let root = paths::home().join(".cargo/registry").join(dir);
+    fs::readdir(&root).unwrap().into_iter().map(|p| p.join(name))
+                      .find(|p| p.exists()).unwrap()
+}
+
+/// Makes it look like `path` was last used 100 days ago.
+fn backdate(path: &Path) {
+    let modified = fs::stat(path).unwrap().modified;
+    let old = modified - 100 * 24 * 60 * 60 * 1000;
+    fs::change_file_times(path, old, old).unwrap();
+}
+
+fn registry_project() -> ProjectBuilder {
+    r::init();
+    r::mock_pkg("bar", "0.0.1", &[]);
+    r::mock_pkg("baz", "0.0.1", &[]);
+    project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+            baz = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+}
+
+test!(gc_removes_unused_entries {
+    let p = registry_project();
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    let bar_crate = registry_entry("cache", "bar-0.0.1.crate");
+    let bar_src = registry_entry("src", "bar-0.0.1");
+    backdate(&bar_crate);
+    backdate(&bar_src);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("clean").arg("--gc")
+                 .arg("--max-age").arg("30"),
+                execs().with_status(0).with_stdout(format!("\
+{removed} 2 cache entries, [..]
+", removed = "     Removed").as_slice()));
+    assert!(!bar_crate.exists());
+    assert!(!bar_src.exists());
+    assert!(registry_entry("cache", "baz-0.0.1.crate").exists());
+    assert!(registry_entry("src", "baz-0.0.1").exists());
+
+    // Whatever was removed is fetched again when needed
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+    assert!(bar_crate.exists());
+    assert!(bar_src.exists());
+});
+
+test!(gc_dry_run {
+    let p = registry_project();
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    let bar_crate = registry_entry("cache", "bar-0.0.1.crate");
+    let bar_src = registry_entry("src", "bar-0.0.1");
+    backdate(&bar_crate);
+    backdate(&bar_src);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("clean").arg("--gc")
+                 .arg("--dry-run").arg("-v"),
+                execs().with_status(0).with_stdout(format!("\
+{would} [..]bar-0.0.1[..]
+{would} [..]bar-0.0.1[..]
+{would} 2 cache entries, [..]
+", would = "Would remove").as_slice()));
+    assert!(bar_crate.exists());
+    assert!(bar_src.exists());
+});
+
+test!(gc_max_size {
+    let p = registry_project();
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    backdate(&registry_entry("cache", "bar-0.0.1.crate"));
+
+    // The least recently used entry goes first
+    let baz_crate = registry_entry("cache", "baz-0.0.1.crate");
+    assert_that(p.process(cargo_dir().join("cargo")).arg("clean").arg("--gc")
+                 .arg("--max-size").arg("0").arg("--dry-run").arg("-v"),
+                execs().with_status(0).with_stdout(format!("\
+{would} [..]bar-0.0.1.crate
+{would} [..]
+{would} [..]
+{would} [..]
+{would} 4 cache entries, [..]
+", would = "Would remove").as_slice()));
+    assert!(baz_crate.exists());
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("clean").arg("--gc")
+                 .arg("--max-size").arg("1G"),
+                execs().with_status(0).with_stdout(format!("\
+{removed} 0 cache entries, 0 B
+", removed = "     Removed").as_slice()));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("clean").arg("--gc")
+                 .arg("--max-size").arg("lots"),
+                execs().with_status(101).with_stderr("\
+invalid size `lots`, expected a number of bytes optionally followed by K, M \
+or G
+"));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("clean").arg("--gc")
+                 .arg("--max-size").arg("20000000000G"),
+                execs().with_status(101).with_stderr("\
+invalid size `20000000000G`, it is too large
+"));
+});
+
+test!(gc_options_need_gc {
+    let p = project("foo")
+              .file("Cargo.toml", basic_bin_manifest("foo").as_slice())
+              .file("src/foo.rs", main_file(r#""i am foo""#, &[]).as_slice());
+
+    assert_that(p.cargo_process("clean").arg("--dry-run"),
+                execs().with_status(1).with_stderr("\
+`--max-age`, `--max-size` and `--dry-run` can only be used with `--gc`
+"));
+});
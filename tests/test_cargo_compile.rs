@@ -1489,6 +1489,82 @@ test!(cargo_platform_specific_dependency_wrong_platform {
     assert!(lockfile.as_slice().contains("bar"))
 });
 
+#[cfg(unix)]
+test!(cargo_platform_cfg_dependency {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+
+            name = "foo"
+            version = "0.5.0"
+            authors = ["wycats@example.com"]
+
+            [target."cfg(unix)".dependencies.bar]
+            path = "bar"
+            [target."cfg(all(windows, not(unix)))".dependencies.baz]
+            path = "baz"
+        "#)
+        .file("src/main.rs",
+              main_file(r#""{}", bar::gimme()"#, &["bar"]).as_slice())
+        .file("bar/Cargo.toml", r#"
+            [project]
+
+            name = "bar"
+            version = "0.5.0"
+            authors = ["wycats@example.com"]
+        "#)
+        .file("bar/src/lib.rs", r#"
+            pub fn gimme() -> String {
+                "test passed".to_string()
+            }
+        "#)
+        .file("baz/Cargo.toml", r#"
+            [project]
+
+            name = "baz"
+            version = "0.5.0"
+            authors = ["wycats@example.com"]
+        "#)
+        .file("baz/src/lib.rs", r#"
+            invalid rust file, should not be compiled
+        "#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0)
+                       .with_stdout(format!("{} bar v0.5.0 ({})\n\
+                                             {} foo v0.5.0 ({})\n",
+                                            COMPILING, p.url(),
+                                            COMPILING, p.url())));
+
+    assert_that(
+      process(p.bin("foo")).unwrap(),
+      execs().with_stdout("test passed\n"));
+});
+
+test!(cargo_platform_bad_cfg_expression {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+
+            name = "foo"
+            version = "0.5.0"
+            authors = ["wycats@example.com"]
+
+            [target."cfg(not(unix, windows))".dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101)
+                       .with_stderr("\
+failed to parse manifest at `[..]`
+Cargo.toml is not a valid manifest
+
+failed to parse `cfg(not(unix, windows))` as a cfg expression
+"));
+});
+
 test!(example_bin_same_name {
     let p = project("foo")
         .file("Cargo.toml", r#"
@@ -241,11 +241,18 @@ test!(links_duplicates {
     assert_that(p.cargo_process("build"),
                 execs().with_status(101)
                        .with_stderr("\
-native library `a` is being linked to by more than one package, and can only be \
-linked to by one package
+failed to select a version for `a` (required by `foo`):
+all possible versions conflict with previously selected packages
 
-  [..] v0.5.0 (file://[..])
-  [..] v0.5.0 (file://[..])
+  `a *` is required through:
+    foo v0.5.0 (file://[..])
+      requires `a *`
+
+  previously selected `foo v0.5.0 (file://[..])`, which is the root package
+
+  rejected versions of `a`:
+    0.5.0: links to native library `a`, which is already linked to by \
+`foo v0.5.0 (file://[..])`
 "));
 });
 
@@ -1002,3 +1009,227 @@ test!(test_dev_dep_build_script {
 
     assert_that(p.cargo_process("test"), execs().with_status(0));
 });
+
+test!(rerun_if_changed {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("src/lib.rs", "")
+        .file("foo.txt", "")
+        .file("build.rs", r#"
+            fn main() {
+                println!("cargo:rerun-if-changed=foo.txt");
+            }
+        "#);
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0));
+    p.root().move_into_the_past().unwrap();
+
+    // Files which weren't declared don't cause a rerun
+    File::create(&p.root().join("some-new-file")).unwrap();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(""));
+
+    File::create(&p.root().join("foo.txt")).write_str("bar").unwrap();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build").arg("-v"),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{compiling} foo v0.5.0 (file://[..])
+{running} `[..]build-script-build[..]`
+{running} `rustc [..] --crate-name foo [..]`
+", compiling = COMPILING, running = RUNNING).as_slice()));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(""));
+});
+
+test!(rerun_if_changed_still_tracks_build_script {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("src/lib.rs", "")
+        .file("foo.txt", "")
+        .file("build.rs", r#"
+            fn main() {
+                println!("cargo:rerun-if-changed=foo.txt");
+            }
+        "#);
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0));
+    p.root().move_into_the_past().unwrap();
+
+    File::create(&p.root().join("build.rs")).write_str(r#"
+        fn main() {
+            println!("cargo:rerun-if-changed=foo.txt");
+            println!("cargo:rustc-env=FOO=bar");
+        }
+    "#).unwrap();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build").arg("-v"),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{compiling} foo v0.5.0 (file://[..])
+{running} `rustc build.rs [..]`
+{running} `[..]build-script-build[..]`
+{running} `rustc [..] --crate-name foo [..]`
+", compiling = COMPILING, running = RUNNING).as_slice()));
+});
+
+test!(rerun_if_env_changed {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("src/lib.rs", "")
+        .file("build.rs", r#"
+            fn main() {
+                println!("cargo:rerun-if-env-changed=FOO");
+            }
+        "#);
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0));
+    p.root().move_into_the_past().unwrap();
+
+    File::create(&p.root().join("some-new-file")).unwrap();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(""));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build").arg("-v")
+                 .env("FOO", Some("bar")),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{compiling} foo v0.5.0 (file://[..])
+{running} `[..]build-script-build[..]`
+{running} `rustc [..] --crate-name foo [..]`
+", compiling = COMPILING, running = RUNNING).as_slice()));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .env("FOO", Some("bar")),
+                execs().with_status(0).with_stdout(""));
+});
+
+test!(rustc_cfg_and_env {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("src/main.rs", r#"
+            #[cfg(foo)]
+            fn foo() -> &'static str { env!("FOO") }
+
+            fn main() {
+                println!("{}", foo());
+            }
+        "#)
+        .file("build.rs", r#"
+            fn main() {
+                println!("cargo:rustc-cfg=foo");
+                println!("cargo:rustc-env=FOO=bar=baz");
+            }
+        "#);
+
+    assert_that(p.cargo_process("run"),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{compiling} foo v0.5.0 (file://[..])
+{running} `target[..]foo`
+bar=baz
+", compiling = COMPILING, running = RUNNING).as_slice()));
+});
+
+test!(rustc_link_search_and_lib {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("src/lib.rs", "")
+        .file("build.rs", r#"
+            fn main() {
+                println!("cargo:rustc-link-search=native=/dummy/path1");
+                println!("cargo:rustc-link-search=/dummy/path2");
+                println!("cargo:rustc-link-lib=static=nonexistinglib");
+            }
+        "#);
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(101)
+                       .with_stdout(format!("\
+{compiling} foo v0.5.0 (file://[..])
+{running} `rustc build.rs [..]`
+{running} `[..]build-script-build[..]`
+{running} `rustc [..] --crate-name foo [..] \
+        -L native=/dummy/path1 -L /dummy/path2 -l nonexistinglib:static`
+", compiling = COMPILING, running = RUNNING).as_slice()));
+});
+
+test!(rustc_link_lib_invalid_kind {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("src/lib.rs", "")
+        .file("build.rs", r#"
+            fn main() {
+                println!("cargo:rustc-link-lib=wrong=foo");
+            }
+        "#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101)
+                       .with_stderr(format!("\
+Invalid library kind in build script of `foo v0.5.0 ({})`: \
+`cargo:rustc-link-lib=wrong=foo`
+", p.url()).as_slice()));
+});
+
+test!(rustc_env_without_value {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("src/lib.rs", "")
+        .file("build.rs", r#"
+            fn main() {
+                println!("cargo:rustc-env=FOO");
+            }
+        "#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101)
+                       .with_stderr(format!("\
+Variable in rustc-env has no value in build script of `foo v0.5.0 ({})`: \
+`cargo:rustc-env=FOO`
+", p.url()).as_slice()));
+});
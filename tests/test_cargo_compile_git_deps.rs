@@ -6,7 +6,8 @@ use git2;
 use support::{ProjectBuilder, project, execs, main_file};
 use support::{cargo_dir, path2url};
 use support::{COMPILING, UPDATING, RUNNING};
-use support::paths::PathExt;
+use support::paths::{self, PathExt};
+use support::http::FileServer;
 use hamcrest::{assert_that,existing_file};
 use cargo;
 use cargo::util::{ProcessError, process};
@@ -602,9 +603,12 @@ test!(recompilation {
 
     // Update the dependency and carry on!
     assert_that(p.process(cargo_dir().join("cargo")).arg("update"),
-                execs().with_stdout(format!("{} git repository `{}`",
+                execs().with_stdout(format!("{} git repository `{}`\n\
+                                             {} bar v0.5.0 (#[..]) -> \
+                                             v0.5.0 (#[..])\n",
                                             UPDATING,
-                                            git_project.url())));
+                                            git_project.url(),
+                                            UPDATING)));
     println!("going for the last compile");
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
                 execs().with_stdout(format!("{} bar v0.5.0 ({}#[..])\n\
@@ -717,9 +721,12 @@ test!(update_with_shared_deps {
     println!("dep1 aggressive update");
     assert_that(p.process(cargo_dir().join("cargo")).arg("update").arg("dep1")
                  .arg("--aggressive"),
-                execs().with_stdout(format!("{} git repository `{}`",
+                execs().with_stdout(format!("{} git repository `{}`\n\
+                                             {} bar v0.5.0 (#[..]) -> \
+                                             v0.5.0 (#[..])\n",
                                             UPDATING,
-                                            git_project.url())));
+                                            git_project.url(),
+                                            UPDATING)));
 
     // Make sure we still only compile one version of the git repo
     println!("build");
@@ -843,8 +850,9 @@ test!(two_deps_only_update_one {
     assert_that(project.process(cargo_dir().join("cargo")).arg("update")
                        .arg("-p").arg("dep1"),
         execs()
-        .with_stdout(format!("{} git repository `{}`\n",
-                             UPDATING, git1.url()))
+        .with_stdout(format!("{} git repository `{}`\n\
+                              {} dep1 v0.5.0 (#[..]) -> v0.5.0 (#[..])\n",
+                             UPDATING, git1.url(), UPDATING))
         .with_stderr(""));
 });
 
@@ -1001,9 +1009,12 @@ test!(dep_with_changed_submodule {
     assert_that(project.process(cargo_dir().join("cargo")).arg("update").arg("-v"),
                 execs()
                 .with_stderr("")
-                .with_stdout(format!("{} git repository `{}`",
+                .with_stdout(format!("{} git repository `{}`\n\
+                                      {} dep1 v0.5.0 (#[..]) -> \
+                                      v0.5.0 (#[..])\n",
                                      UPDATING,
-                                     git_project.url())));
+                                     git_project.url(),
+                                     UPDATING)));
 
     println!("last run");
     assert_that(project.process(cargo_dir().join("cargo")).arg("run"), execs()
@@ -1638,3 +1649,115 @@ test!(switch_sources {
 {compiling} project v0.5.0 ([..])
 ", updating = UPDATING, compiling = COMPILING).as_slice()));
 });
+
+test!(failed_fetches_are_retried {
+    let server = FileServer::failing(&paths::root(), 1);
+    let p = project("foo")
+        .file("Cargo.toml", format!(r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            git = "{}bar"
+        "#, server.url()).as_slice())
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [net]
+            retry = 1
+        "#);
+
+    // The server never has the repository, so the retried fetch fails too.
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(101).with_stdout(format!("\
+{updating} git repository `{url}bar`
+", updating = UPDATING, url = server.url()).as_slice())
+                      .with_stderr(format!("\
+warning: spurious network error (1 tries remaining): failed to clone into: \
+[..]
+Unable to update {url}bar
+
+Caused by:
+  failed to clone into: [..]
+
+Caused by:
+  [..] status code: 404
+", url = server.url())));
+    assert_eq!(server.requests(), vec![
+        "503 /bar/info/refs?service=git-upload-pack".to_string(),
+        "404 /bar/info/refs?service=git-upload-pack".to_string(),
+    ]);
+});
+
+test!(stalled_fetches_time_out {
+    let server = FileServer::stalling(&paths::root(), 1);
+    let p = project("foo")
+        .file("Cargo.toml", format!(r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            git = "{}bar"
+        "#, server.url()).as_slice())
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [http]
+            timeout = 1
+            [net]
+            retry = 1
+        "#);
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(101).with_stderr(format!("\
+warning: spurious network error (1 tries remaining): failed to clone into: \
+[..]
+Unable to update {url}bar
+
+Caused by:
+  failed to clone into: [..]
+
+Caused by:
+  [..] status code: 404
+", url = server.url())));
+    assert_eq!(server.requests(), vec![
+        "stalled /bar/info/refs?service=git-upload-pack".to_string(),
+        "404 /bar/info/refs?service=git-upload-pack".to_string(),
+    ]);
+});
+
+test!(tls_configuration_applies_to_fetches {
+    let server = FileServer::new(&paths::root());
+    let p = project("foo")
+        .file("Cargo.toml", format!(r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            git = "{}bar"
+        "#, server.url()).as_slice())
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [http]
+            cainfo = 1
+        "#);
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(101).with_stderr(format!("\
+Unable to update {url}bar
+
+Caused by:
+  failed to clone into: [..]
+
+Caused by:
+  invalid configuration for key `http.cainfo`
+
+Caused by:
+  expected a string, but found a integer
+", url = server.url())));
+    assert_eq!(server.requests(), Vec::<String>::new());
+});
@@ -653,3 +653,115 @@ test!(everything_in_the_lockfile {
     assert!(lockfile.contains(r#"name = "d2""#), "d2 not found\n{}", lockfile);
     assert!(lockfile.contains(r#"name = "d3""#), "d3 not found\n{}", lockfile);
 });
+
+test!(build_dep_features_not_unified {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            build = "build.rs"
+
+            [dependencies.d1]
+            path = "d1"
+            [build-dependencies.d1]
+            path = "d1"
+            features = ["host"]
+        "#)
+        .file("build.rs", "
+            extern crate d1;
+            fn main() { d1::host(); }
+        ")
+        .file("src/main.rs", "
+            extern crate d1;
+            fn main() { d1::target(); }
+        ")
+        .file("d1/Cargo.toml", r#"
+            [package]
+            name = "d1"
+            version = "0.0.1"
+            authors = []
+
+            [features]
+            host = []
+        "#)
+        .file("d1/src/lib.rs", r#"
+            #[cfg(feature = "host")] pub fn host() {}
+            #[cfg(not(feature = "host"))] pub fn target() {}
+        "#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} d1 v0.0.1 ({dir})
+{compiling} d1 v0.0.1 ({dir})
+{compiling} foo v0.0.1 ({dir})
+", compiling = COMPILING, dir = p.url()).as_slice()));
+    assert_that(p.process(p.bin("foo")), execs().with_status(0));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(""));
+});
+
+test!(build_dep_features_shared_dependencies {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            build = "build.rs"
+
+            [dependencies.d1]
+            path = "d1"
+            [build-dependencies.d1]
+            path = "d1"
+            features = ["host"]
+        "#)
+        .file("build.rs", "
+            extern crate d1;
+            fn main() { d1::host(); }
+        ")
+        .file("src/main.rs", "
+            extern crate d1;
+            fn main() { d1::target(); }
+        ")
+        .file("d1/Cargo.toml", r#"
+            [package]
+            name = "d1"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.d2]
+            path = "../d2"
+
+            [features]
+            host = []
+        "#)
+        .file("d1/src/lib.rs", r#"
+            extern crate d2;
+            #[cfg(feature = "host")] pub fn host() { d2::d2() }
+            #[cfg(not(feature = "host"))] pub fn target() { d2::d2() }
+        "#)
+        .file("d2/Cargo.toml", r#"
+            [package]
+            name = "d2"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("d2/src/lib.rs", "pub fn d2() {}");
+
+    // `d2` has the same features for the host and the target, so it is only
+    // compiled once.
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} d2 v0.0.1 ({dir})
+{compiling} d1 v0.0.1 ({dir})
+{compiling} d1 v0.0.1 ({dir})
+{compiling} foo v0.0.1 ({dir})
+", compiling = COMPILING, dir = p.url()).as_slice()));
+    assert_that(p.process(p.bin("foo")), execs().with_status(0));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(""));
+});
@@ -0,0 +1,217 @@
+use std::io::File;
+use std::os;
+
+use support::{project, execs, cargo_dir, paths};
+use support::{COMPILING, INSTALLING, REMOVING};
+use hamcrest::{assert_that, existing_file, is_not};
+
+fn setup() {
+}
+
+fn installed_bin(root: &Path, name: &str) -> Path {
+    root.join("bin").join(format!("{}{}", name, os::consts::EXE_SUFFIX))
+}
+
+test!(install_from_path {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    let root = paths::root().join("install-root");
+
+    assert_that(p.cargo_process("install").arg("--path").arg(".")
+                 .arg("--root").arg(&root),
+                execs().with_status(0).with_stdout(format!("\
+{installing} foo v0.1.0 ({url})
+{compiling} foo v0.1.0 ({url})
+{installing} {bin}
+",
+        installing = INSTALLING, compiling = COMPILING, url = p.url(),
+        bin = installed_bin(&root, "foo").display())));
+    assert_that(&installed_bin(&root, "foo"), existing_file());
+    assert_that(&root.join(".crates.toml"), existing_file());
+});
+
+test!(install_multiple_binaries {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+
+            [[bin]]
+            name = "foo"
+
+            [[bin]]
+            name = "bar"
+        "#)
+        .file("src/foo.rs", "fn main() {}")
+        .file("src/bar.rs", "fn main() {}");
+    let root = paths::root().join("install-root");
+
+    assert_that(p.cargo_process("install").arg("--path").arg(".")
+                 .arg("--root").arg(&root),
+                execs().with_status(0));
+    assert_that(&installed_bin(&root, "foo"), existing_file());
+    assert_that(&installed_bin(&root, "bar"), existing_file());
+
+    let list = File::open(&root.join(".crates.toml")).read_to_string().unwrap();
+    assert!(list.as_slice().contains("foo 0.1.0"), "{}", list);
+});
+
+test!(install_root_from_env {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    let root = paths::root().join("env-root");
+
+    assert_that(p.cargo_process("install").arg("--path").arg(".")
+                 .env("CARGO_INSTALL_ROOT",
+                      Some(root.display().to_string().as_slice())),
+                execs().with_status(0));
+    assert_that(&installed_bin(&root, "foo"), existing_file());
+});
+
+test!(install_defaults_to_cargo_home {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("install").arg("--path").arg("."),
+                execs().with_status(0));
+    assert_that(&installed_bin(&paths::home().join(".cargo"), "foo"),
+                existing_file());
+});
+
+test!(install_no_binaries {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+    let root = paths::root().join("install-root");
+
+    assert_that(p.cargo_process("install").arg("--path").arg(".")
+                 .arg("--root").arg(&root),
+                execs().with_status(101).with_stderr(format!("\
+specified package `foo v0.1.0 ({url})` has no binaries
+", url = p.url())));
+});
+
+test!(install_conflicting_binary {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+
+            [[bin]]
+            name = "foo"
+        "#)
+        .file("bar/src/foo.rs", "fn main() {}");
+    let root = paths::root().join("install-root");
+
+    assert_that(p.cargo_process("install").arg("--path").arg(".")
+                 .arg("--root").arg(&root),
+                execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("install")
+                 .arg("--path").arg("bar").arg("--root").arg(&root),
+                execs().with_status(101).with_stderr(format!("\
+binary `foo{exe}` already exists in destination as part of `foo v0.1.0 ({url})`
+", exe = os::consts::EXE_SUFFIX, url = p.url())));
+});
+
+test!(install_requires_crate_name {
+    let p = project("foo");
+
+    assert_that(p.cargo_process("install"),
+                execs().with_status(101).with_stderr("\
+must specify a crate to install from crates.io, or use --path or --git to \
+specify an alternate source
+"));
+});
+
+test!(uninstall_removes_binaries {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    let root = paths::root().join("install-root");
+
+    assert_that(p.cargo_process("install").arg("--path").arg(".")
+                 .arg("--root").arg(&root),
+                execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("uninstall")
+                 .arg("foo").arg("--root").arg(&root),
+                execs().with_status(0).with_stdout(format!("\
+{removing} {bin}
+", removing = REMOVING, bin = installed_bin(&root, "foo").display())));
+    assert_that(&installed_bin(&root, "foo"), is_not(existing_file()));
+
+    // the package is no longer listed as installed
+    assert_that(p.process(cargo_dir().join("cargo")).arg("uninstall")
+                 .arg("foo").arg("--root").arg(&root),
+                execs().with_status(101).with_stderr("\
+package id specification `foo` did not match any packages
+"));
+});
+
+test!(uninstall_not_installed {
+    let p = project("foo");
+    let root = paths::root().join("install-root");
+
+    assert_that(p.cargo_process("uninstall").arg("bar").arg("--root").arg(&root),
+                execs().with_status(101).with_stderr("\
+package id specification `bar` did not match any packages
+"));
+});
+
+test!(reinstall_same_package {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    let root = paths::root().join("install-root");
+
+    assert_that(p.cargo_process("install").arg("--path").arg(".")
+                 .arg("--root").arg(&root),
+                execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("install")
+                 .arg("--path").arg(".").arg("--root").arg(&root),
+                execs().with_status(0));
+    assert_that(&installed_bin(&root, "foo"), existing_file());
+});
@@ -0,0 +1,127 @@
+use std::io::{self, fs, File};
+
+use support::{project, execs, paths, cargo_dir, ProjectBuilder, COMPILING};
+use support::registry as r;
+
+use hamcrest::assert_that;
+
+fn setup() {
+    r::init();
+}
+
+fn local_path() -> Path { paths::root().join("local") }
+
+fn foo() -> ProjectBuilder {
+    project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "
+            extern crate bar;
+            fn main() {}
+        ")
+}
+
+/// Moves everything published so far to a local registry replacing the
+/// central one, leaving nothing else to build from.
+fn use_local_registry(p: &ProjectBuilder) {
+    r::local_registry(&local_path());
+    fs::rmdir_recursive(&r::registry_path()).unwrap();
+    fs::rmdir_recursive(&r::dl_path()).unwrap();
+
+    let config = p.root().join(".cargo/config");
+    fs::mkdir_recursive(&config.dir_path(), io::USER_DIR).unwrap();
+    File::create(&config).write_str(format!(r#"
+        [source.crates-io]
+        replace-with = "local"
+
+        [source.local]
+        local-registry = "{}"
+    "#, local_path().display()).as_slice()).unwrap();
+}
+
+test!(build_from_local_registry {
+    r::mock_pkg("bar", "0.0.1", &[("baz", "*", "normal")]);
+    r::mock_pkg("baz", "0.1.0", &[]);
+    let p = foo();
+    p.build();
+    use_local_registry(&p);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} baz v0.1.0 (registry file://[..])
+{compiling} bar v0.0.1 (registry file://[..])
+{compiling} foo v0.0.1 ({dir})
+", compiling = COMPILING, dir = p.url()).as_slice()));
+
+    // The lock file refers to the replaced registry, not the local one.
+    let lock = File::open(&p.root().join("Cargo.lock")).read_to_string()
+                    .unwrap();
+    assert!(lock.contains("registry+file://"), "{}", lock);
+    assert!(!lock.contains("local-registry+"), "{}", lock);
+});
+
+test!(yanked_versions_are_skipped {
+    r::mock_pkg("bar", "0.0.1", &[]);
+    r::mock_pkg_yank("bar", "0.0.2", &[], true);
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+    use_local_registry(&p);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} bar v0.0.1 (registry file://[..])
+{compiling} foo v0.0.1 ({dir})
+", compiling = COMPILING, dir = p.url()).as_slice()));
+});
+
+test!(bad_checksum {
+    r::mock_pkg("bar", "0.0.1", &[]);
+    let p = foo();
+    p.build();
+    use_local_registry(&p);
+    File::create(&local_path().join("bar-0.0.1.crate")).unwrap();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build").arg("-v"),
+                execs().with_status(101).with_stderr("\
+Unable to get packages from source
+
+Caused by:
+  Failed to verify the checksum of `bar v0.0.1 (local registry file://[..])`
+"));
+});
+
+test!(missing_index {
+    let p = foo();
+    p.build();
+    let config = p.root().join(".cargo/config");
+    fs::mkdir_recursive(&config.dir_path(), io::USER_DIR).unwrap();
+    File::create(&config).write_str(format!(r#"
+        [source.crates-io]
+        replace-with = "local"
+
+        [source.local]
+        local-registry = "{}"
+    "#, local_path().display()).as_slice()).unwrap();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(101).with_stderr(format!("\
+local registry path is not a directory with an index: {}
+", local_path().display()).as_slice()));
+});
@@ -0,0 +1,174 @@
+use support::{project, execs, cargo_dir, RUNNING};
+use support::{UPDATING, DOWNLOADING, DOWNLOADED};
+use support::registry as r;
+use hamcrest::assert_that;
+
+fn setup() {
+}
+
+test!(build_json_messages {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "")
+        .file("src/main.rs", "extern crate foo; fn main() {}");
+
+    assert_that(p.cargo_process("build").arg("--message-format").arg("json"),
+                execs().with_status(0)
+                       .with_stdout("\
+{\"reason\":\"compiling-package\",\"package_id\":\"foo 0.0.1 ([..])\",\
+\"fresh\":false}
+{\"reason\":\"compiler-artifact\",\"package_id\":\"foo 0.0.1 ([..])\",\
+\"target\":\"foo\",\"kind\":[\"lib\"],\"profile\":\"compile\",\
+\"filenames\":[\"[..]libfoo[..].rlib\"],\"fresh\":false}
+{\"reason\":\"compiler-artifact\",\"package_id\":\"foo 0.0.1 ([..])\",\
+\"target\":\"foo\",\"kind\":[\"bin\"],\"profile\":\"compile\",\
+\"filenames\":[\"[..]foo[..]\"],\"fresh\":false}
+"));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--message-format").arg("json"),
+                execs().with_status(0)
+                       .with_stdout("\
+{\"reason\":\"compiling-package\",\"package_id\":\"foo 0.0.1 ([..])\",\
+\"fresh\":true}
+{\"reason\":\"compiler-artifact\",\"package_id\":\"foo 0.0.1 ([..])\",\
+\"target\":\"foo\",\"kind\":[\"lib\"],\"profile\":\"compile\",\
+\"filenames\":[\"[..]libfoo[..].rlib\"],\"fresh\":true}
+{\"reason\":\"compiler-artifact\",\"package_id\":\"foo 0.0.1 ([..])\",\
+\"target\":\"foo\",\"kind\":[\"bin\"],\"profile\":\"compile\",\
+\"filenames\":[\"[..]foo[..]\"],\"fresh\":true}
+"));
+});
+
+test!(build_script_output {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("src/lib.rs", "")
+        .file("build.rs", r#"
+            fn main() {
+                println!("cargo:foo=bar");
+            }
+        "#);
+
+    assert_that(p.cargo_process("build").arg("--message-format").arg("json"),
+                execs().with_status(0)
+                       .with_stdout("\
+{\"reason\":\"compiling-package\",\"package_id\":\"foo 0.0.1 ([..])\",\
+\"fresh\":false}
+{\"reason\":\"build-script-executed\",\"package_id\":\"foo 0.0.1 ([..])\",\
+\"linked_libs\":[],\"linked_paths\":[],\"metadata\":[[\"foo\",\"bar\"]]}
+{\"reason\":\"compiler-artifact\",\"package_id\":\"foo 0.0.1 ([..])\",\
+\"target\":\"foo\",\"kind\":[\"lib\"],\"profile\":\"compile\",\
+\"filenames\":[\"[..]libfoo[..].rlib\"],\"fresh\":false}
+"));
+});
+
+test!(build_failure {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "invalid rust code");
+
+    assert_that(p.cargo_process("build").arg("--message-format").arg("json"),
+                execs().with_status(101)
+                       .with_stdout("\
+{\"reason\":\"compiling-package\",\"package_id\":\"foo 0.0.1 ([..])\",\
+\"fresh\":false}
+{\"reason\":\"build-failed\",\"package_id\":\"foo 0.0.1 ([..])\",\
+\"message\":\"Could not compile `foo`.\"}
+"));
+});
+
+test!(run_json_messages {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", r#"
+            fn main() { println!("hello"); }
+        "#);
+
+    // Only the JSON messages and the output of the program itself go to
+    // stdout, the status of cargo goes to stderr.
+    assert_that(p.cargo_process("run").arg("--message-format").arg("json"),
+                execs().with_status(0)
+                       .with_stdout("\
+{\"reason\":\"compiling-package\",\"package_id\":\"foo 0.0.1 ([..])\",\
+\"fresh\":false}
+{\"reason\":\"compiler-artifact\",\"package_id\":\"foo 0.0.1 ([..])\",\
+\"target\":\"foo\",\"kind\":[\"bin\"],\"profile\":\"compile\",\
+\"filenames\":[\"[..]foo[..]\"],\"fresh\":false}
+hello
+")
+                       .with_stderr(format!("\
+{running} `target[..]foo[..]`
+", running = RUNNING)));
+});
+
+test!(registry_status_goes_to_stderr {
+    r::init();
+    r::mock_pkg("bar", "0.0.1", &[]);
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("--message-format").arg("json"),
+                execs().with_status(0)
+                       .with_stdout("\
+{\"reason\":\"compiling-package\",\"package_id\":\"bar 0.0.1 ([..])\",\
+\"fresh\":false}
+{\"reason\":\"compiler-artifact\",\"package_id\":\"bar 0.0.1 ([..])\",\
+\"target\":\"bar\",\"kind\":[\"lib\"],\"profile\":\"compile\",\
+\"filenames\":[\"[..]libbar[..].rlib\"],\"fresh\":false}
+{\"reason\":\"compiling-package\",\"package_id\":\"foo 0.0.1 ([..])\",\
+\"fresh\":false}
+{\"reason\":\"compiler-artifact\",\"package_id\":\"foo 0.0.1 ([..])\",\
+\"target\":\"foo\",\"kind\":[\"lib\"],\"profile\":\"compile\",\
+\"filenames\":[\"[..]libfoo[..].rlib\"],\"fresh\":false}
+")
+                       .with_stderr(format!("\
+{updating} registry `[..]`
+{downloading} bar v0.0.1 (registry file://[..])
+{downloaded} bar v0.0.1 (registry file://[..]) [1/1]
+", updating = UPDATING, downloading = DOWNLOADING, downloaded = DOWNLOADED)));
+});
+
+test!(invalid_message_format {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("--message-format").arg("xml"),
+                execs().with_status(1));
+});
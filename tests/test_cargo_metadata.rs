@@ -0,0 +1,133 @@
+use support::{project, execs};
+use hamcrest::assert_that;
+
+fn setup() {
+}
+
+test!(path_dependency {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("metadata"),
+                execs().with_status(0)
+                       .with_stdout("\
+{\"packages\":[\
+{\"name\":\"bar\",\"version\":\"0.5.0\",\"id\":\"bar 0.5.0 ([..])\",\
+\"source\":null,\"manifest_path\":\"[..]bar[..]Cargo.toml\",\
+\"targets\":[{\"kind\":[\"lib\"],\"name\":\"bar\",[..]}],\"features\":{}},\
+{\"name\":\"foo\",\"version\":\"0.5.0\",\"id\":\"foo 0.5.0 ([..])\",\
+\"source\":null,\"manifest_path\":\"[..]foo[..]Cargo.toml\",\
+\"targets\":[{\"kind\":[\"lib\"],\"name\":\"foo\",[..]}],\"features\":{}}],\
+\"resolve\":{\"root\":\"foo 0.5.0 ([..])\",\"nodes\":[\
+{\"id\":\"bar 0.5.0 ([..])\",\"dependencies\":[],\"features\":[]},\
+{\"id\":\"foo 0.5.0 ([..])\",\"dependencies\":[\"bar 0.5.0 ([..])\"],\
+\"features\":[]}]},\
+\"version\":1}
+"));
+});
+
+test!(features {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [features]
+            default = ["a"]
+            a = []
+            b = []
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("metadata"),
+                execs().with_status(0)
+                       .with_stdout("\
+{\"packages\":[{[..]\"features\":{\"a\":[],\"b\":[],\"default\":[\"a\"]}}],\
+\"resolve\":{\"root\":\"foo 0.5.0 ([..])\",\"nodes\":[\
+{\"id\":\"foo 0.5.0 ([..])\",\"dependencies\":[],\
+\"features\":[\"a\",\"default\"]}]},\
+\"version\":1}
+"));
+
+    assert_that(p.cargo_process("metadata").arg("--no-default-features")
+                 .arg("--features").arg("b"),
+                execs().with_status(0)
+                       .with_stdout("\
+{\"packages\":[{[..]}],\
+\"resolve\":{\"root\":\"foo 0.5.0 ([..])\",\"nodes\":[\
+{\"id\":\"foo 0.5.0 ([..])\",\"dependencies\":[],\"features\":[\"b\"]}]},\
+\"version\":1}
+"));
+});
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+test!(target_filter {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [target."cfg(windows)".dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("metadata"),
+                execs().with_status(0)
+                       .with_stdout("\
+{\"packages\":[{\"name\":\"bar\"[..]},{\"name\":\"foo\"[..]}],\"resolve\":[..]
+"));
+
+    assert_that(p.cargo_process("metadata")
+                 .arg("--target").arg("x86_64-unknown-linux-gnu"),
+                execs().with_status(0)
+                       .with_stdout("\
+{\"packages\":[{\"name\":\"foo\"[..]}],\"resolve\":{\"root\":\"foo 0.5.0 ([..])\",\
+\"nodes\":[{\"id\":\"foo 0.5.0 ([..])\",\"dependencies\":[],\"features\":[]}]},\
+\"version\":1}
+"));
+});
+
+test!(unsupported_version {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("metadata").arg("--format-version").arg("2"),
+                execs().with_status(101)
+                       .with_stderr("\
+metadata version 2 not supported, only 1 is currently supported
+"));
+});
@@ -0,0 +1,187 @@
+use std::io::File;
+use git2;
+
+use support::{project, execs, cargo_dir, UPDATING};
+use support::paths;
+use support::git;
+use support::registry as r;
+use hamcrest::{assert_that, existing_file, is_not};
+
+fn setup() {
+    r::init();
+}
+
+test!(registry_dependencies {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1"
+            [dev-dependencies]
+            baz = "*"
+            [build-dependencies]
+            qux = "*"
+        "#)
+        .file("src/lib.rs", "");
+    p.build();
+
+    r::mock_pkg("bar", "0.1.0", &[]);
+    r::mock_pkg("baz", "0.1.0", &[]);
+    r::mock_pkg("qux", "0.1.0", &[]);
+    assert_that(p.process(cargo_dir().join("cargo")).arg("generate-lockfile"),
+                execs().with_status(0));
+
+    r::mock_pkg("bar", "0.1.1", &[]);
+    r::mock_pkg("bar", "0.2.0", &[]);
+    r::mock_pkg("qux", "0.2.0", &[]);
+    assert_that(p.process(cargo_dir().join("cargo")).arg("outdated"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `[..]`
+Name  Project  Compat  Latest  Kind
+bar   0.1.0    0.1.1   0.2.0   normal
+qux   0.1.0    0.2.0   0.2.0   build
+", updating = UPDATING)));
+});
+
+test!(up_to_date {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1"
+        "#)
+        .file("src/lib.rs", "");
+    p.build();
+
+    r::mock_pkg("bar", "0.1.0", &[]);
+    assert_that(p.process(cargo_dir().join("cargo")).arg("generate-lockfile"),
+                execs().with_status(0));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("outdated"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `[..]`
+All dependencies are up to date
+", updating = UPDATING)));
+});
+
+test!(json_output {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1"
+        "#)
+        .file("src/lib.rs", "");
+    p.build();
+
+    r::mock_pkg("bar", "0.1.0", &[]);
+    assert_that(p.process(cargo_dir().join("cargo")).arg("generate-lockfile"),
+                execs().with_status(0));
+
+    r::mock_pkg("bar", "0.2.0", &[]);
+    assert_that(p.process(cargo_dir().join("cargo")).arg("outdated")
+                 .arg("--format").arg("json"),
+                execs().with_status(0).with_stdout("\
+{\"dependencies\":[{\"name\":\"bar\",\"source\":\"registry+[..]\",\
+\"kind\":\"normal\",\"project\":\"0.1.0\",\"compat\":null,\
+\"latest\":\"0.2.0\"}]}
+")
+                       .with_stderr(format!("\
+{updating} registry `[..]`
+", updating = UPDATING)));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("outdated")
+                 .arg("--format").arg("xml"),
+                execs().with_status(101).with_stderr("\
+unknown output format `xml`
+"));
+});
+
+test!(lockfile_not_written {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1"
+        "#)
+        .file("src/lib.rs", "");
+    p.build();
+
+    r::mock_pkg("bar", "0.1.0", &[]);
+    assert_that(p.process(cargo_dir().join("cargo")).arg("outdated"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `[..]`
+All dependencies are up to date
+", updating = UPDATING)));
+    assert_that(&p.root().join("Cargo.lock"), is_not(existing_file()));
+});
+
+test!(git_dependencies {
+    let bar = git::repo(&paths::root().join("bar"))
+        .file("Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+    bar.build();
+
+    let p = project("foo")
+        .file("Cargo.toml", format!(r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            git = '{}'
+        "#, bar.url()))
+        .file("src/lib.rs", "");
+    p.build();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("generate-lockfile"),
+                execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("outdated"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} git repository `[..]`
+All dependencies are up to date
+", updating = UPDATING)));
+
+    // Move the branch of the dependency forward.
+    File::create(&paths::root().join("bar/src/lib.rs"))
+         .write_str("pub fn bar() {}").unwrap();
+    let repo = git2::Repository::open(&paths::root().join("bar")).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(&Path::new("src/lib.rs")).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = repo.signature().unwrap();
+    let head = repo.head().unwrap().target().unwrap();
+    let parent = repo.find_commit(head).unwrap();
+    repo.commit(Some("HEAD"), &sig, &sig, "Change", &tree,
+                &[&parent]).unwrap();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("outdated"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} git repository `[..]`
+Name  Project[..]Compat[..]Latest[..]Kind
+bar   0.5.0#[..]  0.5.0#[..]  0.5.0#[..]  normal
+", updating = UPDATING)));
+});
@@ -1,4 +1,5 @@
 use std::io::{self, fs, File, MemReader};
+use std::io::fs::PathExtensions;
 
 use flate2::reader::GzDecoder;
 use tar::Archive;
@@ -8,6 +9,7 @@ use support::{project, execs};
 use support::{UPDATING, PACKAGING, UPLOADING};
 use support::paths;
 use support::git::repo;
+use support::registry as r;
 
 use hamcrest::assert_that;
 
@@ -94,7 +96,7 @@ test!(git_deps {
 
     assert_that(p.cargo_process("publish").arg("-v").arg("--no-verify"),
                 execs().with_status(101).with_stderr("\
-all dependencies must come from the same source.
+all dependencies must come from a registry.
 dependency `foo` comes from git://path/to/nowhere instead
 "));
 });
@@ -127,3 +129,126 @@ all path dependencies must have a version specified when publishing.
 dependency `bar` does not specify a version
 "));
 });
+
+test!(named_registry {
+    let config = paths::root().join(".cargo/config");
+    File::create(&config).write_str(format!(r#"
+        [registries.alternative]
+            index = "{reg}"
+            token = "api-token"
+    "#, reg = registry()).as_slice()).unwrap();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("publish").arg("--no-verify")
+                 .arg("--registry").arg("alternative"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `{reg}`
+{packaging} foo v0.0.1 ({dir})
+{uploading} foo v0.0.1 ({dir})
+",
+        updating = UPDATING,
+        uploading = UPLOADING,
+        packaging = PACKAGING,
+        dir = p.url(),
+        reg = registry()).as_slice()));
+    assert!(upload_path().join("api/v1/crates/new").exists());
+});
+
+test!(named_registry_with_verification {
+    r::alt_init();
+    r::mock_alt_pkg("bar", "0.0.1", &[]);
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+
+            [dependencies.bar]
+            path = "bar"
+            version = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("publish").arg("--registry").arg("alternative")
+                 .arg("--token").arg("api-token"),
+                execs().with_status(0));
+    assert!(r::alt_api_path().join("api/v1/crates/new").exists());
+
+    // The path dependency was taken from the registry published to.
+    let lock = p.root().join("target/package/foo-0.0.1/Cargo.lock");
+    let lock = File::open(&lock).read_to_string().unwrap();
+    assert!(lock.contains(format!("registry+{}", r::alt_registry()).as_slice()),
+            "{}", lock);
+});
+
+test!(named_registry_with_central_dependency {
+    r::alt_init();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+
+            [dependencies]
+            baz = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("publish").arg("--no-verify")
+                 .arg("--registry").arg("alternative")
+                 .arg("--token").arg("api-token"),
+                execs().with_status(0));
+
+    // The index of the alternative registry will point `baz` to the default
+    // registry.
+    let mut f = File::open(&r::alt_api_path().join("api/v1/crates/new"))
+                     .unwrap();
+    let sz = f.read_le_u32().unwrap();
+    let json = String::from_utf8(f.read_exact(sz as usize).unwrap()).unwrap();
+    let expected = format!("\"registry\":\"{}\"", registry());
+    assert!(json.contains(expected.as_slice()), "{}", json);
+});
+
+test!(unknown_named_registry {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("publish").arg("--no-verify")
+                 .arg("--registry").arg("nope"),
+                execs().with_status(101).with_stderr("\
+no index found for registry: `nope`
+"));
+});
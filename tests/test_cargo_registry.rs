@@ -2,7 +2,8 @@ use std::io::{self, fs, File};
 use cargo::util::process;
 
 use support::{project, execs, cargo_dir};
-use support::{UPDATING, DOWNLOADING, COMPILING, PACKAGING, VERIFYING};
+use support::{UPDATING, DOWNLOADING, DOWNLOADED, COMPILING, PACKAGING};
+use support::{VERIFYING, ADDING};
 use support::paths::{self, PathExt};
 use support::registry as r;
 use support::git;
@@ -32,11 +33,12 @@ test!(simple {
                 execs().with_status(0).with_stdout(format!("\
 {updating} registry `{reg}`
 {downloading} bar v0.0.1 (registry file://[..])
+{downloaded} bar v0.0.1 (registry file://[..]) [1/1]
 {compiling} bar v0.0.1 (registry file://[..])
 {compiling} foo v0.0.1 ({dir})
 ",
         updating = UPDATING,
-        downloading = DOWNLOADING,
+        downloading = DOWNLOADING, downloaded = DOWNLOADED,
         compiling = COMPILING,
         dir = p.url(),
         reg = r::registry()).as_slice()));
@@ -74,12 +76,14 @@ test!(deps {
 {updating} registry `{reg}`
 {downloading} [..] v0.0.1 (registry file://[..])
 {downloading} [..] v0.0.1 (registry file://[..])
+{downloaded} [..] [1/2]
+{downloaded} [..] [2/2]
 {compiling} baz v0.0.1 (registry file://[..])
 {compiling} bar v0.0.1 (registry file://[..])
 {compiling} foo v0.0.1 ({dir})
 ",
         updating = UPDATING,
-        downloading = DOWNLOADING,
+        downloading = DOWNLOADING, downloaded = DOWNLOADED,
         compiling = COMPILING,
         dir = p.url(),
         reg = r::registry()).as_slice()));
@@ -134,6 +138,149 @@ Caused by:
 "));
 });
 
+test!(every_failed_download_is_reported {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bad-a = "*"
+            bad-b = "*"
+            good = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    r::mock_pkg("bad-a", "0.0.1", &[]);
+    r::mock_pkg("bad-b", "0.0.1", &[]);
+    r::mock_pkg("good", "0.0.1", &[]);
+    File::create(&r::mock_archive_dst("bad-a", "0.0.1")).unwrap();
+    File::create(&r::mock_archive_dst("bad-b", "0.0.1")).unwrap();
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(101).with_stderr("\
+Unable to get packages from source
+
+Caused by:
+  failed to download 2 of 3 packages:
+  Failed to download package `bad-[..] v0.0.1 (registry file://[..])` from \
+[..]: Failed to verify the checksum of `bad-[..] v0.0.1 (registry file://[..])`
+  Failed to download package `bad-[..] v0.0.1 (registry file://[..])` from \
+[..]: Failed to verify the checksum of `bad-[..] v0.0.1 (registry file://[..])`
+"));
+
+    // The package that could be downloaded was kept
+    let cache = paths::home().join(".cargo/registry/cache");
+    let files = fs::walk_dir(&cache).unwrap().filter_map(|f| {
+        f.filename_str().map(|s| s.to_string())
+    }).filter(|f| f.as_slice().ends_with(".crate")).collect::<Vec<_>>();
+    assert_eq!(files, vec!["good-0.0.1.crate".to_string()]);
+});
+
+test!(max_downloads {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+            baz = "*"
+            qux = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [http]
+            max-downloads = 2
+        "#);
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+    r::mock_pkg("baz", "0.0.1", &[]);
+    r::mock_pkg("qux", "0.0.1", &[]);
+
+    // The third download only starts once one of the first two has finished.
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `{reg}`
+{downloading} [..] v0.0.1 (registry file://[..])
+{downloading} [..] v0.0.1 (registry file://[..])
+{downloaded} [..] v0.0.1 (registry file://[..]) [1/3]
+{downloading} [..] v0.0.1 (registry file://[..])
+{downloaded} [..] v0.0.1 (registry file://[..]) [2/3]
+{downloaded} [..] v0.0.1 (registry file://[..]) [3/3]
+{compiling} [..] v0.0.1 (registry file://[..])
+{compiling} [..] v0.0.1 (registry file://[..])
+{compiling} [..] v0.0.1 (registry file://[..])
+{compiling} foo v0.0.1 ({dir})
+",
+        updating = UPDATING,
+        downloading = DOWNLOADING,
+        downloaded = DOWNLOADED,
+        compiling = COMPILING,
+        dir = p.url(),
+        reg = r::registry()).as_slice()));
+});
+
+test!(max_downloads_too_large {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [http]
+            max-downloads = 4294967296
+        "#);
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(101).with_stderr("\
+Unable to get packages from source
+
+Caused by:
+  `http.max-downloads` is too large, found 4294967296 in [..]config
+"));
+});
+
+test!(max_downloads_must_be_positive {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [http]
+            max-downloads = 0
+        "#);
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(101).with_stderr("\
+Unable to get packages from source
+
+Caused by:
+  `http.max-downloads` must be at least 1, found 0 in [..]config
+"));
+});
+
 test!(update_registry {
     let p = project("foo")
         .file("Cargo.toml", r#"
@@ -160,11 +307,12 @@ version required: >= 0.0.0
                 execs().with_status(0).with_stdout(format!("\
 {updating} registry `{reg}`
 {downloading} notyet v0.0.1 (registry file://[..])
+{downloaded} notyet v0.0.1 (registry file://[..]) [1/1]
 {compiling} notyet v0.0.1 (registry file://[..])
 {compiling} foo v0.0.1 ({dir})
 ",
         updating = UPDATING,
-        downloading = DOWNLOADING,
+        downloading = DOWNLOADING, downloaded = DOWNLOADED,
         compiling = COMPILING,
         dir = p.url(),
         reg = r::registry()).as_slice()));
@@ -213,13 +361,14 @@ version required: ^0.0.1
 {verifying} foo v0.0.1 ({dir})
 {updating} registry `[..]`
 {downloading} notyet v0.0.1 (registry file://[..])
+{downloaded} notyet v0.0.1 (registry file://[..]) [1/1]
 {compiling} notyet v0.0.1 (registry file://[..])
 {compiling} foo v0.0.1 ({dir}[..])
 ",
     packaging = PACKAGING,
     verifying = VERIFYING,
     updating = UPDATING,
-    downloading = DOWNLOADING,
+    downloading = DOWNLOADING, downloaded = DOWNLOADED,
     compiling = COMPILING,
     dir = p.url(),
 )));
@@ -245,9 +394,11 @@ test!(lockfile_locks {
                 execs().with_status(0).with_stdout(format!("\
 {updating} registry `[..]`
 {downloading} bar v0.0.1 (registry file://[..])
+{downloaded} bar v0.0.1 (registry file://[..]) [1/1]
 {compiling} bar v0.0.1 (registry file://[..])
 {compiling} foo v0.0.1 ({dir})
 ", updating = UPDATING, downloading = DOWNLOADING, compiling = COMPILING,
+   downloaded = DOWNLOADED,
    dir = p.url()).as_slice()));
 
     p.root().move_into_the_past().unwrap();
@@ -279,10 +430,13 @@ test!(lockfile_locks_transitively {
 {updating} registry `[..]`
 {downloading} [..] v0.0.1 (registry file://[..])
 {downloading} [..] v0.0.1 (registry file://[..])
+{downloaded} [..] [1/2]
+{downloaded} [..] [2/2]
 {compiling} baz v0.0.1 (registry file://[..])
 {compiling} bar v0.0.1 (registry file://[..])
 {compiling} foo v0.0.1 ({dir})
 ", updating = UPDATING, downloading = DOWNLOADING, compiling = COMPILING,
+   downloaded = DOWNLOADED,
    dir = p.url()).as_slice()));
 
     p.root().move_into_the_past().unwrap();
@@ -317,10 +471,13 @@ test!(yanks_are_not_used {
 {updating} registry `[..]`
 {downloading} [..] v0.0.1 (registry file://[..])
 {downloading} [..] v0.0.1 (registry file://[..])
+{downloaded} [..] [1/2]
+{downloaded} [..] [2/2]
 {compiling} baz v0.0.1 (registry file://[..])
 {compiling} bar v0.0.1 (registry file://[..])
 {compiling} foo v0.0.1 ({dir})
 ", updating = UPDATING, downloading = DOWNLOADING, compiling = COMPILING,
+   downloaded = DOWNLOADED,
    dir = p.url()).as_slice()));
 });
 
@@ -347,6 +504,10 @@ test!(relying_on_a_yank_is_bad {
 no package named `baz` found (required by `bar`)
 location searched: registry file://[..]
 version required: = 0.0.2
+versions which were yanked: 0.0.2
+required through:
+    foo v0.0.1 ([..])
+      requires `bar *`: bar v0.0.1 (registry file://[..])
 "));
 });
 
@@ -381,6 +542,117 @@ test!(yanks_in_lockfiles_are_ok {
 no package named `bar` found (required by `foo`)
 location searched: registry file://[..]
 version required: *
+versions which were yanked: 0.0.1
+"));
+});
+
+test!(yanked_pins_are_reported {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+            baz = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+    r::mock_pkg("baz", "0.0.1", &[]);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("update")
+                 .arg("--check-yanked"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `[..]`
+     Checked no package in Cargo.lock has been yanked
+", updating = UPDATING).as_slice()));
+
+    r::yank("bar", "0.0.1");
+    r::mock_pkg("bar", "0.0.2", &[]);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("update")
+                 .arg("--check-yanked"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `[..]`
+      Yanked bar v0.0.1 (registry file://[..])
+", updating = UPDATING).as_slice()));
+
+    // Updating another package leaves the yanked version locked.
+    assert_that(p.process(cargo_dir().join("cargo")).arg("update")
+                 .arg("-p").arg("baz"),
+                execs().with_status(0).with_stderr("\
+warning: package `bar v0.0.1 (registry file://[..])` in Cargo.lock has been \
+yanked
+"));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stderr("\
+warning: package `bar v0.0.1 (registry file://[..])` in Cargo.lock has been \
+yanked
+"));
+
+    // Updating it picks a version which hasn't been yanked.
+    assert_that(p.process(cargo_dir().join("cargo")).arg("update")
+                 .arg("-p").arg("bar"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `[..]`
+{updating} bar v0.0.1 -> v0.0.2
+", updating = UPDATING).as_slice())
+                       .with_stderr(""));
+});
+
+test!(aggressive_update_keeps_yanked_dependencies {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            baz = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+    r::mock_pkg("baz", "0.0.1", &[("bar", "*", "normal")]);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+
+    r::yank("bar", "0.0.1");
+    r::mock_pkg("baz", "0.0.2", &[("bar", "*", "normal")]);
+
+    // Nothing but the yanked version can be used for `bar`, which was only
+    // updated because `baz` depends on it.
+    assert_that(p.process(cargo_dir().join("cargo")).arg("update")
+                 .arg("-p").arg("baz").arg("--aggressive"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `[..]`
+{updating} baz v0.0.1 -> v0.0.2
+", updating = UPDATING).as_slice())
+                       .with_stderr("\
+warning: package `bar v0.0.1 (registry file://[..])` in Cargo.lock has been \
+yanked
+"));
+
+    // Asking for `bar` itself to be updated fails.
+    assert_that(p.process(cargo_dir().join("cargo")).arg("update")
+                 .arg("-p").arg("bar"),
+                execs().with_status(101).with_stderr("\
+no package named `bar` found (required by `baz`)
+location searched: registry file://[..]
+version required: *
+versions which were yanked: 0.0.1
+required through:
+    foo v0.0.1 ([..])
+      requires `baz *`: baz v0.0.2 (registry file://[..])
 "));
 });
 
@@ -408,7 +680,9 @@ test!(update_with_lockfile_if_packages_missing {
                 execs().with_status(0).with_stdout(format!("\
 {updating} registry `[..]`
 {downloading} bar v0.0.1 (registry file://[..])
-", updating = UPDATING, downloading = DOWNLOADING).as_slice()));
+{downloaded} bar v0.0.1 (registry file://[..]) [1/1]
+", updating = UPDATING, downloading = DOWNLOADING,
+   downloaded = DOWNLOADED).as_slice()));
 });
 
 test!(update_lockfile {
@@ -435,14 +709,113 @@ test!(update_lockfile {
                  .arg("-p").arg("bar"),
                 execs().with_status(0).with_stdout(format!("\
 {updating} registry `[..]`
+{updating} bar v0.0.1 -> v0.0.2
 ", updating = UPDATING).as_slice()));
 
     assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
                 execs().with_status(0).with_stdout(format!("\
 {downloading} [..] v0.0.2 (registry file://[..])
+{downloaded} [..] v0.0.2 (registry file://[..]) [1/1]
 {compiling} bar v0.0.2 (registry file://[..])
 {compiling} foo v0.0.1 ({dir})
-", downloading = DOWNLOADING, compiling = COMPILING,
+", downloading = DOWNLOADING, downloaded = DOWNLOADED, compiling = COMPILING,
+   dir = p.url()).as_slice()));
+});
+
+test!(update_dry_run {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+            qux = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+    r::mock_pkg("qux", "0.0.1", &[]);
+    assert_that(p.process(cargo_dir().join("cargo")).arg("generate-lockfile"),
+                execs().with_status(0));
+    let lock = p.root().join("Cargo.lock");
+    let before = File::open(&lock).read_to_string().unwrap();
+
+    r::mock_pkg("baz", "0.0.1", &[]);
+    r::mock_pkg("bar", "0.0.2", &[("baz", "*", "normal")]);
+    assert_that(p.process(cargo_dir().join("cargo")).arg("update")
+                 .arg("--dry-run"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `[..]`
+{updating} bar v0.0.1 -> v0.0.2
+{adding} baz v0.0.1
+", updating = UPDATING, adding = ADDING).as_slice())
+                       .with_stderr("\
+warning: not updating the lock file due to dry run
+"));
+    assert_eq!(File::open(&lock).read_to_string().unwrap(), before);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("update"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `[..]`
+{updating} bar v0.0.1 -> v0.0.2
+{adding} baz v0.0.1
+", updating = UPDATING, adding = ADDING).as_slice()));
+    assert!(File::open(&lock).read_to_string().unwrap() != before);
+});
+
+test!(minimal_versions {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.1.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("baz", "0.0.1", &[]);
+    r::mock_pkg("baz", "0.0.2", &[]);
+    r::mock_pkg("bar", "0.1.0", &[("baz", "*", "normal")]);
+    r::mock_pkg("bar", "0.1.1", &[("baz", "*", "normal")]);
+    r::mock_pkg("bar", "0.1.2", &[("baz", "*", "normal")]);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("generate-lockfile")
+                 .arg("--minimal-versions"),
+                execs().with_status(0));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(format!("\
+{downloading} [..] v[..] (registry file://[..])
+{downloading} [..] v[..] (registry file://[..])
+{downloaded} [..] [1/2]
+{downloaded} [..] [2/2]
+{compiling} baz v0.0.1 (registry file://[..])
+{compiling} bar v0.1.1 (registry file://[..])
+{compiling} foo v0.0.1 ({dir})
+", downloading = DOWNLOADING, downloaded = DOWNLOADED, compiling = COMPILING,
+   dir = p.url()).as_slice()));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("update"),
+                execs().with_status(0));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(format!("\
+{downloading} [..] v[..] (registry file://[..])
+{downloading} [..] v[..] (registry file://[..])
+{downloaded} [..] [1/2]
+{downloaded} [..] [2/2]
+{compiling} baz v0.0.2 (registry file://[..])
+{compiling} bar v0.1.2 (registry file://[..])
+{compiling} foo v0.0.1 ({dir})
+", downloading = DOWNLOADING, downloaded = DOWNLOADED, compiling = COMPILING,
    dir = p.url()).as_slice()));
 });
 
@@ -467,9 +840,11 @@ test!(dev_dependency_not_used {
                 execs().with_status(0).with_stdout(format!("\
 {updating} registry `[..]`
 {downloading} [..] v0.0.1 (registry file://[..])
+{downloaded} [..] v0.0.1 (registry file://[..]) [1/1]
 {compiling} bar v0.0.1 (registry file://[..])
 {compiling} foo v0.0.1 ({dir})
 ", updating = UPDATING, downloading = DOWNLOADING, compiling = COMPILING,
+   downloaded = DOWNLOADED,
    dir = p.url()).as_slice()));
 });
 
@@ -533,10 +908,12 @@ test!(updating_a_dep {
                 execs().with_status(0).with_stdout(format!("\
 {updating} registry `[..]`
 {downloading} bar v0.0.1 (registry file://[..])
+{downloaded} bar v0.0.1 (registry file://[..]) [1/1]
 {compiling} bar v0.0.1 (registry file://[..])
 {compiling} a v0.0.1 ({dir})
 {compiling} foo v0.0.1 ({dir})
 ", updating = UPDATING, downloading = DOWNLOADING, compiling = COMPILING,
+   downloaded = DOWNLOADED,
    dir = p.url()).as_slice()));
 
     File::create(&p.root().join("a/Cargo.toml")).write_str(r#"
@@ -555,10 +932,12 @@ test!(updating_a_dep {
                 execs().with_status(0).with_stdout(format!("\
 {updating} registry `[..]`
 {downloading} bar v0.1.0 (registry file://[..])
+{downloaded} bar v0.1.0 (registry file://[..]) [1/1]
 {compiling} bar v0.1.0 (registry file://[..])
 {compiling} a v0.0.1 ({dir})
 {compiling} foo v0.0.1 ({dir})
 ", updating = UPDATING, downloading = DOWNLOADING, compiling = COMPILING,
+   downloaded = DOWNLOADED,
    dir = p.url()).as_slice()));
 });
 
@@ -599,10 +978,12 @@ test!(git_and_registry_dep {
 {updating} [..]
 {updating} [..]
 {downloading} a v0.0.1 (registry file://[..])
+{downloaded} a v0.0.1 (registry file://[..]) [1/1]
 {compiling} a v0.0.1 (registry [..])
 {compiling} b v0.0.1 ([..])
 {compiling} foo v0.0.1 ({dir})
 ", updating = UPDATING, downloading = DOWNLOADING, compiling = COMPILING,
+   downloaded = DOWNLOADED,
    dir = p.url()).as_slice()));
     p.root().move_into_the_past().unwrap();
 
@@ -635,6 +1016,12 @@ test!(update_publish_then_update {
 
     let lock = p.root().join("Cargo.lock");
     let s = File::open(&lock).unwrap().read_to_string().unwrap();
+    // The recorded checksum belongs to 0.1.0, so drop it along with the rest
+    // of the metadata when pointing the lockfile at 0.1.1.
+    let s = match s.as_slice().find_str("[metadata]") {
+        Some(i) => s.as_slice().slice_to(i).to_string(),
+        None => s,
+    };
     File::create(&lock).unwrap().write_str(s.replace("0.1.0", "0.1.1").as_slice())
                        .unwrap();
     println!("second");
@@ -644,9 +1031,473 @@ test!(update_publish_then_update {
                 execs().with_status(0).with_stdout(format!("\
 {updating} [..]
 {downloading} a v0.1.1 (registry file://[..])
+{downloaded} a v0.1.1 (registry file://[..]) [1/1]
 {compiling} a v0.1.1 (registry [..])
 {compiling} foo v0.5.0 ({dir})
 ", updating = UPDATING, downloading = DOWNLOADING, compiling = COMPILING,
+   downloaded = DOWNLOADED,
    dir = p.url()).as_slice()));
 
 });
+
+test!(lockfile_records_checksums {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+
+    let archive = File::open(&r::mock_archive_dst("bar", "0.0.1"))
+                       .read_to_end().unwrap();
+    let lock = File::open(&p.root().join("Cargo.lock"))
+                    .read_to_string().unwrap();
+    let expected = format!("[metadata]\n\
+                            \"checksum bar 0.0.1 (registry+{})\" = \"{}\"\n",
+                           r::registry(), r::cksum(archive.as_slice()));
+    assert!(lock.as_slice().contains(expected.as_slice()), "{}", lock);
+});
+
+test!(lockfile_checksum_mismatch {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+
+    let archive = File::open(&r::mock_archive_dst("bar", "0.0.1"))
+                       .read_to_end().unwrap();
+    let cksum = r::cksum(archive.as_slice());
+    let lock = p.root().join("Cargo.lock");
+    let s = File::open(&lock).unwrap().read_to_string().unwrap();
+    let other = "0".repeat(cksum.len());
+    File::create(&lock).unwrap()
+         .write_str(s.replace(cksum.as_slice(), other.as_slice()).as_slice())
+         .unwrap();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(101).with_stderr("\
+checksum for `bar v0.0.1 (registry file://[..])` changed between lock files
+
+this could be indicative of a few possible errors:
+
+    * the lock file is corrupt
+    * a replacement source in use (e.g. a mirror) returned a different checksum
+    * the source itself may be corrupt in one way or another
+
+unable to verify that `bar v0.0.1 (registry file://[..])` is the same as when \
+the lockfile was generated
+"));
+});
+
+test!(locked_lockfile_must_not_change {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--locked"),
+                execs().with_status(101).with_stderr("\
+the lock file [..]Cargo.lock needs to be generated but --locked was passed to \
+prevent this
+"));
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--locked"),
+                execs().with_status(0).with_stdout(""));
+
+    r::mock_pkg("bar", "0.0.2", &[]);
+    File::create(&p.root().join("Cargo.toml")).write_str(r#"
+        [project]
+        name = "foo"
+        version = "0.0.1"
+        authors = []
+
+        [dependencies]
+        bar = "0.0.2"
+    "#).unwrap();
+    let lock = p.root().join("Cargo.lock");
+    let before = File::open(&lock).read_to_string().unwrap();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--locked"),
+                execs().with_status(101).with_stderr("\
+the lock file [..]Cargo.lock needs to be updated but --locked was passed to \
+prevent this
+changes:
+  - bar v0.0.1 (registry file://[..])
+  + bar v0.0.2 (registry file://[..])
+"));
+    assert_eq!(File::open(&lock).read_to_string().unwrap(), before);
+});
+
+test!(locked_lockfile_for_every_command {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+    File::create(&p.root().join("Cargo.toml")).write_str(r#"
+        [project]
+        name = "foo"
+        version = "0.0.1"
+        authors = []
+    "#).unwrap();
+    let lock = p.root().join("Cargo.lock");
+    let before = File::open(&lock).read_to_string().unwrap();
+
+    for cmd in ["metadata", "tree", "vendor", "outdated", "update",
+                "generate-lockfile"].iter() {
+        for flag in ["--locked", "--frozen"].iter() {
+            assert_that(p.process(cargo_dir().join("cargo")).arg(*cmd)
+                         .arg(*flag),
+                        execs().with_status(101).with_stderr(format!("\
+the lock file [..]Cargo.lock needs to be updated but {} was passed to \
+prevent this
+changes:
+  - bar v0.0.1 (registry file://[..])
+", flag)));
+            assert_eq!(File::open(&lock).read_to_string().unwrap(), before);
+        }
+    }
+
+    // The lock file is brought along to verify the package.
+    assert_that(p.process(cargo_dir().join("cargo")).arg("package")
+                 .arg("--no-metadata").arg("--frozen"),
+                execs().with_status(101).with_stderr("\
+failed to verify package tarball
+
+Caused by:
+  the lock file [..]Cargo.lock needs to be updated but --frozen was passed to \
+prevent this
+changes:
+  - bar v0.0.1 (registry file://[..])
+"));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("install")
+                 .arg("--path").arg(".").arg("--locked"),
+                execs().with_status(101).with_stderr("\
+failed to compile `foo v0.0.1 ([..])`, intermediate artifacts can be found at \
+`[..]`
+
+Caused by:
+  the lock file [..]Cargo.lock needs to be updated but --locked was passed to \
+prevent this
+changes:
+  - bar v0.0.1 (registry file://[..])
+"));
+    assert_eq!(File::open(&lock).read_to_string().unwrap(), before);
+});
+
+test!(offline_uses_downloaded_index {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+
+    // The new version isn't in the downloaded copy of the index.
+    r::mock_pkg("bar", "0.0.2", &[]);
+    assert_that(p.process(cargo_dir().join("cargo")).arg("update")
+                 .arg("--offline"),
+                execs().with_status(0).with_stdout(""));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--frozen"),
+                execs().with_status(0));
+    let lock = File::open(&p.root().join("Cargo.lock")).read_to_string();
+    assert!(lock.unwrap().contains("0.0.1"));
+
+    File::create(&p.root().join("Cargo.toml")).write_str(r#"
+        [project]
+        name = "foo"
+        version = "0.0.1"
+        authors = []
+
+        [dependencies]
+        bar = "0.0.2"
+    "#).unwrap();
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--offline"),
+                execs().with_status(101).with_stderr("\
+no package named `bar` found (required by `foo`)
+location searched: registry file://[..]
+version required: ^0.0.2
+the downloaded index of `file://[..]` may be out of date, and it can't be \
+updated while offline
+"));
+});
+
+test!(offline_needs_downloaded_packages {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("generate-lockfile"),
+                execs().with_status(0));
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--frozen").arg("-v"),
+                execs().with_status(101).with_stderr("\
+Unable to get packages from source
+
+Caused by:
+  package `bar v0.0.1 (registry file://[..])` needs to be downloaded, which \
+can't be done while offline
+"));
+});
+
+test!(offline_without_index {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    r::mock_pkg("bar", "0.0.1", &[]);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .arg("--offline").arg("-v"),
+                execs().with_status(101).with_stderr("\
+unable to get `bar` from registry file://[..] while offline
+
+Caused by:
+  Unable to update registry file://[..]
+
+Caused by:
+  the index of `file://[..]` has never been downloaded, and can't be while \
+offline
+"));
+});
+
+test!(alt_registry_dependency {
+    r::alt_init();
+    r::mock_alt_pkg("bar", "0.0.1", &[]);
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            version = "0.0.1"
+            registry = "alternative"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `{reg}`
+{downloading} bar v0.0.1 (registry {reg})
+{downloaded} bar v0.0.1 (registry {reg}) [1/1]
+{compiling} bar v0.0.1 (registry {reg})
+{compiling} foo v0.0.1 ({dir})
+", updating = UPDATING, downloading = DOWNLOADING, compiling = COMPILING,
+   downloaded = DOWNLOADED,
+   dir = p.url(), reg = r::alt_registry()).as_slice()));
+
+    let lock = File::open(&p.root().join("Cargo.lock")).read_to_string()
+                    .unwrap();
+    assert!(lock.contains(format!("registry+{}", r::alt_registry()).as_slice()),
+            "{}", lock);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(""));
+});
+
+test!(alt_registry_and_central_registry {
+    r::alt_init();
+    r::mock_alt_pkg("bar", "0.0.1", &[]);
+    r::mock_pkg("bar", "0.0.2", &[]);
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            version = "0.0.1"
+            registry = "alternative"
+            [build-dependencies.bar]
+            version = "0.0.2"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("fetch"), execs().with_status(0));
+
+    let lock = File::open(&p.root().join("Cargo.lock")).read_to_string()
+                    .unwrap();
+    assert!(lock.contains(format!("registry+{}", r::alt_registry()).as_slice()),
+            "{}", lock);
+    assert!(lock.contains(format!("registry+{}", r::registry()).as_slice()),
+            "{}", lock);
+});
+
+test!(alt_registry_package_depending_on_central_registry {
+    r::alt_init();
+    r::mock_pkg("baz", "0.0.1", &[]);
+    r::mock_alt_pkg_with_central_deps("bar", "0.0.1", &[("baz", "0.0.1",
+                                                         "normal")]);
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            version = "0.0.1"
+            registry = "alternative"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    let lock = File::open(&p.root().join("Cargo.lock")).read_to_string()
+                    .unwrap();
+    assert!(lock.contains(format!("bar 0.0.1 (registry+{})",
+                                  r::alt_registry()).as_slice()), "{}", lock);
+    assert!(lock.contains(format!("baz 0.0.1 (registry+{})",
+                                  r::registry()).as_slice()), "{}", lock);
+});
+
+test!(unknown_alt_registry {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            version = "0.0.1"
+            registry = "nope"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+failed to parse manifest at `[..]`
+Cargo.toml is not a valid manifest
+
+no index found for registry: `nope`
+"));
+});
+
+test!(alt_registry_with_git {
+    r::alt_init();
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            git = "https://example.com/bar"
+            registry = "alternative"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+failed to parse manifest at `[..]`
+Cargo.toml is not a valid manifest
+
+dependency `bar` can't be taken from a registry as well as from `git` or `path`
+"));
+});
+
+test!(login_to_named_registry {
+    r::alt_init();
+    assert_that(process(cargo_dir().join("cargo")).unwrap()
+                       .arg("login").arg("--registry").arg("alternative")
+                       .arg("alt-token")
+                       .cwd(paths::root())
+                       .env("HOME", Some(paths::home())),
+                execs().with_status(0));
+
+    let config = File::open(&paths::home().join(".cargo/config"))
+                      .read_to_string().unwrap();
+    assert!(config.contains("[registries.alternative]"), "{}", config);
+    assert!(config.contains("token = \"alt-token\""), "{}", config);
+    // The token of the default registry is left alone.
+    assert!(config.contains("token = \"api-token\""), "{}", config);
+});
@@ -0,0 +1,255 @@
+use std::io::{self, fs, File};
+
+use support::{project, execs, paths, cargo_dir, ProjectBuilder};
+use support::{UPDATING, DOWNLOADING, DOWNLOADED, COMPILING};
+use support::http::FileServer;
+use support::registry as r;
+
+use hamcrest::assert_that;
+
+fn setup() {
+    r::init();
+}
+
+/// Serves the index of the registry over HTTP, and makes it the index used
+/// for crates.io.
+fn serve_index() -> FileServer {
+    use_index(FileServer::new(&r::registry_path()), "")
+}
+
+/// Makes the index served by `server` the index used for crates.io, with
+/// `extra` appended to the configuration.
+fn use_index(server: FileServer, extra: &str) -> FileServer {
+    let config = paths::home().join(".cargo/config");
+    fs::mkdir_recursive(&config.dir_path(), io::USER_DIR).unwrap();
+    File::create(&config).write_str(format!(r#"
+        [registry]
+            index = "sparse+{}"
+            token = "api-token"
+        {}
+    "#, server.url(), extra).as_slice()).unwrap();
+    server
+}
+
+fn foo() -> ProjectBuilder {
+    project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+}
+
+fn sorted(mut v: Vec<String>) -> Vec<String> {
+    v.sort();
+    v
+}
+
+test!(only_needed_crates_are_fetched {
+    r::mock_pkg("baz", "0.0.1", &[]);
+    r::mock_pkg("bar", "0.0.1", &[("baz", "*", "normal")]);
+    r::mock_pkg("unrelated", "0.0.1", &[]);
+    let server = serve_index();
+    let p = foo();
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `sparse+{reg}`
+{downloading} [..] v0.0.1 (registry sparse+http://[..])
+{downloading} [..] v0.0.1 (registry sparse+http://[..])
+{downloaded} [..] [1/2]
+{downloaded} [..] [2/2]
+{compiling} baz v0.0.1 (registry sparse+http://[..])
+{compiling} bar v0.0.1 (registry sparse+http://[..])
+{compiling} foo v0.0.1 ({dir})
+",
+        updating = UPDATING,
+        downloading = DOWNLOADING, downloaded = DOWNLOADED,
+        compiling = COMPILING,
+        dir = p.url(),
+        reg = server.url()).as_slice()));
+
+    assert_eq!(sorted(server.requests()), vec![
+        "200 /3/b/bar".to_string(),
+        "200 /3/b/baz".to_string(),
+        "200 /config.json".to_string(),
+    ]);
+
+    // Everything needed is in the lock file and the local copy of the index
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0));
+    assert_eq!(server.requests(), Vec::<String>::new());
+});
+
+test!(unchanged_files_are_not_fetched_again {
+    r::mock_pkg("bar", "0.0.1", &[]);
+    let server = serve_index();
+    let p = foo();
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    assert_eq!(sorted(server.requests()), vec![
+        "200 /3/b/bar".to_string(),
+        "200 /config.json".to_string(),
+    ]);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("update"),
+                execs().with_status(0));
+    assert_eq!(sorted(server.requests()), vec![
+        "304 /3/b/bar".to_string(),
+        "304 /config.json".to_string(),
+    ]);
+
+    r::mock_pkg("bar", "0.0.2", &[]);
+    assert_that(p.process(cargo_dir().join("cargo")).arg("update"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `[..]`
+{updating} bar v0.0.1 -> v0.0.2
+", updating = UPDATING).as_slice()));
+    assert_eq!(sorted(server.requests()), vec![
+        "200 /3/b/bar".to_string(),
+        "304 /config.json".to_string(),
+    ]);
+});
+
+test!(missing_crate {
+    let server = serve_index();
+    let p = foo();
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+no package named `bar` found (required by `foo`)
+location searched: registry sparse+http://[..]
+version required: *
+"));
+    assert_eq!(sorted(server.requests()), vec![
+        "200 /config.json".to_string(),
+        "404 /3/b/bar".to_string(),
+    ]);
+});
+
+test!(not_an_index {
+    let server = serve_index();
+    fs::unlink(&r::registry_path().join("config.json")).unwrap();
+    let p = foo();
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(101).with_stderr(format!("\
+Unable to update registry sparse+{url}
+
+Caused by:
+  `{url}` has no `config.json`, it isn't a registry index
+", url = server.url()).as_slice()));
+});
+
+test!(failed_requests_are_retried {
+    r::mock_pkg("bar", "0.0.1", &[]);
+    let server = use_index(FileServer::failing(&r::registry_path(), 2), r#"
+        [net]
+            retry = 2
+    "#);
+    let p = foo();
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr("\
+warning: spurious network error (2 tries remaining): failed to fetch \
+`http://[..]/config.json`, got 503
+warning: spurious network error (1 tries remaining): failed to fetch \
+`http://[..]/config.json`, got 503
+"));
+    assert_eq!(server.requests(), vec![
+        "503 /config.json".to_string(),
+        "503 /config.json".to_string(),
+        "200 /config.json".to_string(),
+        "200 /3/b/bar".to_string(),
+    ]);
+});
+
+test!(retries_run_out {
+    r::mock_pkg("bar", "0.0.1", &[]);
+    let _server = use_index(FileServer::failing(&r::registry_path(), 2), r#"
+        [net]
+            retry = 1
+    "#);
+    let p = foo();
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(101).with_stderr("\
+warning: spurious network error (1 tries remaining): failed to fetch \
+`http://[..]/config.json`, got 503
+Unable to update registry sparse+http://[..]
+
+Caused by:
+  failed to fetch `http://[..]/config.json`, got 503
+"));
+});
+
+test!(stalled_requests_time_out {
+    r::mock_pkg("bar", "0.0.1", &[]);
+    let server = use_index(FileServer::stalling(&r::registry_path(), 1), r#"
+        [http]
+            timeout = 1
+        [net]
+            retry = 1
+    "#);
+    let p = foo();
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr("\
+warning: spurious network error (1 tries remaining): failed to fetch \
+`http://[..]/config.json`
+"));
+    assert_eq!(server.requests(), vec![
+        "stalled /config.json".to_string(),
+        "200 /config.json".to_string(),
+        "200 /3/b/bar".to_string(),
+    ]);
+});
+
+test!(tls_configuration_is_checked {
+    r::mock_pkg("bar", "0.0.1", &[]);
+    let server = use_index(FileServer::new(&r::registry_path()), r#"
+        [http]
+            check-revoke = "yes"
+    "#);
+    let p = foo();
+
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(101).with_stderr("\
+Unable to update registry sparse+http://[..]
+
+Caused by:
+  invalid configuration for key `http.check-revoke`
+
+Caused by:
+  expected a bool, but found a string
+"));
+    assert_eq!(server.requests(), Vec::<String>::new());
+});
+
+test!(failed_downloads_are_retried {
+    r::mock_pkg("bar", "0.0.1", &[]);
+    let dl = FileServer::failing(&paths::root(), 1);
+    File::create(&r::registry_path().join("config.json")).write_str(format!(r#"
+        {{"dl":"{}dl","api":""}}
+    "#, dl.url()).as_slice()).unwrap();
+    let _server = use_index(FileServer::new(&r::registry_path()), r#"
+        [net]
+            retry = 1
+    "#);
+    let p = foo();
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr("\
+warning: spurious network error (1 tries remaining): failed to fetch \
+`http://[..]/dl/bar/0.0.1/download`, got 503
+"));
+    assert_eq!(dl.requests(), vec![
+        "503 /dl/bar/0.0.1/download".to_string(),
+        "200 /dl/bar/0.0.1/download".to_string(),
+    ]);
+});
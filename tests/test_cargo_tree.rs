@@ -0,0 +1,130 @@
+use support::{project, execs, ProjectBuilder, UPDATING, DOWNLOADING};
+use support::DOWNLOADED;
+use support::registry as r;
+use hamcrest::assert_that;
+
+fn setup() {
+    r::init();
+}
+
+fn graph() -> ProjectBuilder {
+    project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+            [dev-dependencies.baz]
+            path = "baz"
+            [build-dependencies.qux]
+            path = "qux"
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.baz]
+            path = "../baz"
+        "#)
+        .file("bar/src/lib.rs", "")
+        .file("baz/Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("baz/src/lib.rs", "")
+        .file("qux/Cargo.toml", r#"
+            [package]
+            name = "qux"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            path = "../bar"
+        "#)
+        .file("qux/src/lib.rs", "")
+}
+
+test!(simple {
+    let p = graph();
+
+    assert_that(p.cargo_process("tree"),
+                execs().with_status(0)
+                       .with_stdout("\
+foo v0.5.0 ([..])
+|-- bar v0.5.0 ([..])
+|   `-- baz v0.5.0 ([..])
+|-- baz v0.5.0 ([..]) (dev)
+`-- qux v0.5.0 ([..]) (build)
+    `-- bar v0.5.0 ([..]) (*)
+"));
+});
+
+test!(invert {
+    let p = graph();
+
+    assert_that(p.cargo_process("tree").arg("--invert").arg("baz"),
+                execs().with_status(0)
+                       .with_stdout("\
+baz v0.5.0 ([..])
+|-- bar v0.5.0 ([..])
+|   |-- foo v0.5.0 ([..])
+|   `-- qux v0.5.0 ([..])
+|       `-- foo v0.5.0 ([..]) (build)
+`-- foo v0.5.0 ([..]) (dev)
+"));
+});
+
+test!(invert_unknown_spec {
+    let p = graph();
+
+    assert_that(p.cargo_process("tree").arg("-i").arg("nope"),
+                execs().with_status(101)
+                       .with_stderr("\
+package id specification `nope` matched no packages
+"));
+});
+
+test!(duplicates {
+    r::mock_pkg("a", "0.1.0", &[]);
+    r::mock_pkg("a", "0.2.0", &[]);
+    r::mock_pkg("b", "0.1.0", &[("a", "0.2.0", "normal")]);
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            a = "0.1.0"
+            b = "0.1.0"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("tree").arg("--duplicates"),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{updating} registry `[..]`
+{downloading} [..]
+{downloading} [..]
+{downloading} [..]
+{downloaded} [..] [1/3]
+{downloaded} [..] [2/3]
+{downloaded} [..] [3/3]
+a v0.1.0 (registry file://[..])
+`-- foo v0.5.0 ([..])
+
+a v0.2.0 (registry file://[..])
+`-- b v0.1.0 (registry file://[..])
+    `-- foo v0.5.0 ([..])
+", updating = UPDATING, downloading = DOWNLOADING, downloaded = DOWNLOADED)));
+});
@@ -0,0 +1,350 @@
+use std::io::{self, fs, File};
+
+use support::{project, execs, cargo_dir, ProjectBuilder};
+use support::{UPDATING, DOWNLOADING, DOWNLOADED, COMPILING, VENDORING};
+use support::paths;
+use support::registry as r;
+use support::git;
+
+use hamcrest::{assert_that, existing_file};
+
+fn setup() {
+    r::init();
+}
+
+fn registry_project() -> ProjectBuilder {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = ">= 0.0.0"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    r::mock_pkg("bar", "0.0.1", &[]);
+    p
+}
+
+fn use_vendored_sources(p: &ProjectBuilder, config: &str) {
+    let path = p.root().join(".cargo/config");
+    fs::mkdir_recursive(&path.dir_path(), io::USER_DIR).unwrap();
+    File::create(&path).write_str(config).unwrap();
+}
+
+test!(vendor_registry_dependency {
+    let p = registry_project();
+
+    assert_that(p.cargo_process("vendor"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} registry `{reg}`
+{downloading} bar v0.0.1 (registry file://[..])
+{downloaded} bar v0.0.1 (registry file://[..]) [1/1]
+{vendoring} bar v0.0.1 (registry file://[..])
+To use vendored sources, add this to your .cargo/config for this project:
+
+[source.crates-io]
+replace-with = \"vendored-sources\"
+
+[source.vendored-sources]
+directory = \"vendor\"
+",
+        updating = UPDATING,
+        downloading = DOWNLOADING, downloaded = DOWNLOADED,
+        vendoring = VENDORING,
+        reg = r::registry()).as_slice()));
+
+    let dir = p.root().join("vendor/bar-0.0.1");
+    assert_that(&dir.join("Cargo.toml"), existing_file());
+    assert_that(&dir.join("src/lib.rs"), existing_file());
+    assert_that(&dir.join(".cargo-checksum.json"), existing_file());
+});
+
+test!(build_from_vendored_registry_offline {
+    let p = registry_project();
+    assert_that(p.cargo_process("vendor"), execs().with_status(0));
+    use_vendored_sources(&p, r#"
+        [source.crates-io]
+        replace-with = "vendored-sources"
+
+        [source.vendored-sources]
+        directory = "vendor"
+    "#);
+
+    // Nothing but the vendored copies is left to build from.
+    fs::rmdir_recursive(&r::registry_path()).unwrap();
+    fs::rmdir_recursive(&r::dl_path()).unwrap();
+    fs::rmdir_recursive(&paths::home().join(".cargo/registry")).unwrap();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} bar v0.0.1 (registry file://[..])
+{compiling} foo v0.0.1 ({dir})
+",
+        compiling = COMPILING,
+        dir = p.url()).as_slice()));
+});
+
+test!(vendored_file_modified {
+    let p = registry_project();
+    assert_that(p.cargo_process("vendor"), execs().with_status(0));
+    use_vendored_sources(&p, r#"
+        [source.crates-io]
+        replace-with = "vendored-sources"
+
+        [source.vendored-sources]
+        directory = "vendor"
+    "#);
+    File::create(&p.root().join("vendor/bar-0.0.1/src/lib.rs"))
+         .write_str("pub fn edited() {}").unwrap();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build").arg("-v"),
+                execs().with_status(101).with_stderr("\
+Unable to get packages from source
+
+Caused by:
+  the listed checksum of `[..]lib.rs` has changed:
+expected: [..]
+actual:   [..]
+
+directory sources are not intended to be edited, if modifications are \
+required then a copy of the package should be listed in `paths` instead
+"));
+});
+
+test!(vendor_git_dependency {
+    let git = git::repo(&paths::root().join("bar"))
+        .file("Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "pub fn bar() {}");
+    git.build();
+
+    let p = project("foo")
+        .file("Cargo.toml", format!(r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            git = '{}'
+        "#, git.url()))
+        .file("src/main.rs", "extern crate bar; fn main() { bar::bar(); }");
+
+    assert_that(p.cargo_process("vendor").arg("third-party"),
+                execs().with_status(0).with_stdout(format!("\
+{updating} git repository `{url}`
+{vendoring} bar v0.5.0 ({url}#[..])
+To use vendored sources, add this to your .cargo/config for this project:
+
+[source.\"{url}\"]
+git = \"{url}\"
+replace-with = \"vendored-sources\"
+
+[source.vendored-sources]
+directory = \"third-party\"
+",
+        updating = UPDATING,
+        vendoring = VENDORING,
+        url = git.url()).as_slice()));
+
+    use_vendored_sources(&p, format!(r#"
+        [source."{url}"]
+        git = "{url}"
+        replace-with = "vendored-sources"
+
+        [source.vendored-sources]
+        directory = "third-party"
+    "#, url = git.url()).as_slice());
+    fs::rmdir_recursive(&paths::root().join("bar")).unwrap();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} bar v0.5.0 ({url}#[..])
+{compiling} foo v0.0.1 ({dir})
+",
+        compiling = COMPILING,
+        url = git.url(),
+        dir = p.url()).as_slice()));
+});
+
+test!(replace_with_unknown_source {
+    let p = registry_project();
+    p.build();
+    use_vendored_sources(&p, r#"
+        [source.crates-io]
+        replace-with = "missing"
+    "#);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(101).with_stderr("\
+could not find a configured source with the name `missing` when attempting \
+to lookup `crates-io` (configuration in `[..]config`)
+"));
+});
+
+test!(replace_with_cycle {
+    let p = registry_project();
+    p.build();
+    use_vendored_sources(&p, r#"
+        [source.crates-io]
+        replace-with = "one"
+
+        [source.one]
+        directory = "vendor"
+        replace-with = "crates-io"
+    "#);
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(101).with_stderr("\
+detected a cycle of `replace-with` sources, the source `crates-io` is \
+eventually replaced with itself (configuration in `[..]config`)
+"));
+});
+
+test!(vendored_package_checksum_mismatch {
+    let p = registry_project();
+    assert_that(p.cargo_process("vendor"), execs().with_status(0));
+    use_vendored_sources(&p, r#"
+        [source.crates-io]
+        replace-with = "vendored-sources"
+
+        [source.vendored-sources]
+        directory = "vendor"
+    "#);
+
+    // Pretend the vendored copy was unpacked from a different `.crate` than
+    // the one recorded in the lockfile.
+    let archive = File::open(&r::mock_archive_dst("bar", "0.0.1"))
+                       .read_to_end().unwrap();
+    let cksum = r::cksum(archive.as_slice());
+    let path = p.root().join("vendor/bar-0.0.1/.cargo-checksum.json");
+    let s = File::open(&path).read_to_string().unwrap();
+    assert!(s.as_slice().contains(cksum.as_slice()), "{}", s);
+    let other = "0".repeat(cksum.len());
+    File::create(&path)
+         .write_str(s.replace(cksum.as_slice(), other.as_slice()).as_slice())
+         .unwrap();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(101).with_stderr("\
+checksum for `bar v0.0.1 (registry file://[..])` changed between lock files
+
+this could be indicative of a few possible errors:
+
+    * the lock file is corrupt
+    * a replacement source in use (e.g. a mirror) returned a different checksum
+    * the source itself may be corrupt in one way or another
+
+unable to verify that `bar v0.0.1 (registry file://[..])` is the same as when \
+the lockfile was generated
+"));
+});
+
+test!(vendor_git_dependency_with_path_dependency {
+    let git = git::repo(&paths::root().join("bar"))
+        .file("Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.baz]
+            path = "baz"
+        "#)
+        .file("src/lib.rs", "extern crate baz; pub fn bar() { baz::baz() }")
+        .file("baz/Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.5.0"
+            authors = []
+        "#)
+        .file("baz/src/lib.rs", "pub fn baz() {}");
+    git.build();
+
+    let p = project("foo")
+        .file("Cargo.toml", format!(r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            git = '{}'
+        "#, git.url()))
+        .file("src/main.rs", "extern crate bar; fn main() { bar::bar(); }");
+
+    assert_that(p.cargo_process("vendor"), execs().with_status(0));
+    use_vendored_sources(&p, format!(r#"
+        [source."{url}"]
+        git = "{url}"
+        replace-with = "vendored-sources"
+
+        [source.vendored-sources]
+        directory = "vendor"
+    "#, url = git.url()).as_slice());
+    fs::rmdir_recursive(&paths::root().join("bar")).unwrap();
+
+    // `baz` is taken from the vendored copy of the git repository, not from
+    // crates.io
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build"),
+                execs().with_status(0).with_stdout(format!("\
+{compiling} baz v0.5.0 ({url}#[..])
+{compiling} bar v0.5.0 ({url}#[..])
+{compiling} foo v0.0.1 ({dir})
+",
+        compiling = COMPILING,
+        url = git.url(),
+        dir = p.url()).as_slice()));
+});
+
+test!(vendor_same_package_from_two_sources {
+    r::mock_pkg("bar", "0.0.1", &[]);
+    let git = git::repo(&paths::root().join("baz"))
+        .file("Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+    git.build();
+
+    let p = project("foo")
+        .file("Cargo.toml", format!(r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+
+            [dependencies.baz]
+            git = '{}'
+        "#, git.url()))
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("vendor"),
+                execs().with_status(101).with_stderr("\
+`bar v0.0.1 ([..])` and `bar v0.0.1 ([..])` would both be vendored to \
+`[..]bar-0.0.1`, packages of the same name and version from different \
+sources can't be vendored together
+"));
+});
@@ -0,0 +1,198 @@
+use std::io::File;
+use std::io::fs::PathExtensions;
+
+use support::{project, execs, cargo_dir};
+use support::{COMPILING, RUNNING};
+use hamcrest::{assert_that, existing_file, existing_dir, is_not};
+
+fn setup() {
+}
+
+test!(simple_explicit {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+
+            [workspace]
+            members = ["bar"]
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("bar/src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    assert_that(&p.bin("foo"), existing_file());
+    assert_that(&p.bin("bar"), existing_file());
+
+    assert_that(&p.root().join("Cargo.lock"), existing_file());
+    assert_that(&p.root().join("bar/Cargo.lock"), is_not(existing_file()));
+    assert_that(&p.root().join("bar/target"), is_not(existing_dir()));
+});
+
+test!(member_shares_root_lockfile_and_target {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+
+            [workspace]
+            members = ["bar"]
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("bar/src/main.rs", "fn main() {}");
+    p.build();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("build")
+                 .cwd(p.root().join("bar")),
+                execs().with_status(0)
+                       .with_stdout(format!("{} bar v0.1.0 ({})\n",
+                                            COMPILING, p.url())));
+    assert_that(&p.bin("bar"), existing_file());
+    assert_that(&p.bin("foo"), is_not(existing_file()));
+
+    assert_that(&p.root().join("Cargo.lock"), existing_file());
+    assert_that(&p.root().join("bar/Cargo.lock"), is_not(existing_file()));
+    assert_that(&p.root().join("bar/target"), is_not(existing_dir()));
+});
+
+test!(lockfile_contains_all_members {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+
+            [workspace]
+            members = ["bar", "baz"]
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies.baz]
+            path = "../baz"
+        "#)
+        .file("bar/src/lib.rs", "extern crate baz;")
+        .file("baz/Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("baz/src/lib.rs", "");
+
+    assert_that(p.cargo_process("generate-lockfile"), execs().with_status(0));
+
+    let lockfile = File::open(&p.root().join("Cargo.lock")).read_to_string()
+                                                           .unwrap();
+    assert!(lockfile.as_slice().contains("name = \"bar\""), "{}", lockfile);
+    assert!(lockfile.as_slice().contains("name = \"baz\""), "{}", lockfile);
+});
+
+test!(test_member_from_its_directory {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+
+            [workspace]
+            members = ["bar"]
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+
+            [lib]
+            name = "bar"
+            doctest = false
+        "#)
+        .file("bar/src/lib.rs", r#"
+            #[test]
+            fn bar_test() {}
+        "#);
+    p.build();
+
+    assert_that(p.process(cargo_dir().join("cargo")).arg("test")
+                 .cwd(p.root().join("bar")),
+                execs().with_status(0)
+                       .with_stdout(format!("\
+{compiling} bar v0.1.0 ({dir})
+{running} [..]target[..]bar-[..]
+
+running 1 test
+test bar_test ... ok
+
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured\n
+", compiling = COMPILING, running = RUNNING,
+   dir = p.url()).as_slice()));
+    assert_that(&p.root().join("bar/target"), is_not(existing_dir()));
+});
+
+test!(missing_member {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+
+            [workspace]
+            members = ["bar"]
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101)
+                       .with_stderr(format!("workspace member `bar` listed in \
+                                             `{}` does not contain a \
+                                             Cargo.toml\n",
+                                            p.root().join("Cargo.toml")
+                                             .display())));
+});
+
+test!(invalid_members {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+
+            [workspace]
+            members = "bar"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101)
+                       .with_stderr(format!("`workspace.members` in `{}` \
+                                             must be an array of strings\n",
+                                            p.root().join("Cargo.toml")
+                                             .display())));
+    assert!(!p.root().join("target").exists());
+});
@@ -26,6 +26,7 @@ macro_rules! test {
 }
 
 mod test_cargo;
+mod test_cargo_add;
 mod test_cargo_bench;
 mod test_cargo_build_auth;
 mod test_cargo_build_lib;
@@ -42,13 +43,22 @@ mod test_cargo_features;
 mod test_cargo_fetch;
 mod test_cargo_freshness;
 mod test_cargo_generate_lockfile;
+mod test_cargo_install;
+mod test_cargo_local_registry;
+mod test_cargo_message_format;
+mod test_cargo_metadata;
 mod test_cargo_new;
+mod test_cargo_outdated;
 mod test_cargo_package;
 mod test_cargo_profiles;
 mod test_cargo_publish;
 mod test_cargo_registry;
 mod test_cargo_run;
 mod test_cargo_search;
+mod test_cargo_sparse_registry;
 mod test_cargo_test;
+mod test_cargo_tree;
+mod test_cargo_vendor;
 mod test_cargo_version;
+mod test_cargo_workspaces;
 mod test_shell;